@@ -1,5 +1,7 @@
 use std::{mem, ops};
 
+use crate::vec3::Vec3;
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
 pub struct ColorRGB(u8, u8, u8);
 
@@ -119,6 +121,14 @@ impl ops::Mul<ColorRGBMapTo0_1> for f32 {
     }
 }
 
+impl ops::Mul<Vec3> for ColorRGBMapTo0_1 {
+    type Output = Self;
+
+    fn mul(self, rhs: Vec3) -> Self::Output {
+        Self(self.0 * rhs.0, self.1 * rhs.1, self.2 * rhs.2)
+    }
+}
+
 impl From<ColorRGB> for ColorRGBMapTo0_1 {
     fn from(color: ColorRGB) -> Self {
         Self(
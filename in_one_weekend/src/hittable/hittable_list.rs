@@ -24,7 +24,7 @@ impl<H: AsRef<dyn Hittable>> HittableList<H> {
 }
 
 impl<H: AsRef<dyn Hittable> + Send + Sync> Hittable for HittableList<H> {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
         self.objects
         .iter()
         .filter_map(|object| object.as_ref().hit(ray, t_min, t_max))
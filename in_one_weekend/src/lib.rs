@@ -1,6 +1,8 @@
 pub mod color;
 pub mod image;
 pub mod point;
+pub mod quaternion;
 pub mod thread_pool;
+pub mod transform;
 pub mod utils;
 pub mod vec3;
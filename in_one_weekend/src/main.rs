@@ -5,7 +5,6 @@ mod image;
 mod material;
 mod point;
 mod ray;
-mod thread_pool;
 mod utils;
 mod vec3;
 
@@ -13,11 +12,7 @@ extern crate num_cpus;
 use humantime::format_duration;
 use rand::{random, rngs::ThreadRng, Rng};
 
-use std::{
-    f32,
-    sync::{Arc, Mutex},
-    time,
-};
+use std::{f32, sync::Arc, time};
 
 use crate::{
     camera::Camera,
@@ -27,9 +22,9 @@ use crate::{
     material::{Attenuation, Dielectric, Lambertian, Material, Metal},
     point::Point3,
     ray::Ray,
-    thread_pool::ThreadPool,
     vec3::Vec3,
 };
+use in_one_weekend::thread_pool::{JoinHandle, ThreadPool};
 
 const ASPECT_RATIO: f32 = 3.0 / 2.0;
 
@@ -44,12 +39,9 @@ fn main() -> std::io::Result<()> {
     const IMAGE_HEIGHT: usize = (IMAGE_WIDTH as f32 / ASPECT_RATIO) as usize;
     const SAMPLES_PER_PIXEL: usize = 500;
     const OUTPUT_IMAGE_PATH: &str = "./target/image.ppm";
-    let image: Arc<Mutex<_>> = Arc::new(Mutex::new(PPMImg::<IMAGE_WIDTH, IMAGE_HEIGHT>::new(
-        PPMImgMagicNum::P3,
-    )));
 
     // World
-    let world: Arc<HittableList<Box<dyn Hittable>>> = Arc::new(self::random_scene());
+    let world: HittableList<Box<dyn Hittable>> = self::random_scene();
 
     // Camera
     let look_from: Point3 = Point3::new(13.0, 2.0, 3.0);
@@ -57,7 +49,7 @@ fn main() -> std::io::Result<()> {
     let disk_to_focus: f32 = 10.0;
     const APERTURE: f32 = 0.1;
 
-    let camera: Arc<Camera> = Arc::new(Camera::new(
+    let camera: Camera = Camera::new(
         look_from,
         look_at,
         Vec3::new(0.0, 1.0, 0.0),
@@ -65,78 +57,54 @@ fn main() -> std::io::Result<()> {
         ASPECT_RATIO,
         APERTURE,
         disk_to_focus,
-    ));
+    );
 
     let time_render_start: time::Instant = time::Instant::now();
 
-    // Render
+    // Render, a job per row borrowing the world and the camera.
     const MAX_DEPTH_RAY_RECURSION: u16 = 50;
-    let num_pixels_has_rendered: Arc<Mutex<usize>> = Arc::new(Mutex::new(0usize));
-
-    (0..IMAGE_HEIGHT).for_each(|row| {
-        (0..IMAGE_WIDTH).for_each(|column| {
-            let image = Arc::clone(&image);
-            let world = Arc::clone(&world);
-            let camera = Arc::clone(&camera);
-            let num_pixels_has_rendered = Arc::clone(&num_pixels_has_rendered);
-
-            thread_pool.execute(move || {
-                let pixel_color: ColorRGB = self::pixel_color::<
-                    IMAGE_HEIGHT,
-                    IMAGE_WIDTH,
-                    SAMPLES_PER_PIXEL,
-                    MAX_DEPTH_RAY_RECURSION,
-                >(row, column, world.as_ref(), &camera);
-
-                image
-                    .lock()
-                    .unwrap()
-                    .set_pixel_color(row, column, pixel_color);
-
-                if let Ok(mut num) = num_pixels_has_rendered.lock() {
-                    *num += 1;
-                    if *num % (IMAGE_HEIGHT * IMAGE_WIDTH / 1000) == 0 {
-                        utils::log_progress(*num as f64 / (IMAGE_HEIGHT * IMAGE_WIDTH) as f64)
-                            .unwrap();
-                    }
-                };
+    let mut image = PPMImg::<IMAGE_WIDTH, IMAGE_HEIGHT>::new(PPMImgMagicNum::P3);
+
+    thread_pool.scope(|scope| {
+        let rows: Vec<JoinHandle<Vec<ColorRGB>>> = (0..IMAGE_HEIGHT)
+            .map(|row| {
+                let (world, camera) = (&world, &camera);
+                scope.spawn(move || {
+                    (0..IMAGE_WIDTH)
+                        .map(|column| {
+                            self::pixel_color::<
+                                IMAGE_HEIGHT,
+                                IMAGE_WIDTH,
+                                SAMPLES_PER_PIXEL,
+                                MAX_DEPTH_RAY_RECURSION,
+                            >(row, column, world, camera)
+                        })
+                        .collect()
+                })
             })
+            .collect();
+
+        rows.into_iter().enumerate().for_each(|(row, colors)| {
+            colors
+                .join()
+                .unwrap_or_else(|panic| panic!("{panic}"))
+                .into_iter()
+                .enumerate()
+                .for_each(|(column, color)| image.set_pixel_color(row, column, color));
+            utils::log_progress((row + 1) as f64 / IMAGE_HEIGHT as f64).unwrap();
         });
     });
 
-    {
-        let num_pixels_has_rendered = Arc::clone(&num_pixels_has_rendered);
-        thread_pool.execute(move || {
-            while let Ok(num_mutex_guard) = num_pixels_has_rendered.lock() {
-                if *num_mutex_guard == IMAGE_HEIGHT * IMAGE_WIDTH {
-                    break;
-                }
-            }
-
-            eprintln!(
-                "The render took {}",
-                format_duration(time_render_start.elapsed())
-            );
-        });
-    }
-
-    thread_pool.execute(move || {
-        while let Ok(num_mutex_guard) = num_pixels_has_rendered.lock() {
-            if *num_mutex_guard == IMAGE_HEIGHT * IMAGE_WIDTH {
-                break;
-            }
-        }
+    eprintln!(
+        "The render took {}",
+        format_duration(time_render_start.elapsed())
+    );
 
-        image
-            .lock()
-            .map(|img_mutex_guard| {
-                eprintln!("Writing image to file···");
-                img_mutex_guard.write_to_file(OUTPUT_IMAGE_PATH)
-            })
-            .unwrap_or_else(|p_err| p_err.into_inner().write_to_file(OUTPUT_IMAGE_PATH))
-            .map(|_| eprintln!("Writing image to file done!"))
-            .unwrap_or_else(|err| eprintln!("Writing image to file failed! {}", err))
-    });
+    eprintln!("Writing image to file···");
+    image
+        .write_to_file(OUTPUT_IMAGE_PATH)
+        .map(|_| eprintln!("Writing image to file done!"))
+        .unwrap_or_else(|err| eprintln!("Writing image to file failed! {}", err));
 
     Ok(())
 }
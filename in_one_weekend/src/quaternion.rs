@@ -0,0 +1,176 @@
+use std::ops;
+
+use crate::{
+    transform::{Mat4, Transform},
+    vec3::Vec3,
+};
+
+/// A unit quaternion `w + xi + yj + zk` representing a rotation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quaternion {
+    w: f32,
+    v: Vec3,
+}
+
+impl Quaternion {
+    pub fn identity() -> Self {
+        Self {
+            w: 1.0,
+            v: Vec3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    /// Counterclockwise rotation around `axis` looking from its tip towards the
+    /// origin, like `Transform::rotate_axis_angle`.
+    pub fn from_axis_angle(axis: Vec3, radians: f32) -> Self {
+        let (sin, cos): (f32, f32) = (radians / 2.0).sin_cos();
+
+        Self {
+            w: cos,
+            v: sin * axis.unit_vector(),
+        }
+    }
+
+    #[inline]
+    pub fn w(&self) -> f32 {
+        self.w
+    }
+
+    #[inline]
+    pub fn v(&self) -> Vec3 {
+        self.v
+    }
+
+    pub fn dot(&self, other: &Self) -> f32 {
+        self.w * other.w + self.v.dot(other.v)
+    }
+
+    pub fn normalize(&self) -> Self {
+        let len: f32 = self.dot(self).sqrt();
+
+        Self {
+            w: self.w / len,
+            v: self.v / len,
+        }
+    }
+
+    /// Angle in radians of the rotation that takes `self` to `other`.
+    pub fn angle_to(&self, other: &Self) -> f32 {
+        2.0 * self.dot(other).abs().min(1.0).acos()
+    }
+
+    /// Spherical linear interpolation along the shorter arc, `t` in [0, 1].
+    pub fn slerp(&self, other: &Self, t: f32) -> Self {
+        let mut cos_theta: f32 = self.dot(other);
+        let other: Self = match cos_theta < 0.0 {
+            true => {
+                cos_theta = -cos_theta;
+                Self {
+                    w: -other.w,
+                    v: -other.v,
+                }
+            }
+            false => *other,
+        };
+
+        // Nearly parallel quaternions: fall back to normalised lerp.
+        if 0.9995 < cos_theta {
+            return Self {
+                w: self.w + t * (other.w - self.w),
+                v: self.v + t * (other.v - self.v),
+            }
+            .normalize();
+        }
+
+        let theta: f32 = cos_theta.acos();
+        let sin_theta: f32 = theta.sin();
+        let [a, b]: [f32; 2] = [
+            ((1.0 - t) * theta).sin() / sin_theta,
+            (t * theta).sin() / sin_theta,
+        ];
+
+        Self {
+            w: a * self.w + b * other.w,
+            v: a * self.v + b * other.v,
+        }
+    }
+
+    pub fn to_transform(&self) -> Transform {
+        let Self { w, v } = self.normalize();
+        let (x, y, z): (f32, f32, f32) = (v.x(), v.y(), v.z());
+
+        let matrix = Mat4([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - z * w),
+                2.0 * (x * z + y * w),
+                0.0,
+            ],
+            [
+                2.0 * (x * y + z * w),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - x * w),
+                0.0,
+            ],
+            [
+                2.0 * (x * z - y * w),
+                2.0 * (y * z + x * w),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        Transform::from_rotation_matrix(matrix)
+    }
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+/// Hamilton product: `a * b` rotates by `b` first, then by `a`.
+impl ops::Mul for Quaternion {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self {
+            w: self.w * rhs.w - self.v.dot(rhs.v),
+            v: self.w * rhs.v + rhs.w * self.v + self.v.cross(rhs.v),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quaternion_should_match_axis_angle_rotations() {
+        let axis: Vec3 = Vec3::new(1.0, 2.0, -0.5);
+        let q: Quaternion = Quaternion::from_axis_angle(axis, 1.1);
+        let p: Vec3 = Vec3::new(0.3, -2.0, 4.0);
+        let expected: Vec3 = Transform::rotate_axis_angle(axis, 1.1).point(p);
+        assert!((q.to_transform().point(p) - expected).len() < 1e-5);
+
+        let composed: Quaternion = Quaternion::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), 0.4) * q;
+        let expected: Vec3 = Transform::rotate_y(0.4).point(expected);
+        assert!((composed.to_transform().point(p) - expected).len() < 1e-5);
+    }
+
+    #[test]
+    fn slerp_should_interpolate_the_angle() {
+        let y: Vec3 = Vec3::new(0.0, 1.0, 0.0);
+        let [a, b]: [Quaternion; 2] =
+            [0.0, 90_f32.to_radians()].map(|r| Quaternion::from_axis_angle(y, r));
+        let half: Quaternion = a.slerp(&b, 0.5);
+
+        let expected: Vec3 =
+            Transform::rotate_y(45_f32.to_radians()).point(Vec3::new(1.0, 0.0, 0.0));
+        assert!((half.to_transform().point(Vec3::new(1.0, 0.0, 0.0)) - expected).len() < 1e-5);
+        assert!((a.angle_to(&half) - 45_f32.to_radians()).abs() < 1e-4);
+        assert!(1.0 - a.dot(&a.slerp(&b, 0.0)) < 1e-6);
+    }
+}
@@ -1,46 +1,160 @@
 use std::{
-    sync::{mpsc, Arc, Mutex},
+    any::Any,
+    cell::Cell,
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    marker::PhantomData,
+    mem,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc, Condvar, Mutex,
+    },
     thread,
+    time::{Duration, Instant},
 };
 
-type Job = Box<dyn FnOnce() + Send + 'static>;
+type Run = Box<dyn FnOnce() + Send + 'static>;
 
+struct Job {
+    id: usize,
+    run: Run,
+}
+
+thread_local! {
+    /// The index of the worker running on this thread, if it is one.
+    static WORKER: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// A pool of worker threads, each with a queue of its own.
+///
+/// Jobs are dealt out to the queues in turn. A worker takes jobs from the
+/// front of its own queue and, once that is empty, steals from the back of the
+/// others, so that a worker stuck on a slow job does not hold up the ones
+/// queued behind it.
+///
+/// A job that panics does not take its worker down: the panic is caught,
+/// reported with the job and the worker, and kept for `panics`.
 pub struct ThreadPool {
     workers: Vec<Worker>,
-    sender: Option<mpsc::Sender<Job>>,
+    shared: Arc<Shared>,
 }
 
 impl ThreadPool {
     pub fn new(size: usize) -> Self {
         assert!(size > 0);
 
-        let (sender, receiver) = mpsc::channel();
-
-        let receiver = Arc::new(Mutex::new(receiver));
+        let shared: Arc<Shared> = Arc::new(Shared {
+            queues: (0..size).map(|_| Mutex::default()).collect(),
+            queued: Mutex::new(0),
+            has_job: Condvar::new(),
+            shutdown: AtomicBool::new(false),
+            next_queue: AtomicUsize::new(0),
+            next_job: AtomicUsize::new(0),
+            pending: Pending::default(),
+            stats: (0..size).map(|_| Mutex::default()).collect(),
+            panics: Mutex::default(),
+        });
 
         let workers: Vec<Worker> = (0..size)
-            .map(|id| Worker::new(id, Arc::clone(&receiver)))
+            .map(|id| Worker::new(id, Arc::clone(&shared)))
             .collect();
 
-        Self {
-            workers,
-            sender: Some(sender),
-        }
+        Self { workers, shared }
     }
 
     pub fn execute<F>(&self, f: F)
     where
         F: FnOnce() + Send + 'static,
     {
-        let job = Box::new(f);
+        let id: usize = self.shared.next_job();
+        self.send(id, Box::new(f));
+    }
+
+    /// Run `f` on the pool and return a handle to wait for what it returns.
+    pub fn spawn<F, T>(&self, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let id: usize = self.shared.next_job();
+        let (run, handle) = returning(&self.shared, id, f);
+        self.send(id, Box::new(run));
+
+        handle
+    }
+
+    /// Block until every job given to the pool so far has finished.
+    pub fn wait_all(&self) {
+        self.shared.pending.wait();
+    }
+
+    /// Run `f` with a `Scope` whose jobs may borrow from the caller, and wait
+    /// for all of them to finish before returning, even if `f` panics.
+    pub fn scope<'env, F, T>(&self, f: F) -> T
+    where
+        F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> T,
+    {
+        let scope: Scope = Scope {
+            pool: self,
+            pending: Arc::default(),
+            scope: PhantomData,
+            env: PhantomData,
+        };
+        let _wait: WaitOnDrop = WaitOnDrop(Arc::clone(&scope.pending));
+
+        f(&scope)
+    }
+
+    /// The number of worker threads.
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
 
-        self.sender.as_ref().unwrap().send(job).unwrap();
+    /// What each worker has done so far, in the order of the workers.
+    pub fn stats(&self) -> Vec<WorkerStats> {
+        self.shared
+            .stats
+            .iter()
+            .map(|stats| *stats.lock().unwrap())
+            .collect()
+    }
+
+    /// Every job that has panicked so far.
+    pub fn panics(&self) -> Vec<JobPanic> {
+        self.shared.panics.lock().unwrap().clone()
+    }
+
+    /// The index of the worker running the current thread, in the order of
+    /// `stats`, or `None` if the thread is not a worker of a pool.
+    pub fn current_worker() -> Option<usize> {
+        WORKER.with(Cell::get)
+    }
+
+    fn send(&self, id: usize, run: Run) {
+        let shared: &Shared = &self.shared;
+        shared.pending.add();
+
+        // Counted before it is queued, so that it is never taken off the
+        // queue before it is counted.
+        *shared.queued.lock().unwrap() += 1;
+        let queue: usize = shared.next_queue.fetch_add(1, Ordering::Relaxed) % shared.queues.len();
+        shared.queues[queue]
+            .lock()
+            .unwrap()
+            .push_back(Job { id, run });
+        shared.has_job.notify_one();
     }
 }
 
 impl Drop for ThreadPool {
     fn drop(&mut self) {
-        std::mem::drop(self.sender.take());
+        {
+            let _queued = self.shared.queued.lock().unwrap();
+            self.shared.shutdown.store(true, Ordering::Relaxed);
+        }
+        self.shared.has_job.notify_all();
 
         self.workers.iter_mut().for_each(|worker| {
             if let Some(thread) = worker.thread.take() {
@@ -53,6 +167,242 @@ impl Drop for ThreadPool {
     }
 }
 
+/// What a job given to `ThreadPool::spawn` or `Scope::spawn` returns, once it
+/// has finished.
+pub struct JoinHandle<T> {
+    receiver: mpsc::Receiver<Result<T, JobPanic>>,
+}
+
+impl<T> JoinHandle<T> {
+    /// Wait for the job to finish and take its result, or how it panicked.
+    pub fn join(self) -> Result<T, JobPanic> {
+        self.receiver
+            .recv()
+            .expect("a job always sends its result before it is done")
+    }
+}
+
+/// Jobs that may borrow from the caller of `ThreadPool::scope` for `'env`.
+pub struct Scope<'scope, 'env: 'scope> {
+    pool: &'scope ThreadPool,
+    pending: Arc<Pending>,
+    scope: PhantomData<&'scope mut &'scope ()>,
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+impl<'scope, 'env> Scope<'scope, 'env> {
+    /// Run `f` on the pool and return a handle to wait for what it returns.
+    pub fn spawn<F, T>(&'scope self, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'scope,
+        T: Send + 'scope,
+    {
+        let id: usize = self.pool.shared.next_job();
+        let (run, handle) = returning(&self.pool.shared, id, f);
+        let pending: Arc<Pending> = Arc::clone(&self.pending);
+        pending.add();
+        let run: Box<dyn FnOnce() + Send + 'scope> = Box::new(move || {
+            // Declared first to be dropped last, once nothing of the job is
+            // left to touch what it borrows.
+            let _done: Done = Done(pending);
+            run()
+        });
+
+        // SAFETY: `ThreadPool::scope` does not return before `pending` drops
+        // back to zero, so the job is over while everything it borrows for
+        // `'scope` is still alive.
+        let run: Run = unsafe { mem::transmute::<Box<dyn FnOnce() + Send + 'scope>, Run>(run) };
+        self.pool.send(id, run);
+
+        handle
+    }
+}
+
+/// Set to stop work that checks it, such as the tiles of a render that have
+/// not started yet. Clones share the same flag.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A job that panicked, numbered in the order the jobs were given to the pool.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JobPanic {
+    pub job: usize,
+    pub worker: usize,
+    pub message: String,
+}
+
+impl fmt::Display for JobPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "job {} panicked on worker {}: {}",
+            self.job, self.worker, self.message
+        )
+    }
+}
+
+impl Error for JobPanic {}
+
+/// What a worker has done so far.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WorkerStats {
+    /// Jobs run, whether they returned or panicked.
+    pub jobs_run: usize,
+    /// How many of those were taken from the queue of another worker.
+    pub jobs_stolen: usize,
+    pub panics: usize,
+    /// Time spent running jobs.
+    pub busy: Duration,
+    /// Time spent waiting for jobs.
+    pub idle: Duration,
+}
+
+struct Shared {
+    queues: Vec<Mutex<VecDeque<Job>>>,
+    /// The number of jobs in all the queues, for idle workers to wait on.
+    queued: Mutex<usize>,
+    has_job: Condvar,
+    shutdown: AtomicBool,
+    next_queue: AtomicUsize,
+    next_job: AtomicUsize,
+    pending: Pending,
+    stats: Vec<Mutex<WorkerStats>>,
+    panics: Mutex<Vec<JobPanic>>,
+}
+
+impl Shared {
+    fn next_job(&self) -> usize {
+        self.next_job.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// The next job for `worker`, from the front of its own queue or else
+    /// stolen from the back of another, and whether it was stolen.
+    fn find_job(&self, worker: usize) -> Option<(Job, bool)> {
+        let n: usize = self.queues.len();
+        // A statement of its own, so that the lock on the queue is let go of
+        // before taking the lock of another, which could be waiting on this.
+        let own: Option<Job> = self.queues[worker].lock().unwrap().pop_front();
+        let job: Option<(Job, bool)> = own.map(|job| (job, false)).or_else(|| {
+            (1..n).find_map(|offset| {
+                self.queues[(worker + offset) % n]
+                    .lock()
+                    .unwrap()
+                    .pop_back()
+                    .map(|job| (job, true))
+            })
+        });
+
+        if job.is_some() {
+            *self.queued.lock().unwrap() -= 1;
+        }
+        job
+    }
+
+    /// Keep the panic of job `id` for `ThreadPool::panics`, report it and
+    /// return it.
+    fn record_panic(&self, id: usize, payload: Box<dyn Any + Send>) -> JobPanic {
+        let message: String = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        let worker: usize = WORKER.with(Cell::get).unwrap_or_default();
+        let panic: JobPanic = JobPanic {
+            job: id,
+            worker,
+            message,
+        };
+
+        eprintln!("{panic}");
+        self.stats[worker].lock().unwrap().panics += 1;
+        self.panics.lock().unwrap().push(panic.clone());
+        panic
+    }
+}
+
+/// `f` as a job that sends what it returns, or how it panicked, to the
+/// returned handle.
+fn returning<'a, F, T>(
+    shared: &Arc<Shared>,
+    id: usize,
+    f: F,
+) -> (impl FnOnce() + Send + 'a, JoinHandle<T>)
+where
+    F: FnOnce() -> T + Send + 'a,
+    T: Send + 'a,
+{
+    let (sender, receiver) = mpsc::channel();
+    let shared: Arc<Shared> = Arc::clone(shared);
+    let run = move || {
+        let result: Result<T, JobPanic> = panic::catch_unwind(AssertUnwindSafe(f))
+            .map_err(|payload| shared.record_panic(id, payload));
+        // Nobody waits for the result if the handle was dropped.
+        let _ = sender.send(result);
+    };
+
+    (run, JoinHandle { receiver })
+}
+
+/// A count of the jobs that have not finished yet, to wait for it to drop to
+/// zero.
+#[derive(Default)]
+struct Pending {
+    count: Mutex<usize>,
+    zero: Condvar,
+}
+
+impl Pending {
+    fn add(&self) {
+        *self.count.lock().unwrap() += 1;
+    }
+
+    fn done(&self) {
+        let mut count = self.count.lock().unwrap();
+        *count -= 1;
+        if *count == 0 {
+            self.zero.notify_all();
+        }
+    }
+
+    fn wait(&self) {
+        let mut count = self.count.lock().unwrap();
+        while *count != 0 {
+            count = self.zero.wait(count).unwrap();
+        }
+    }
+}
+
+/// Counts a scoped job as done when dropped, whether it returned or panicked.
+struct Done(Arc<Pending>);
+
+impl Drop for Done {
+    fn drop(&mut self) {
+        self.0.done();
+    }
+}
+
+struct WaitOnDrop(Arc<Pending>);
+
+impl Drop for WaitOnDrop {
+    fn drop(&mut self) {
+        self.0.wait();
+    }
+}
+
 struct Worker {
     #[allow(unused)]
     id: usize,
@@ -60,19 +410,41 @@ struct Worker {
 }
 
 impl Worker {
-    fn new(id: usize, receiver: Arc<Mutex<mpsc::Receiver<Job>>>) -> Self {
-        let thread = thread::spawn(move || loop {
-            let message = receiver.lock().unwrap().recv();
+    fn new(id: usize, shared: Arc<Shared>) -> Self {
+        let thread = thread::spawn(move || {
+            WORKER.with(|worker| worker.set(Some(id)));
+            let mut idle_since: Instant = Instant::now();
 
-            match message {
-                Ok(job) => {
-                    // println!("Worker {id} got a job; executing.");
+            loop {
+                match shared.find_job(id) {
+                    Some((job, stolen)) => {
+                        let start: Instant = Instant::now();
+                        if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(job.run)) {
+                            shared.record_panic(job.id, payload);
+                        }
 
-                    job();
-                }
-                Err(_) => {
-                    // println!("Worker {id} disconnected; shutting down.");
-                    break;
+                        {
+                            let mut stats = shared.stats[id].lock().unwrap();
+                            stats.jobs_run += 1;
+                            stats.jobs_stolen += usize::from(stolen);
+                            stats.idle += start - idle_since;
+                            idle_since = Instant::now();
+                            stats.busy += idle_since - start;
+                        }
+                        // Only now, so that `wait_all` sees the panics and the
+                        // stats of every job it waited for.
+                        shared.pending.done();
+                    }
+                    None => {
+                        let mut queued = shared.queued.lock().unwrap();
+                        while *queued == 0 && !shared.shutdown.load(Ordering::Relaxed) {
+                            queued = shared.has_job.wait(queued).unwrap();
+                        }
+                        if *queued == 0 {
+                            // println!("Worker {id} disconnected; shutting down.");
+                            break;
+                        }
+                    }
                 }
             }
         });
@@ -83,3 +455,125 @@ impl Worker {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spawn_should_return_the_result_of_the_job() {
+        let thread_pool: ThreadPool = ThreadPool::new(2);
+        let handles: Vec<JoinHandle<usize>> =
+            (0..10).map(|i| thread_pool.spawn(move || i * i)).collect();
+
+        let squares: Vec<usize> = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect();
+        assert_eq!((0..10).map(|i| i * i).collect::<Vec<usize>>(), squares);
+    }
+
+    #[test]
+    fn wait_all_should_return_once_every_job_is_done() {
+        let thread_pool: ThreadPool = ThreadPool::new(3);
+        let done: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
+        (0..20).for_each(|_| {
+            let done = Arc::clone(&done);
+            thread_pool.execute(move || {
+                thread::sleep(Duration::from_millis(1));
+                done.fetch_add(1, Ordering::Relaxed);
+            })
+        });
+
+        thread_pool.wait_all();
+        assert_eq!(20, done.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn scoped_jobs_should_borrow_from_the_caller() {
+        let thread_pool: ThreadPool = ThreadPool::new(2);
+        let numbers: Vec<usize> = (1..=100).collect();
+        let sum: AtomicUsize = AtomicUsize::new(0);
+
+        let halves: usize = thread_pool.scope(|scope| {
+            numbers.chunks(10).for_each(|chunk| {
+                let sum: &AtomicUsize = &sum;
+                scope.spawn(move || sum.fetch_add(chunk.iter().sum(), Ordering::Relaxed));
+            });
+
+            let handles: Vec<JoinHandle<usize>> = numbers
+                .chunks(50)
+                .map(|half| scope.spawn(move || half.iter().sum()))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .sum()
+        });
+
+        assert_eq!(5050, sum.load(Ordering::Relaxed));
+        assert_eq!(5050, halves);
+    }
+
+    #[test]
+    fn panics_should_be_reported_and_leave_the_pool_working() {
+        let thread_pool: ThreadPool = ThreadPool::new(2);
+        thread_pool.execute(|| panic!("first"));
+        let handle: JoinHandle<()> = thread_pool.spawn(|| panic!("second {}", 2));
+        thread_pool.wait_all();
+
+        let panic: JobPanic = handle.join().unwrap_err();
+        assert_eq!((1, "second 2"), (panic.job, panic.message.as_str()));
+        let mut panics: Vec<JobPanic> = thread_pool.panics();
+        panics.sort_by_key(|panic| panic.job);
+        assert_eq!((0, "first"), (panics[0].job, panics[0].message.as_str()));
+        assert_eq!(panic, panics[1]);
+
+        // Both workers are still there to run jobs.
+        let handles: Vec<JoinHandle<usize>> =
+            (0..4).map(|i| thread_pool.spawn(move || i)).collect();
+        assert_eq!(
+            6,
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .sum::<usize>()
+        );
+        thread_pool.wait_all();
+        let stats: Vec<WorkerStats> = thread_pool.stats();
+        assert_eq!(6, stats.iter().map(|stats| stats.jobs_run).sum::<usize>());
+        assert_eq!(2, stats.iter().map(|stats| stats.panics).sum::<usize>());
+    }
+
+    #[test]
+    fn idle_workers_should_steal_queued_jobs() {
+        let thread_pool: ThreadPool = ThreadPool::new(2);
+        // Every other job goes to the queue of the first worker, behind one
+        // that holds up whichever worker takes it until the rest are done, so
+        // the four after it can only be done by stealing them.
+        let (release, blocked) = mpsc::channel::<()>();
+        thread_pool.execute(move || blocked.recv().unwrap());
+        let handles: Vec<JoinHandle<usize>> =
+            (0..9).map(|i| thread_pool.spawn(move || i)).collect();
+        let sum: usize = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .sum();
+        release.send(()).unwrap();
+        thread_pool.wait_all();
+
+        assert_eq!(36, sum);
+        let stats: Vec<WorkerStats> = thread_pool.stats();
+        assert_eq!(10, stats.iter().map(|stats| stats.jobs_run).sum::<usize>());
+        assert!(4 <= stats.iter().map(|stats| stats.jobs_stolen).sum::<usize>());
+    }
+
+    #[test]
+    fn cancellation_token_should_be_shared_by_clones() {
+        let token: CancellationToken = CancellationToken::new();
+        let clone: CancellationToken = token.clone();
+        assert!(!clone.is_cancelled());
+        token.cancel();
+        assert!(clone.is_cancelled());
+    }
+}
@@ -0,0 +1,507 @@
+use std::ops;
+
+use crate::{point::Point3, quaternion::Quaternion, vec3::Vec3};
+
+/// Row-major 4x4 matrix acting on column vectors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mat4(pub [[f32; 4]; 4]);
+
+impl Mat4 {
+    pub const IDENTITY: Self = Self([
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+
+    pub fn transpose(&self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        (0..4).for_each(|row| (0..4).for_each(|col| m[row][col] = self.0[col][row]));
+
+        Self(m)
+    }
+
+    /// Inverse by Gauss-Jordan elimination with partial pivoting, `None` when
+    /// the matrix is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let mut m: [[f32; 4]; 4] = self.0;
+        let mut inv: [[f32; 4]; 4] = Self::IDENTITY.0;
+
+        for col in 0..4 {
+            let pivot: usize =
+                (col..4).max_by(|&a, &b| m[a][col].abs().total_cmp(&m[b][col].abs()))?;
+            if m[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            m.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale: f32 = 1.0 / m[col][col];
+            (0..4).for_each(|c| {
+                m[col][c] *= scale;
+                inv[col][c] *= scale;
+            });
+
+            for row in (0..4).filter(|&row| row != col) {
+                let factor: f32 = m[row][col];
+                (0..4).for_each(|c| {
+                    m[row][c] -= factor * m[col][c];
+                    inv[row][c] -= factor * inv[col][c];
+                });
+            }
+        }
+
+        Some(Self(inv))
+    }
+
+    /// Apply to a point, i.e. with homogeneous coordinate 1.
+    pub fn transform_point(&self, p: Point3) -> Point3 {
+        let m = &self.0;
+        let [x, y, z, w]: [f32; 4] = [0, 1, 2, 3]
+            .map(|row| m[row][0] * p.x() + m[row][1] * p.y() + m[row][2] * p.z() + m[row][3]);
+
+        match w {
+            1.0 => Point3::new(x, y, z),
+            _ => Point3::new(x / w, y / w, z / w),
+        }
+    }
+
+    /// Apply to a direction, i.e. with homogeneous coordinate 0.
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let m = &self.0;
+        let [x, y, z]: [f32; 3] =
+            [0, 1, 2].map(|row| m[row][0] * v.x() + m[row][1] * v.y() + m[row][2] * v.z());
+
+        Vec3::new(x, y, z)
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl ops::Mul for Mat4 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut m = [[0.0; 4]; 4];
+        (0..4).for_each(|row| {
+            (0..4)
+                .for_each(|col| m[row][col] = (0..4).map(|k| self.0[row][k] * rhs.0[k][col]).sum())
+        });
+
+        Self(m)
+    }
+}
+
+/// An invertible affine transform, kept together with its inverse.
+///
+/// `a * b` applies `b` first, then `a`; `a.then(b)` reads in application order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    matrix: Mat4,
+    inverse: Mat4,
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Self {
+            matrix: Mat4::IDENTITY,
+            inverse: Mat4::IDENTITY,
+        }
+    }
+
+    /// `None` when `matrix` is singular.
+    pub fn from_matrix(matrix: Mat4) -> Option<Self> {
+        Some(Self {
+            matrix,
+            inverse: matrix.inverse()?,
+        })
+    }
+
+    pub fn translate(displacement: Vec3) -> Self {
+        let matrix = Mat4([
+            [1.0, 0.0, 0.0, displacement.x()],
+            [0.0, 1.0, 0.0, displacement.y()],
+            [0.0, 0.0, 1.0, displacement.z()],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let inverse = Mat4([
+            [1.0, 0.0, 0.0, -displacement.x()],
+            [0.0, 1.0, 0.0, -displacement.y()],
+            [0.0, 0.0, 1.0, -displacement.z()],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        Self { matrix, inverse }
+    }
+
+    /// Scale by the given factor along each axis, which must not be zero.
+    pub fn scale(factors: Vec3) -> Self {
+        debug_assert!(factors.x() != 0.0 && factors.y() != 0.0 && factors.z() != 0.0);
+
+        let matrix = Mat4([
+            [factors.x(), 0.0, 0.0, 0.0],
+            [0.0, factors.y(), 0.0, 0.0],
+            [0.0, 0.0, factors.z(), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        let inverse = Mat4([
+            [1.0 / factors.x(), 0.0, 0.0, 0.0],
+            [0.0, 1.0 / factors.y(), 0.0, 0.0],
+            [0.0, 0.0, 1.0 / factors.z(), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        Self { matrix, inverse }
+    }
+
+    /// Counterclockwise rotation looking from +X towards the origin.
+    pub fn rotate_x(radians: f32) -> Self {
+        Self::rotate_axis_angle(Vec3::new(1.0, 0.0, 0.0), radians)
+    }
+
+    /// Counterclockwise rotation looking from +Y towards the origin, the same
+    /// as `Instance::RotateY`.
+    pub fn rotate_y(radians: f32) -> Self {
+        Self::rotate_axis_angle(Vec3::new(0.0, 1.0, 0.0), radians)
+    }
+
+    /// Counterclockwise rotation looking from +Z towards the origin.
+    pub fn rotate_z(radians: f32) -> Self {
+        Self::rotate_axis_angle(Vec3::new(0.0, 0.0, 1.0), radians)
+    }
+
+    /// Counterclockwise rotation around `axis` looking from its tip towards the
+    /// origin (Rodrigues' formula).
+    pub fn rotate_axis_angle(axis: Vec3, radians: f32) -> Self {
+        let a: Vec3 = axis.unit_vector();
+        let (sin, cos): (f32, f32) = radians.sin_cos();
+        let t: f32 = 1.0 - cos;
+
+        let matrix = Mat4([
+            [
+                t * a.x() * a.x() + cos,
+                t * a.x() * a.y() - sin * a.z(),
+                t * a.x() * a.z() + sin * a.y(),
+                0.0,
+            ],
+            [
+                t * a.x() * a.y() + sin * a.z(),
+                t * a.y() * a.y() + cos,
+                t * a.y() * a.z() - sin * a.x(),
+                0.0,
+            ],
+            [
+                t * a.x() * a.z() - sin * a.y(),
+                t * a.y() * a.z() + sin * a.x(),
+                t * a.z() * a.z() + cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        Self::from_rotation_matrix(matrix)
+    }
+
+    /// Rotations are orthogonal, so the inverse is just the transpose.
+    pub(crate) fn from_rotation_matrix(matrix: Mat4) -> Self {
+        Self {
+            matrix,
+            inverse: matrix.transpose(),
+        }
+    }
+
+    /// Shear where each coordinate grows proportionally to the other two, e.g.
+    /// `xy` is how much x grows per unit of y.
+    pub fn shear(xy: f32, xz: f32, yx: f32, yz: f32, zx: f32, zy: f32) -> Option<Self> {
+        Self::from_matrix(Mat4([
+            [1.0, xy, xz, 0.0],
+            [yx, 1.0, yz, 0.0],
+            [zx, zy, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]))
+    }
+
+    /// Place an object at `from` so that its local +Z axis points at `to` and
+    /// its local +Y axis leans towards `up`.
+    pub fn look_at(from: Point3, to: Point3, up: Vec3) -> Self {
+        let w: Vec3 = (to - from).unit_vector();
+        let u: Vec3 = up.cross(w).unit_vector();
+        let v: Vec3 = w.cross(u);
+
+        let rotation = Mat4([
+            [u.x(), v.x(), w.x(), 0.0],
+            [u.y(), v.y(), w.y(), 0.0],
+            [u.z(), v.z(), w.z(), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        Self::translate(from) * Self::from_rotation_matrix(rotation)
+    }
+
+    /// Apply `self` first, then `next`.
+    pub fn then(self, next: Self) -> Self {
+        next * self
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    #[inline]
+    pub fn matrix(&self) -> &Mat4 {
+        &self.matrix
+    }
+
+    #[inline]
+    pub fn inverse_matrix(&self) -> &Mat4 {
+        &self.inverse
+    }
+
+    pub fn point(&self, p: Point3) -> Point3 {
+        self.matrix.transform_point(p)
+    }
+
+    pub fn vector(&self, v: Vec3) -> Vec3 {
+        self.matrix.transform_vector(v)
+    }
+
+    /// Transform a surface normal with the inverse transpose, so that it stays
+    /// perpendicular to the transformed surface. The result is not normalised.
+    pub fn normal(&self, n: Vec3) -> Vec3 {
+        self.inverse.transpose().transform_vector(n)
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl ops::Mul for Transform {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self {
+            matrix: self.matrix * rhs.matrix,
+            inverse: rhs.inverse * self.inverse,
+        }
+    }
+}
+
+/// A pose at `time`: scale first, then rotate, then translate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Keyframe {
+    pub time: f32,
+    pub translation: Vec3,
+    pub rotation: Quaternion,
+    pub scale: Vec3,
+}
+
+impl Keyframe {
+    pub fn new(time: f32, translation: Vec3, rotation: Quaternion, scale: Vec3) -> Self {
+        Self {
+            time,
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn to_transform(&self) -> Transform {
+        Transform::scale(self.scale)
+            .then(self.rotation.to_transform())
+            .then(Transform::translate(self.translation))
+    }
+
+    /// Linear in translation and scale, slerp in rotation.
+    fn interpolate(&self, next: &Self, time: f32) -> Self {
+        let t: f32 = (time - self.time) / (next.time - self.time);
+
+        Self {
+            time,
+            translation: self.translation + t * (next.translation - self.translation),
+            rotation: self.rotation.slerp(&next.rotation, t),
+            scale: self.scale + t * (next.scale - self.scale),
+        }
+    }
+}
+
+/// A transform that changes over time by interpolating between keyframes. It
+/// holds the first pose before the first keyframe and the last pose after the
+/// last one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimatedTransform {
+    keyframes: Vec<Keyframe>,
+}
+
+impl AnimatedTransform {
+    /// Panics if `keyframes` is empty.
+    pub fn new(mut keyframes: Vec<Keyframe>) -> Self {
+        assert!(!keyframes.is_empty(), "an animation needs keyframes");
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        Self { keyframes }
+    }
+
+    /// Move from `start` at `time_0` to `end` at `time_1`.
+    pub fn between(time_0: f32, start: Keyframe, time_1: f32, end: Keyframe) -> Self {
+        Self::new(vec![
+            Keyframe {
+                time: time_0,
+                ..start
+            },
+            Keyframe {
+                time: time_1,
+                ..end
+            },
+        ])
+    }
+
+    #[inline]
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    /// The interpolated pose at `time`.
+    pub fn keyframe_at(&self, time: f32) -> Keyframe {
+        let next: usize = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time);
+        match next {
+            0 => Keyframe {
+                time,
+                ..self.keyframes[0]
+            },
+            n if n == self.keyframes.len() => Keyframe {
+                time,
+                ..self.keyframes[n - 1]
+            },
+            n => self.keyframes[n - 1].interpolate(&self.keyframes[n], time),
+        }
+    }
+
+    pub fn at(&self, time: f32) -> Transform {
+        self.keyframe_at(time).to_transform()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(expected: Vec3, actual: Vec3) {
+        assert!((expected - actual).len() < 1e-5, "{expected} != {actual}");
+    }
+
+    #[test]
+    fn transform_should_work() {
+        let transform: Transform = Transform::scale(Vec3::new(2.0, 1.0, 0.5))
+            .then(Transform::rotate_z(90_f32.to_radians()))
+            .then(Transform::translate(Vec3::new(1.0, 2.0, 3.0)));
+
+        assert_near(
+            Point3::new(1.0, 4.0, 3.0),
+            transform.point(Point3::new(1.0, 0.0, 0.0)),
+        );
+        assert_near(
+            Vec3::new(0.0, 2.0, 0.0),
+            transform.vector(Vec3::new(1.0, 0.0, 0.0)),
+        );
+        assert_near(
+            Point3::new(1.0, 0.0, 0.0),
+            transform
+                .inverse()
+                .point(transform.point(Point3::new(1.0, 0.0, 0.0))),
+        );
+
+        let product: Mat4 = *transform.matrix() * *transform.inverse_matrix();
+        (0..4).for_each(|row| {
+            (0..4).for_each(|col| {
+                assert!((product.0[row][col] - Mat4::IDENTITY.0[row][col]).abs() < 1e-5)
+            })
+        });
+        let inverse: Mat4 = transform.matrix().inverse().unwrap();
+        (0..4).for_each(|row| {
+            (0..4).for_each(|col| {
+                assert!((inverse.0[row][col] - transform.inverse_matrix().0[row][col]).abs() < 1e-5)
+            })
+        });
+        assert!(Mat4([[0.0; 4]; 4]).inverse().is_none());
+    }
+
+    #[test]
+    fn normals_should_stay_perpendicular() {
+        let transform: Transform = Transform::shear(0.5, 0.0, 0.0, 0.0, 0.3, 0.0)
+            .unwrap()
+            .then(Transform::rotate_axis_angle(Vec3::new(1.0, 1.0, 0.0), 0.7));
+
+        // A plane spanned by x and y with normal z.
+        let tangents = [Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)];
+        let normal: Vec3 = transform.normal(Vec3::new(0.0, 0.0, 1.0));
+        tangents
+            .into_iter()
+            .for_each(|tangent| assert!(transform.vector(tangent).dot(normal).abs() < 1e-5));
+
+        assert_near(
+            Point3::new(0.0, 0.0, 1.0),
+            Transform::look_at(
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(0.0, 0.0, 5.0),
+                Vec3::new(0.0, 1.0, 0.0),
+            )
+            .vector(Vec3::new(0.0, 0.0, 1.0)),
+        );
+        assert_near(
+            Point3::new(1.0, 2.0, 3.0),
+            Transform::look_at(
+                Point3::new(1.0, 2.0, 3.0),
+                Point3::new(4.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+            )
+            .point(Point3::new(0.0, 0.0, 0.0)),
+        );
+    }
+
+    #[test]
+    fn animated_transform_should_interpolate_keyframes() {
+        let y: Vec3 = Vec3::new(0.0, 1.0, 0.0);
+        let animation = AnimatedTransform::new(vec![
+            Keyframe::new(
+                1.0,
+                Vec3::new(2.0, 0.0, 0.0),
+                Quaternion::from_axis_angle(y, 90_f32.to_radians()),
+                Vec3::new(3.0, 3.0, 3.0),
+            ),
+            Keyframe::new(
+                0.0,
+                Vec3::new(0.0, 0.0, 0.0),
+                Quaternion::identity(),
+                Vec3::new(1.0, 1.0, 1.0),
+            ),
+        ]);
+
+        assert_eq!(0.0, animation.keyframes()[0].time);
+        assert_near(
+            Point3::new(1.0, 0.0, 0.0),
+            animation.at(-1.0).point(Point3::new(1.0, 0.0, 0.0)),
+        );
+        assert_near(
+            Point3::new(2.0, 0.0, -3.0),
+            animation.at(2.0).point(Point3::new(1.0, 0.0, 0.0)),
+        );
+
+        let half: f32 = 2.0 * 45_f32.to_radians().cos();
+        assert_near(
+            Point3::new(1.0 + half, 0.0, -half),
+            animation.at(0.5).point(Point3::new(1.0, 0.0, 0.0)),
+        );
+    }
+}
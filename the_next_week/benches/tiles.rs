@@ -0,0 +1,133 @@
+//! How long a render takes with a job per pixel, as `main` used to submit
+//! them, against the tiles of `TileScheduler`:
+//!
+//! ```text
+//! cargo bench -p the_next_week --bench tiles
+//! ```
+//!
+//! A few samples per pixel of the Cornell box keep the work per pixel small,
+//! so that what the scheduling costs shows.
+
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use in_one_weekend::{
+    color::{ColorRGB, ColorRGBMapTo0_1},
+    image::{PPMImg, PPMImgMagicNum},
+    thread_pool::ThreadPool,
+    utils,
+};
+use the_next_week::{
+    render::{TileOrder, TileScheduler},
+    sampler::{Sampler, Sobol},
+    scene::Scene,
+};
+
+const IMAGE_WIDTH: usize = 256;
+const IMAGE_HEIGHT: usize = 256;
+const SAMPLES_PER_PIXEL: usize = 4;
+const SEED: u64 = 0;
+/// Each way of rendering is timed this many times, and the fastest kept.
+const RUNS: usize = 5;
+
+fn pixel_color(row: usize, column: usize, scene: &Scene, sampler: &mut dyn Sampler) -> ColorRGB {
+    let [red, green, blue] = (0..SAMPLES_PER_PIXEL)
+        .fold([0.0, 0.0, 0.0], |[r, g, b], index| {
+            sampler.start_pixel_sample(column, row, index);
+            let color: ColorRGBMapTo0_1 =
+                scene.sample(row, column, IMAGE_WIDTH, IMAGE_HEIGHT, sampler);
+
+            [r + color.r(), g + color.g(), b + color.b()]
+        })
+        .map(|v: f32| (v * scene.exposure() / SAMPLES_PER_PIXEL as f32).sqrt());
+
+    ColorRGBMapTo0_1::new(red, green, blue).into()
+}
+
+/// A boxed job per pixel, each locking the image to write its pixel and the
+/// count of pixels done to log the progress.
+fn render_per_pixel(thread_pool: &ThreadPool, scene: &Arc<Scene>) {
+    let num_pixels: usize = IMAGE_WIDTH * IMAGE_HEIGHT;
+    let image: Arc<Mutex<PPMImg<IMAGE_WIDTH, IMAGE_HEIGHT>>> =
+        Arc::new(Mutex::new(PPMImg::new(PPMImgMagicNum::P3)));
+    let num_pixels_has_rendered: Arc<Mutex<usize>> = Arc::new(Mutex::new(0));
+
+    (0..IMAGE_HEIGHT).for_each(|row| {
+        (0..IMAGE_WIDTH).for_each(|column| {
+            let image = Arc::clone(&image);
+            let scene = Arc::clone(scene);
+            let num_pixels_has_rendered = Arc::clone(&num_pixels_has_rendered);
+
+            thread_pool.execute(move || {
+                let mut sampler: Sobol = Sobol::new().set_seed(SEED);
+                let color: ColorRGB = pixel_color(row, column, &scene, &mut sampler);
+                image.lock().unwrap().set_pixel_color(row, column, color);
+
+                let mut num = num_pixels_has_rendered.lock().unwrap();
+                *num += 1;
+                if (*num).is_multiple_of(num_pixels / 1000) {
+                    utils::log_progress(*num as f64 / num_pixels as f64).unwrap();
+                }
+            })
+        })
+    });
+
+    while *num_pixels_has_rendered.lock().unwrap() < num_pixels {
+        thread::yield_now();
+    }
+}
+
+fn render_tiled(thread_pool: &ThreadPool, scene: &Scene, size: usize) {
+    let pixel_color = |row: usize, column: usize, sampler: &mut dyn Sampler| {
+        self::pixel_color(row, column, scene, sampler)
+    };
+    let _: PPMImg<IMAGE_WIDTH, IMAGE_HEIGHT> = TileScheduler::new(size, TileOrder::Hilbert).render(
+        thread_pool,
+        Sobol::new().set_seed(SEED),
+        &pixel_color,
+    );
+}
+
+fn fastest(mut render: impl FnMut()) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start: Instant = Instant::now();
+            render();
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+fn main() {
+    let num_cpus: usize = num_cpus::get();
+    let thread_pool: ThreadPool = ThreadPool::new(num_cpus);
+    let scene: Arc<Scene> =
+        Arc::new(Scene::builtin("cornell_box", IMAGE_WIDTH as f32 / IMAGE_HEIGHT as f32).unwrap());
+
+    let mut timings: Vec<(String, Duration)> = vec![(
+        "job per pixel".to_string(),
+        fastest(|| render_per_pixel(&thread_pool, &scene)),
+    )];
+    for size in [1, 8, 16, 32, 64] {
+        timings.push((
+            format!("{size}x{size} tiles"),
+            fastest(|| render_tiled(&thread_pool, &scene, size)),
+        ));
+    }
+
+    eprintln!(
+        "\n{IMAGE_WIDTH}x{IMAGE_HEIGHT}, {SAMPLES_PER_PIXEL} samples per pixel, {num_cpus} threads, fastest of {RUNS}:"
+    );
+    let per_pixel: Duration = timings[0].1;
+    for (name, time) in &timings {
+        eprintln!(
+            "  {name:<14} {:>9.1} ms  {:>5.2}x",
+            time.as_secs_f64() * 1e3,
+            per_pixel.as_secs_f64() / time.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,201 @@
+extern crate num_cpus;
+use humantime::format_duration;
+use in_one_weekend::{
+    color::{ColorRGB, ColorRGBMapTo0_1},
+    image::PPMImg,
+    point::Point3,
+    quaternion::Quaternion,
+    thread_pool::ThreadPool,
+    transform::{AnimatedTransform, Keyframe},
+    vec3::Vec3,
+};
+
+use std::{f32::consts::TAU, sync::Arc, time};
+
+use the_next_week::{
+    animation::{Interpolation, Sequence, Track},
+    camera::{AnimatedCamera, Camera, CameraTimeline},
+    hittable::{Cuboid, Hittable, HittableList, Instance, Sphere},
+    material::{Attenuation, Keyframed, Lambertian, Material, Metal},
+    render::TileScheduler,
+    sampler::{Sampler, Sobol},
+    scene::{Background, Scene},
+    textures::{CheckerTexture, SolidColor},
+};
+
+const ASPECT_RATIO: f32 = 16.0 / 9.0;
+
+/// Renders a two second turntable as numbered frames, e.g. for
+/// `ffmpeg -framerate 12 -i ./target/frame_%04d.ppm turntable.mp4`.
+fn main() -> std::io::Result<()> {
+    let num_cpus = num_cpus::get();
+    println!("num_cpus::get() : {num_cpus}");
+
+    let thread_pool: ThreadPool = ThreadPool::new(num_cpus);
+
+    // Image
+    const IMAGE_WIDTH: usize = 400;
+    const IMAGE_HEIGHT: usize = (IMAGE_WIDTH as f32 / ASPECT_RATIO) as usize;
+    const SAMPLES_PER_PIXEL: usize = 50;
+    const OUTPUT_IMAGE_PREFIX: &str = "./target/frame_";
+    // Every random number of the render derives from it, for repeatable images.
+    const SEED: u64 = 0;
+
+    // Frames
+    const DURATION: f32 = 2.0;
+    let sequence: Sequence = Sequence::new(0.0, 12.0, 24).set_shutter_angle(180.0);
+
+    // Camera: once around the scene, closing in on it half way.
+    const ORBIT_KEYS: usize = 8;
+    let look_from: Track<Point3> = Track::new(
+        (0..=ORBIT_KEYS)
+            .map(|i| {
+                let s: f32 = i as f32 / ORBIT_KEYS as f32;
+                let radius: f32 = 10.0 - 3.0 * (s * TAU / 2.0).sin();
+                let (sin, cos): (f32, f32) = (s * TAU).sin_cos();
+                (s * DURATION, Point3::new(radius * sin, 2.5, radius * cos))
+            })
+            .collect(),
+    )
+    .set_interpolation(Interpolation::CatmullRom);
+
+    let timeline: CameraTimeline = CameraTimeline::new(
+        Camera::builder()
+            .look_at(Point3::new(0.0, 1.0, 0.0))
+            .up(Vec3::new(0.0, 1.0, 0.0))
+            .aspect_ratio(ASPECT_RATIO)
+            .aperture(0.05),
+    )
+    .look_from(look_from)
+    .fov(Track::new(vec![(0.0, 30.0), (DURATION, 20.0)]))
+    .focus_dist(Track::new(vec![
+        (0.0, 10.0),
+        (DURATION / 2.0, 7.0),
+        (DURATION, 10.0),
+    ]));
+
+    let sampler: Sobol = Sobol::new().set_seed(SEED);
+
+    let time_render_start: time::Instant = time::Instant::now();
+
+    // Render
+    const MAX_DEPTH_RAY_RECURSION: u16 = 50;
+
+    for frame in sequence.frames() {
+        // The world is small enough to build again around the camera of each
+        // frame.
+        let camera: AnimatedCamera = timeline
+            .clone()
+            .build(frame.shutter_open, frame.shutter_close);
+        let scene: Scene = Scene::new(self::turntable(DURATION), camera, Background::Sky)
+            .set_max_depth(MAX_DEPTH_RAY_RECURSION);
+        let pixel_color = |row: usize, column: usize, sampler: &mut dyn Sampler| {
+            self::pixel_color::<IMAGE_HEIGHT, IMAGE_WIDTH, SAMPLES_PER_PIXEL>(
+                row, column, &scene, sampler,
+            )
+        };
+        let image: PPMImg<IMAGE_WIDTH, IMAGE_HEIGHT> =
+            TileScheduler::default().render(&thread_pool, sampler, &pixel_color);
+
+        let path: String = frame.file_name(OUTPUT_IMAGE_PREFIX, "ppm");
+        image.write_to_file(&path)?;
+        eprintln!("\nFrame {} of {}: {path}", frame.index + 1, sequence.len());
+    }
+
+    eprintln!(
+        "The render of {} frames took {}",
+        sequence.len(),
+        format_duration(time_render_start.elapsed())
+    );
+
+    Ok(())
+}
+
+fn pixel_color<const HEIGHT: usize, const WIDTH: usize, const SAMPLES: usize>(
+    row: usize,
+    column: usize,
+    scene: &Scene,
+    sampler: &mut dyn Sampler,
+) -> ColorRGB {
+    let [red, green, blue] = (0..SAMPLES)
+        .fold([0.0, 0.0, 0.0], |[r, g, b], index| {
+            sampler.start_pixel_sample(column, row, index);
+            let color: ColorRGBMapTo0_1 = scene.sample(row, column, WIDTH, HEIGHT, sampler);
+
+            [r + color.r(), g + color.g(), b + color.b()]
+        })
+        // Divide the color by the number of samples and gamma-correct for gamma=2.0.
+        .map(|v: f32| (v * scene.exposure() / SAMPLES as f32).sqrt());
+
+    ColorRGBMapTo0_1::new(red, green, blue).into()
+}
+
+/// A spinning, bobbing box between two spheres, one of which turns from
+/// matte to polished over `duration`.
+fn turntable(duration: f32) -> HittableList<Box<dyn Hittable>> {
+    let mut objects: HittableList<Box<dyn Hittable>> = HittableList::default();
+
+    let checker: Arc<dyn Material> = Arc::new(Lambertian::new(CheckerTexture::new(
+        SolidColor::from(ColorRGBMapTo0_1::new(0.2, 0.3, 0.1)),
+        SolidColor::from(ColorRGBMapTo0_1::new(0.9, 0.9, 0.9)),
+    )));
+    objects.add(Box::new(Sphere::new(
+        Point3::new(0.0, -1000.0, 0.0),
+        1000.0,
+        checker,
+    )));
+
+    let orange: Arc<dyn Material> = Arc::new(Lambertian::new(SolidColor::from(
+        ColorRGBMapTo0_1::new(0.8, 0.4, 0.1),
+    )));
+    const SPIN_KEYS: usize = 8;
+    let spin: AnimatedTransform = AnimatedTransform::new(
+        (0..=SPIN_KEYS)
+            .map(|i| {
+                let s: f32 = i as f32 / SPIN_KEYS as f32;
+                Keyframe::new(
+                    s * duration,
+                    Vec3::new(0.0, 1.0 + 0.5 * (s * 2.0 * TAU).sin().abs(), 0.0),
+                    Quaternion::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), s * TAU),
+                    Vec3::new(1.0, 1.0, 1.0),
+                )
+            })
+            .collect(),
+    );
+    let cube: Box<dyn Hittable> = Box::new(Cuboid::new(
+        Point3::new(-0.7, -0.7, -0.7),
+        Point3::new(0.7, 0.7, 0.7),
+        orange,
+    ));
+    objects.add(Box::new(Instance::Animated {
+        prototype: cube,
+        animation: spin,
+    }));
+
+    let blue: Arc<dyn Material> = Arc::new(Lambertian::new(SolidColor::from(
+        ColorRGBMapTo0_1::new(0.1, 0.2, 0.5),
+    )));
+    let polishing: Arc<dyn Material> = Arc::new(Keyframed::new(vec![
+        (
+            0.0,
+            Arc::new(Metal::new(Attenuation::new(Vec3::new(0.8, 0.8, 0.8)), 1.0))
+                as Arc<dyn Material>,
+        ),
+        (
+            duration,
+            Arc::new(Metal::new(Attenuation::new(Vec3::new(0.8, 0.8, 0.8)), 0.0)),
+        ),
+    ]));
+    objects.add(Box::new(Sphere::new(
+        Point3::new(-3.0, 1.0, 0.0),
+        1.0,
+        blue,
+    )));
+    objects.add(Box::new(Sphere::new(
+        Point3::new(3.0, 1.0, 0.0),
+        1.0,
+        polishing,
+    )));
+
+    objects
+}
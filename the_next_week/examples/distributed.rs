@@ -0,0 +1,92 @@
+extern crate num_cpus;
+use humantime::format_duration;
+use in_one_weekend::thread_pool::ThreadPool;
+
+use std::{io, path::Path, time};
+
+use the_next_week::{
+    distributed::{self, Coordinator, RenderJob},
+    render::{self, Framebuffer, TileOrder, TileScheduler},
+    scene::{Scene, SceneSource},
+};
+
+const ASPECT_RATIO: f32 = 16.0 / 9.0;
+
+const USAGE: &str = "usage: distributed coordinator <address> <scene file or built-in name>
+       distributed worker <address>";
+
+/// Renders a scene across several processes, on one machine or many:
+///
+/// ```text
+/// cargo run --release --example distributed -- coordinator 0.0.0.0:7878 cornell_box
+/// cargo run --release --example distributed -- worker 192.168.1.2:7878
+/// ```
+///
+/// Workers may be started before or during the render, and stopped at any
+/// time as long as one is left.
+fn main() -> io::Result<()> {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let arguments: Vec<&str> = arguments.iter().map(String::as_str).collect();
+
+    match arguments.as_slice() {
+        ["coordinator", address, scene] => coordinate(address, scene),
+        ["worker", address] => {
+            let num_cpus = num_cpus::get();
+            println!("num_cpus::get() : {num_cpus}");
+
+            let thread_pool: ThreadPool = ThreadPool::new(num_cpus);
+            eprintln!("Rendering for {address}");
+            distributed::run_worker(address, &thread_pool)?;
+            eprintln!("Render finished");
+            Ok(())
+        }
+        _ => Err(io::Error::new(io::ErrorKind::InvalidInput, USAGE)),
+    }
+}
+
+fn coordinate(address: &str, scene: &str) -> io::Result<()> {
+    // Image
+    const IMAGE_WIDTH: usize = 800;
+    const IMAGE_HEIGHT: usize = (IMAGE_WIDTH as f32 / ASPECT_RATIO) as usize;
+    const SAMPLES_PER_PIXEL: usize = 256;
+    const OUTPUT_IMAGE_PATH: &str = "./target/image.ppm";
+    // Every random number of the render derives from it, for repeatable images.
+    const SEED: u64 = 0;
+    const TILE_SIZE: usize = 32;
+
+    let scene: SceneSource = if Path::new(scene).is_file() {
+        SceneSource::file(scene)?
+    } else {
+        SceneSource::Builtin(scene.to_string())
+    };
+    // Fail here rather than on every worker if the scene does not load, and
+    // keep it for the exposure of its camera.
+    let exposure: f32 = scene.load(ASPECT_RATIO)?.exposure();
+    let job: RenderJob = RenderJob {
+        scene,
+        samples_per_pixel: SAMPLES_PER_PIXEL,
+        seed: SEED,
+    };
+
+    let coordinator: Coordinator = Coordinator::bind(address)?;
+    eprintln!(
+        "Waiting for workers on {}, the built-in scenes are {:?}",
+        coordinator.local_addr()?,
+        Scene::BUILTIN
+    );
+
+    let start = time::Instant::now();
+    let framebuffer: Framebuffer<IMAGE_WIDTH, IMAGE_HEIGHT> =
+        coordinator.render(&job, &TileScheduler::new(TILE_SIZE, TileOrder::Spiral))?;
+    eprintln!(
+        "\nRender finished in {}",
+        format_duration(time::Duration::from_millis(
+            start.elapsed().as_millis() as u64
+        ))
+    );
+
+    render::write_image(&framebuffer.to_image(exposure), OUTPUT_IMAGE_PATH)?;
+    eprintln!("Image written to {OUTPUT_IMAGE_PATH}");
+
+    Ok(())
+}
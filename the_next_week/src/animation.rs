@@ -0,0 +1,193 @@
+use std::ops;
+
+/// How a `Track` moves between its keys.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Hold each key until the next one.
+    Step,
+    Linear,
+    /// A smooth curve through the keys, for camera paths.
+    CatmullRom,
+}
+
+/// A value that changes over time by interpolating between keys, such as the
+/// position or field of view of a camera. It holds the first value before the
+/// first key and the last value after the last one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track<T> {
+    keys: Vec<(f32, T)>,
+    interpolation: Interpolation,
+}
+
+impl<T> Track<T>
+where
+    T: Copy + ops::Add<Output = T> + ops::Sub<Output = T> + ops::Mul<f32, Output = T>,
+{
+    /// Linear between `(time, value)` keys. Panics if `keys` is empty.
+    pub fn new(mut keys: Vec<(f32, T)>) -> Self {
+        assert!(!keys.is_empty(), "a track needs keys");
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Self {
+            keys,
+            interpolation: Interpolation::Linear,
+        }
+    }
+
+    pub fn constant(value: T) -> Self {
+        Self::new(vec![(0.0, value)])
+    }
+
+    pub fn set_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    #[inline]
+    pub fn keys(&self) -> &[(f32, T)] {
+        &self.keys
+    }
+
+    pub fn at(&self, time: f32) -> T {
+        let next: usize = self.keys.partition_point(|(key_time, _)| *key_time <= time);
+        if next == 0 {
+            return self.keys[0].1;
+        }
+        if next == self.keys.len() {
+            return self.keys[next - 1].1;
+        }
+
+        let ((time_a, a), (time_b, b)) = (self.keys[next - 1], self.keys[next]);
+        let s: f32 = (time - time_a) / (time_b - time_a);
+        match self.interpolation {
+            Interpolation::Step => a,
+            Interpolation::Linear => a + (b - a) * s,
+            Interpolation::CatmullRom => {
+                let before: T = match next {
+                    1 => a,
+                    n => self.keys[n - 2].1,
+                };
+                let after: T = self.keys.get(next + 1).map_or(b, |key| key.1);
+                let tangent_a: T = (b - before) * 0.5;
+                let tangent_b: T = (after - a) * 0.5;
+
+                // The cubic Hermite basis.
+                let (s2, s3): (f32, f32) = (s * s, s * s * s);
+                a * (2.0 * s3 - 3.0 * s2 + 1.0)
+                    + tangent_a * (s3 - 2.0 * s2 + s)
+                    + b * (3.0 * s2 - 2.0 * s3)
+                    + tangent_b * (s3 - s2)
+            }
+        }
+    }
+}
+
+/// The frames of an animation at a steady frame rate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sequence {
+    start: f32,
+    fps: f32,
+    frames: usize,
+    shutter_angle: f32,
+}
+
+/// When one frame of a `Sequence` is taken.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrameTime {
+    pub index: usize,
+    pub shutter_open: f32,
+    pub shutter_close: f32,
+}
+
+impl Sequence {
+    /// `frames` frames from `start`, with a 180° shutter.
+    pub fn new(start: f32, fps: f32, frames: usize) -> Self {
+        debug_assert!(0.0 < fps);
+        Self {
+            start,
+            fps,
+            frames,
+            shutter_angle: 180.0,
+        }
+    }
+
+    /// How much of each frame the shutter is open, 360° for all of it and 0°
+    /// for no motion blur.
+    pub fn set_shutter_angle(mut self, degrees: f32) -> Self {
+        debug_assert!((0.0..=360.0).contains(&degrees));
+        self.shutter_angle = degrees;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames == 0
+    }
+
+    pub fn frames(&self) -> impl Iterator<Item = FrameTime> + '_ {
+        (0..self.frames).map(|index| {
+            let shutter_open: f32 = self.start + index as f32 / self.fps;
+            FrameTime {
+                index,
+                shutter_open,
+                shutter_close: shutter_open + self.shutter_angle / 360.0 / self.fps,
+            }
+        })
+    }
+}
+
+impl FrameTime {
+    /// A file name numbered for the frame, such as `frame_0042.ppm`.
+    pub fn file_name(&self, prefix: &str, extension: &str) -> String {
+        format!("{prefix}{:04}.{extension}", self.index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use in_one_weekend::vec3::Vec3;
+
+    use super::*;
+
+    #[test]
+    fn track_should_interpolate_between_keys() {
+        let track = Track::new(vec![(2.0, 10.0), (0.0, 0.0), (1.0, 4.0)]);
+        assert_eq!(0.0, track.at(-1.0));
+        assert_eq!(2.0, track.at(0.5));
+        assert_eq!(7.0, track.at(1.5));
+        assert_eq!(10.0, track.at(3.0));
+        assert_eq!(
+            4.0,
+            track.clone().set_interpolation(Interpolation::Step).at(1.9)
+        );
+
+        // A smooth path through the keys, which overshoots none of them on a
+        // straight line.
+        let path = Track::new(vec![
+            (0.0, Vec3::new(0.0, 0.0, 0.0)),
+            (1.0, Vec3::new(1.0, 1.0, 0.0)),
+            (2.0, Vec3::new(2.0, 0.0, 0.0)),
+        ])
+        .set_interpolation(Interpolation::CatmullRom);
+        assert_eq!(Vec3::new(1.0, 1.0, 0.0), path.at(1.0));
+        let near_top: Vec3 = path.at(0.9);
+        assert!(0.9 < near_top.y() && near_top.y() < 1.0, "{near_top}");
+        let line = Track::new(vec![(0.0, 0.0), (1.0, 1.0), (2.0, 2.0), (3.0, 3.0)])
+            .set_interpolation(Interpolation::CatmullRom);
+        assert!((line.at(1.25) - 1.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn sequence_should_number_frames_and_open_the_shutter() {
+        let sequence = Sequence::new(1.0, 24.0, 48).set_shutter_angle(90.0);
+        let frames: Vec<FrameTime> = sequence.frames().collect();
+        assert_eq!(48, frames.len());
+        assert_eq!(1.0, frames[0].shutter_open);
+        assert!((frames[24].shutter_open - 2.0).abs() < 1e-6);
+        assert!((frames[24].shutter_close - frames[24].shutter_open - 1.0 / 96.0).abs() < 1e-6);
+        assert_eq!("frame_0024.ppm", frames[24].file_name("frame_", "ppm"));
+    }
+}
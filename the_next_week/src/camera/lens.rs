@@ -0,0 +1,266 @@
+use in_one_weekend::{point::Point3, vec3::Vec3};
+
+/// Number of times `LensSystem::film_distance` refines the film position for
+/// a finite focus distance.
+const FOCUS_ITERATIONS: usize = 16;
+
+/// One surface of a `LensSystem`, as listed in lens patents, in millimetres.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LensElement {
+    /// Radius of curvature, positive where the surface bulges towards the
+    /// scene. A radius of 0 marks the aperture stop, a flat opening in air.
+    pub radius: f32,
+    /// Distance along the axis to the next surface towards the film.
+    pub thickness: f32,
+    /// Index of refraction of the glass behind the surface, towards the film,
+    /// or 0 for air.
+    pub eta: f32,
+    /// Diameter of the surface.
+    pub aperture: f32,
+}
+
+impl LensElement {
+    pub fn new(radius: f32, thickness: f32, eta: f32, aperture: f32) -> Self {
+        Self {
+            radius,
+            thickness,
+            eta,
+            aperture,
+        }
+    }
+
+    fn eta_behind(&self) -> f32 {
+        match self.eta {
+            0.0 => 1.0,
+            eta => eta,
+        }
+    }
+}
+
+/// A real lens made of spherical glass surfaces, through which rays are
+/// traced on their way from the film. Rays stopped by the rims of the
+/// surfaces darken the corners of the image, as they do in real lenses.
+///
+/// It works in a frame of its own, in millimetres, where the film is at
+/// `z = 0` and the lens in front of it along `+z`.
+#[derive(Clone, Debug)]
+pub struct LensSystem {
+    /// From the scene towards the film; the thickness of the last one is
+    /// replaced by the distance to the film.
+    elements: Vec<LensElement>,
+}
+
+impl LensSystem {
+    pub fn new(elements: Vec<LensElement>) -> Self {
+        assert!(!elements.is_empty());
+        Self { elements }
+    }
+
+    /// A 50 mm f/2 double Gauss lens, the layout of most standard lenses.
+    pub fn double_gauss() -> Self {
+        Self::new(vec![
+            LensElement::new(29.475, 3.76, 1.67, 25.2),
+            LensElement::new(84.83, 0.12, 0.0, 25.2),
+            LensElement::new(19.275, 4.025, 1.67, 23.0),
+            LensElement::new(40.77, 3.275, 1.699, 23.0),
+            LensElement::new(12.75, 5.705, 0.0, 18.0),
+            LensElement::new(0.0, 4.5, 0.0, 17.1),
+            LensElement::new(-14.495, 1.18, 1.603, 17.0),
+            LensElement::new(40.77, 6.065, 1.658, 20.0),
+            LensElement::new(-20.385, 0.19, 0.0, 20.0),
+            LensElement::new(437.065, 3.22, 1.717, 20.0),
+            LensElement::new(-39.73, 0.0, 0.0, 20.0),
+        ])
+    }
+
+    pub fn elements(&self) -> &[LensElement] {
+        &self.elements
+    }
+
+    /// Close the aperture stop, if there is one, to at most `diameter`.
+    pub fn set_stop_diameter(mut self, diameter: f32) -> Self {
+        self.elements
+            .iter_mut()
+            .filter(|element| element.radius == 0.0)
+            .for_each(|stop| stop.aperture = stop.aperture.min(diameter));
+        self
+    }
+
+    /// Radius of the surface nearest to the film.
+    pub fn rear_radius(&self) -> f32 {
+        self.elements.last().expect("not empty").aperture / 2.0
+    }
+
+    /// The effective focal length, from a ray coming in parallel to the axis,
+    /// or `None` if no such ray makes it through.
+    pub fn focal_length(&self) -> Option<f32> {
+        let height: f32 = self.elements[0].aperture * 0.01;
+        let (_, direction) = self.trace_from_scene(
+            0.0,
+            Point3::new(height, 0.0, self.front_z(0.0) + 1.0),
+            Vec3::new(0.0, 0.0, -1.0),
+        )?;
+
+        (direction.x() < 0.0).then(|| height * direction.z() / direction.x())
+    }
+
+    /// How far behind the lens the film must be for points `focus_distance`
+    /// in front of the film to be sharp, with 0 focusing at infinity. `None`
+    /// if the lens cannot focus that close.
+    pub fn film_distance(&self, focus_distance: f32) -> Option<f32> {
+        let height: f32 = self.elements[0].aperture * 0.01;
+
+        // Where rays from an object at `z`, traced with the film distance at
+        // 0, cross the axis behind the lens.
+        let image_z = |object_z: Option<f32>| -> Option<f32> {
+            let front: f32 = self.front_z(0.0);
+            let (origin, direction): (Point3, Vec3) = match object_z {
+                None => (
+                    Point3::new(height, 0.0, front + 1.0),
+                    Vec3::new(0.0, 0.0, -1.0),
+                ),
+                Some(z) => (
+                    Point3::new(0.0, 0.0, z),
+                    Point3::new(height, 0.0, front) - Point3::new(0.0, 0.0, z),
+                ),
+            };
+            let (origin, direction) = self.trace_from_scene(0.0, origin, direction)?;
+            if direction.x() == 0.0 || direction.z() >= 0.0 {
+                return None;
+            }
+            Some(origin.z() - origin.x() * direction.z() / direction.x())
+        };
+
+        let mut distance: f32 = -image_z(None)?;
+        if focus_distance != 0.0 {
+            for _ in 0..FOCUS_ITERATIONS {
+                let object_z: f32 = focus_distance - distance;
+                if object_z <= self.front_z(0.0) {
+                    return None;
+                }
+                distance = -image_z(Some(object_z))?;
+            }
+        }
+
+        (0.0 < distance).then_some(distance)
+    }
+
+    /// The axial position of the front surface.
+    fn front_z(&self, film_distance: f32) -> f32 {
+        self.surface_z(film_distance)[0]
+    }
+
+    /// The axial positions of the surface vertices.
+    fn surface_z(&self, film_distance: f32) -> Vec<f32> {
+        let mut z: Vec<f32> = vec![film_distance; self.elements.len()];
+        for i in (0..self.elements.len() - 1).rev() {
+            z[i] = z[i + 1] + self.elements[i].thickness;
+        }
+        z
+    }
+
+    /// The ray leaving the front of the lens for one leaving the film from
+    /// `origin` towards `direction`, or `None` if it is stopped inside.
+    pub fn trace_from_film(
+        &self,
+        film_distance: f32,
+        origin: Point3,
+        direction: Vec3,
+    ) -> Option<(Point3, Vec3)> {
+        self.trace(film_distance, origin, direction, true)
+    }
+
+    fn trace_from_scene(
+        &self,
+        film_distance: f32,
+        origin: Point3,
+        direction: Vec3,
+    ) -> Option<(Point3, Vec3)> {
+        self.trace(film_distance, origin, direction, false)
+    }
+
+    fn trace(
+        &self,
+        film_distance: f32,
+        mut origin: Point3,
+        direction: Vec3,
+        from_film: bool,
+    ) -> Option<(Point3, Vec3)> {
+        let z: Vec<f32> = self.surface_z(film_distance);
+        let mut direction: Vec3 = direction.unit_vector();
+        let order: Vec<usize> = match from_film {
+            true => (0..self.elements.len()).rev().collect(),
+            false => (0..self.elements.len()).collect(),
+        };
+
+        for i in order {
+            let element: &LensElement = &self.elements[i];
+            let (t, normal): (f32, Option<Vec3>) = match element.radius {
+                0.0 => ((z[i] - origin.z()) / direction.z(), None),
+                radius => {
+                    let center: Point3 = Point3::new(0.0, 0.0, z[i] - radius);
+                    let t: f32 = sphere_hit(origin, direction, center, radius)?;
+                    (t, Some((origin + t * direction - center).unit_vector()))
+                }
+            };
+            if t.is_nan() || t <= 0.0 {
+                return None;
+            }
+
+            origin += t * direction;
+            let r: f32 = element.aperture / 2.0;
+            if r * r < origin.x() * origin.x() + origin.y() * origin.y() {
+                return None;
+            }
+
+            if let Some(normal) = normal {
+                let eta_front: f32 = match i {
+                    0 => 1.0,
+                    i => self.elements[i - 1].eta_behind(),
+                };
+                let (eta_from, eta_to): (f32, f32) = match from_film {
+                    true => (element.eta_behind(), eta_front),
+                    false => (eta_front, element.eta_behind()),
+                };
+                let normal: Vec3 = match normal.dot(direction) < 0.0 {
+                    true => normal,
+                    false => -normal,
+                };
+                direction = refract(direction, normal, eta_from / eta_to)?;
+            }
+        }
+
+        Some((origin, direction))
+    }
+}
+
+/// Distance along the unit `direction` to the cap of the sphere on the side
+/// of its vertex, where its radius points.
+fn sphere_hit(origin: Point3, direction: Vec3, center: Point3, radius: f32) -> Option<f32> {
+    let oc: Vec3 = origin - center;
+    let half_b: f32 = oc.dot(direction);
+    let c: f32 = oc.len_squared() - radius * radius;
+    let discriminant: f32 = half_b * half_b - c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let root: f32 = discriminant.sqrt();
+    [-half_b - root, -half_b + root].into_iter().find(|&t| {
+        let z: f32 = origin.z() + t * direction.z() - center.z();
+        0.0 < t && (0.0 < z) == (0.0 < radius)
+    })
+}
+
+/// `direction` bent through a surface whose `normal` faces it, or `None` on
+/// total internal reflection.
+fn refract(direction: Vec3, normal: Vec3, eta_ratio: f32) -> Option<Vec3> {
+    let cos_i: f32 = -direction.dot(normal);
+    let sin2_t: f32 = eta_ratio * eta_ratio * (1.0 - cos_i * cos_i).max(0.0);
+    if 1.0 < sin2_t {
+        return None;
+    }
+    let cos_t: f32 = (1.0 - sin2_t).sqrt();
+
+    Some(eta_ratio * direction + (eta_ratio * cos_i - cos_t) * normal)
+}
@@ -0,0 +1,490 @@
+mod lens;
+mod physical;
+mod stereo;
+mod timeline;
+
+use std::f32::consts::{PI, TAU};
+
+use crate::{ray::Ray, sampler::Sampler};
+use in_one_weekend::{point::Point3, vec3::Vec3};
+
+pub use self::{
+    lens::{LensElement, LensSystem},
+    physical::{Aperture, PhysicalCamera, PhysicalCameraBuilder},
+    stereo::{Eye, OmniStereo, Stereo, StereoBuilder, StereoLayout},
+    timeline::{AnimatedCamera, CameraTimeline},
+};
+
+/// How a camera maps the image to rays.
+///
+/// `u` runs from 0 at the left edge of the image to 1 at the right, and `v`
+/// from 0 at the bottom to 1 at the top. Points outside of what the
+/// projection covers, such as the corners around a circular fisheye image,
+/// have no ray and should be rendered black. The lens and shutter time are
+/// sampled from `sampler`.
+pub trait Projection: Send + Sync {
+    fn get_ray(&self, u: f32, v: f32, sampler: &mut dyn Sampler) -> Option<Ray>;
+
+    /// Factor the light reaching the image is scaled by.
+    fn exposure(&self) -> f32 {
+        1.0
+    }
+}
+
+pub struct Camera {
+    origin: Point3,
+    lower_left_corner: Point3,
+    horizontal: Vec3,
+    vertical: Vec3,
+    u: Vec3,
+    v: Vec3,
+    #[allow(unused)]
+    w: Vec3,
+    lens_radius: f32,
+    time_0: f32,
+    time_1: f32,
+}
+
+impl Camera {
+    pub fn builder() -> CameraBuilder {
+        CameraBuilder::default()
+    }
+
+    pub fn get_ray(&self, u: f32, v: f32, sampler: &mut dyn Sampler) -> Ray {
+        let rd: Vec3 = self.lens_radius * sampler.in_unit_disk();
+        let offset: Vec3 = self.u * rd.x() + self.v * rd.y();
+
+        Ray::new(
+            self.origin + offset,
+            self.lower_left_corner + u * self.horizontal + v * self.vertical - self.origin - offset,
+            shutter_time(self.time_0, self.time_1, sampler),
+        )
+    }
+}
+
+/// A time while the shutter is open, which is `time_0` for a shutter that
+/// opens and closes at once.
+fn shutter_time(time_0: f32, time_1: f32, sampler: &mut dyn Sampler) -> f32 {
+    match time_0 < time_1 {
+        true => time_0 + sampler.get_1d() * (time_1 - time_0),
+        false => time_0,
+    }
+}
+
+impl Projection for Camera {
+    fn get_ray(&self, u: f32, v: f32, sampler: &mut dyn Sampler) -> Option<Ray> {
+        Some(Camera::get_ray(self, u, v, sampler))
+    }
+}
+
+/// Where a camera is and which way it looks, for the projections other than
+/// the thin lens `Camera`.
+struct Frame {
+    origin: Point3,
+    /// Right, up, and backwards from the view direction.
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    time_0: f32,
+    time_1: f32,
+}
+
+impl Frame {
+    /// A ray from `origin` towards `direction`, given as right, up and
+    /// forward components.
+    fn ray(&self, origin: Point3, direction: Vec3, sampler: &mut dyn Sampler) -> Ray {
+        Ray::new(
+            origin,
+            self.vector(direction),
+            shutter_time(self.time_0, self.time_1, sampler),
+        )
+    }
+
+    /// `local` given as right, up and forward components.
+    fn vector(&self, local: Vec3) -> Vec3 {
+        local.x() * self.u + local.y() * self.v - local.z() * self.w
+    }
+
+    /// The point `local` away from the origin, as for `vector`.
+    fn point(&self, local: Vec3) -> Point3 {
+        self.origin + self.vector(local)
+    }
+}
+
+/// Parallel rays from a rectangle facing the view direction, e.g. for
+/// architectural elevations.
+pub struct Orthographic {
+    frame: Frame,
+    width: f32,
+    height: f32,
+}
+
+impl Projection for Orthographic {
+    fn get_ray(&self, u: f32, v: f32, sampler: &mut dyn Sampler) -> Option<Ray> {
+        let origin: Point3 = self.frame.origin
+            + (u - 0.5) * self.width * self.frame.u
+            + (v - 0.5) * self.height * self.frame.v;
+
+        Some(self.frame.ray(origin, Vec3::new(0.0, 0.0, 1.0), sampler))
+    }
+}
+
+/// How a `Fisheye` spaces the angle from the view direction over the
+/// distance from the centre of the image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FisheyeMapping {
+    /// Distance proportional to the angle, so that all directions up to
+    /// behind the camera fit.
+    Equidistant,
+    /// Area in the image proportional to solid angle.
+    Equisolid,
+}
+
+/// A circular fisheye image whose circle spans the height of the image and
+/// the vertical field of view.
+pub struct Fisheye {
+    frame: Frame,
+    aspect_ratio: f32,
+    half_fov: f32,
+    mapping: FisheyeMapping,
+}
+
+impl Projection for Fisheye {
+    fn get_ray(&self, u: f32, v: f32, sampler: &mut dyn Sampler) -> Option<Ray> {
+        let x: f32 = (2.0 * u - 1.0) * self.aspect_ratio;
+        let y: f32 = 2.0 * v - 1.0;
+        let r: f32 = (x * x + y * y).sqrt();
+        if 1.0 < r {
+            return None;
+        }
+
+        let theta: f32 = match self.mapping {
+            FisheyeMapping::Equidistant => r * self.half_fov,
+            FisheyeMapping::Equisolid => 2.0 * (r * (self.half_fov / 2.0).sin()).asin(),
+        };
+        let (sin, cos): (f32, f32) = theta.sin_cos();
+        let direction: Vec3 = match r {
+            0.0 => Vec3::new(0.0, 0.0, 1.0),
+            r => Vec3::new(sin * x / r, sin * y / r, cos),
+        };
+
+        Some(self.frame.ray(self.frame.origin, direction, sampler))
+    }
+}
+
+/// All directions around the camera, by longitude across the image and
+/// latitude up it, as used for 360° panoramas. The view direction is in the
+/// middle of the image.
+pub struct Equirectangular {
+    frame: Frame,
+}
+
+impl Projection for Equirectangular {
+    fn get_ray(&self, u: f32, v: f32, sampler: &mut dyn Sampler) -> Option<Ray> {
+        let (sin_phi, cos_phi): (f32, f32) = ((u - 0.5) * TAU).sin_cos();
+        let (sin_theta, cos_theta): (f32, f32) = ((v - 0.5) * PI).sin_cos();
+
+        Some(self.frame.ray(
+            self.frame.origin,
+            Vec3::new(cos_theta * sin_phi, sin_theta, cos_theta * cos_phi),
+            sampler,
+        ))
+    }
+}
+
+/// All directions around the camera, as the six faces of a cube laid out in
+/// a 3×2 grid, each a 90° perspective view. In the frame where x is right, y
+/// up and z behind the camera, the top row holds +x, −x, +y and the bottom
+/// row −y, +z, −z, as for OpenGL cube maps; render it with an aspect ratio of
+/// 3:2.
+pub struct CubeMap {
+    frame: Frame,
+}
+
+impl CubeMap {
+    /// Face direction and its up direction, in the order of the grid.
+    const FACES: [(Vec3, Vec3); 6] = [
+        (Vec3(1.0, 0.0, 0.0), Vec3(0.0, 1.0, 0.0)),
+        (Vec3(-1.0, 0.0, 0.0), Vec3(0.0, 1.0, 0.0)),
+        (Vec3(0.0, 1.0, 0.0), Vec3(0.0, 0.0, 1.0)),
+        (Vec3(0.0, -1.0, 0.0), Vec3(0.0, 0.0, -1.0)),
+        (Vec3(0.0, 0.0, 1.0), Vec3(0.0, 1.0, 0.0)),
+        (Vec3(0.0, 0.0, -1.0), Vec3(0.0, 1.0, 0.0)),
+    ];
+}
+
+impl Projection for CubeMap {
+    fn get_ray(&self, u: f32, v: f32, sampler: &mut dyn Sampler) -> Option<Ray> {
+        let column: f32 = (u * 3.0).clamp(0.0, 2.999);
+        let row: f32 = ((1.0 - v) * 2.0).clamp(0.0, 1.999);
+        let (forward, up): (Vec3, Vec3) = Self::FACES[row as usize * 3 + column as usize];
+        let right: Vec3 = forward.cross(up);
+        let a: f32 = 2.0 * column.fract() - 1.0;
+        let b: f32 = 1.0 - 2.0 * row.fract();
+
+        // In the frame the faces are given in, +z points behind the camera.
+        let direction: Vec3 = forward + a * right + b * up;
+        Some(self.frame.ray(
+            self.frame.origin,
+            Vec3::new(direction.x(), direction.y(), -direction.z()),
+            sampler,
+        ))
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct CameraBuilder {
+    look_from: Point3,
+    look_at: Point3,
+    up: Vec3,
+    v_fov: f32, // vertical field-of-view in degrees
+    aspect_ratio: f32,
+    aperture: f32,
+    focus_dist: f32,
+    view_height: f32, // height of the view of an orthographic camera
+    time_0: f32,      // shutter open times
+    time_1: f32,      // shutter close times
+}
+
+impl CameraBuilder {
+    pub fn look_from(mut self, point: Point3) -> Self {
+        self.look_from = point;
+        self
+    }
+
+    pub fn look_at(mut self, point: Point3) -> Self {
+        self.look_at = point;
+        self
+    }
+
+    pub fn up(mut self, direction: Vec3) -> Self {
+        self.up = direction;
+        self
+    }
+
+    pub fn fov(mut self, degree: f32) -> Self {
+        self.v_fov = degree;
+        self
+    }
+
+    pub fn aspect_ratio(mut self, ratio: f32) -> Self {
+        self.aspect_ratio = ratio;
+        self
+    }
+
+    pub fn aperture(mut self, diameter: f32) -> Self {
+        self.aperture = diameter;
+        self
+    }
+
+    pub fn focus_dist(mut self, distance: f32) -> Self {
+        self.focus_dist = distance;
+        self
+    }
+
+    pub fn view_height(mut self, height: f32) -> Self {
+        self.view_height = height;
+        self
+    }
+
+    pub fn time_0(mut self, time: f32) -> Self {
+        self.time_0 = time;
+        self
+    }
+
+    pub fn time_1(mut self, time: f32) -> Self {
+        self.time_1 = time;
+        self
+    }
+
+    pub fn build(&self) -> Camera {
+        debug_assert!(self.time_0 <= self.time_1);
+
+        let theta: f32 = self.v_fov.to_radians();
+        let h: f32 = (theta / 2.0).tan();
+        let viewport_height: f32 = 2.0 * h;
+        let viewport_width: f32 = self.aspect_ratio * viewport_height;
+
+        let w: Vec3 = (self.look_from - self.look_at).unit_vector();
+        let u: Vec3 = (self.up.cross(w)).unit_vector();
+        let v: Vec3 = w.cross(u);
+
+        let origin: Point3 = self.look_from;
+        let horizontal: Vec3 = self.focus_dist * viewport_width * u;
+        let vertical: Vec3 = self.focus_dist * viewport_height * v;
+        let lower_left_corner: Point3 =
+            origin - horizontal / 2.0 - vertical / 2.0 - self.focus_dist * w;
+
+        let lens_radius: f32 = self.aperture / 2.0;
+
+        Camera {
+            origin,
+            lower_left_corner,
+            horizontal,
+            vertical,
+            u,
+            v,
+            w,
+            lens_radius,
+            time_0: self.time_0,
+            time_1: self.time_1,
+        }
+    }
+
+    /// An orthographic camera seeing `view_height` across the height of the
+    /// image.
+    pub fn build_orthographic(&self) -> Orthographic {
+        Orthographic {
+            frame: self.frame(),
+            width: self.aspect_ratio * self.view_height,
+            height: self.view_height,
+        }
+    }
+
+    /// A fisheye camera with `fov` across the height of the image.
+    pub fn build_fisheye(&self, mapping: FisheyeMapping) -> Fisheye {
+        debug_assert!(0.0 < self.v_fov && self.v_fov <= 360.0);
+        Fisheye {
+            frame: self.frame(),
+            aspect_ratio: self.aspect_ratio,
+            half_fov: self.v_fov.to_radians() / 2.0,
+            mapping,
+        }
+    }
+
+    /// A 360° camera, best rendered with an aspect ratio of 2:1.
+    pub fn build_equirectangular(&self) -> Equirectangular {
+        Equirectangular {
+            frame: self.frame(),
+        }
+    }
+
+    pub fn build_cube_map(&self) -> CubeMap {
+        CubeMap {
+            frame: self.frame(),
+        }
+    }
+
+    fn frame(&self) -> Frame {
+        debug_assert!(self.time_0 <= self.time_1);
+
+        let w: Vec3 = (self.look_from - self.look_at).unit_vector();
+        let u: Vec3 = (self.up.cross(w)).unit_vector();
+        let v: Vec3 = w.cross(u);
+
+        Frame {
+            origin: self.look_from,
+            u,
+            v,
+            w,
+            time_0: self.time_0,
+            time_1: self.time_1,
+        }
+    }
+}
+
+impl Default for CameraBuilder {
+    fn default() -> Self {
+        Self {
+            look_from: Point3::new(0.0, 0.0, 0.0),
+            look_at: Point3::new(0.0, 0.0, -1.0),
+            up: Vec3::new(0.0, 1.0, 0.0),
+            v_fov: 90.0,
+            aspect_ratio: 1.0,
+            aperture: 0.0,
+            focus_dist: 0.0,
+            view_height: 2.0,
+            time_0: f32::NEG_INFINITY,
+            time_1: f32::INFINITY,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::Independent;
+
+    fn builder() -> CameraBuilder {
+        Camera::builder()
+            .look_from(Point3::new(0.0, 0.0, 5.0))
+            .look_at(Point3::new(0.0, 0.0, 0.0))
+            .time_0(0.0)
+            .time_1(1.0)
+    }
+
+    fn direction(projection: &dyn Projection, u: f32, v: f32) -> Vec3 {
+        projection
+            .get_ray(u, v, &mut Independent::new())
+            .unwrap()
+            .direction()
+            .unit_vector()
+    }
+
+    fn assert_near(expected: Vec3, actual: Vec3) {
+        assert!((expected - actual).len() < 1e-5, "{actual}");
+    }
+
+    #[test]
+    fn projections_should_look_along_the_view_direction() {
+        let forward: Vec3 = Vec3::new(0.0, 0.0, -1.0);
+
+        let orthographic = builder()
+            .aspect_ratio(2.0)
+            .view_height(4.0)
+            .build_orthographic();
+        let ray: Ray = orthographic
+            .get_ray(1.0, 0.0, &mut Independent::new())
+            .unwrap();
+        assert_near(Point3::new(4.0, -2.0, 5.0), ray.origin());
+        assert_near(forward, ray.direction());
+
+        let fisheye = builder()
+            .fov(180.0)
+            .build_fisheye(FisheyeMapping::Equidistant);
+        assert_near(forward, direction(&fisheye, 0.5, 0.5));
+        assert_near(Vec3::new(0.0, 1.0, 0.0), direction(&fisheye, 0.5, 1.0));
+        assert!(fisheye.get_ray(1.0, 1.0, &mut Independent::new()).is_none());
+        let equisolid = builder()
+            .fov(180.0)
+            .build_fisheye(FisheyeMapping::Equisolid);
+        assert_near(Vec3::new(-1.0, 0.0, 0.0), direction(&equisolid, 0.0, 0.5));
+        // Equal areas: half way out sees less than half way round.
+        let half_way: Vec3 = direction(&equisolid, 0.75, 0.5);
+        assert!(half_way.x() < direction(&fisheye, 0.75, 0.5).x());
+
+        let equirectangular = builder().build_equirectangular();
+        assert_near(forward, direction(&equirectangular, 0.5, 0.5));
+        assert_near(
+            Vec3::new(1.0, 0.0, 0.0),
+            direction(&equirectangular, 0.75, 0.5),
+        );
+        assert_near(
+            Vec3::new(0.0, 0.0, 1.0),
+            direction(&equirectangular, 0.0, 0.5),
+        );
+        assert_near(
+            Vec3::new(0.0, -1.0, 0.0),
+            direction(&equirectangular, 0.3, 0.0),
+        );
+
+        // The centres of the faces in the order of the grid.
+        let cube_map = builder().build_cube_map();
+        [
+            ((1.0, 0.75), Vec3::new(1.0, 0.0, 0.0)),
+            ((3.0, 0.75), Vec3::new(-1.0, 0.0, 0.0)),
+            ((5.0, 0.75), Vec3::new(0.0, 1.0, 0.0)),
+            ((1.0, 0.25), Vec3::new(0.0, -1.0, 0.0)),
+            ((3.0, 0.25), Vec3::new(0.0, 0.0, 1.0)),
+            ((5.0, 0.25), forward),
+        ]
+        .into_iter()
+        .for_each(|((sixths, v), expected)| {
+            assert_near(expected, direction(&cube_map, sixths / 6.0, v));
+        });
+        // The top edge of the forward face meets the forward edge of the up face.
+        let edge: Vec3 = Vec3::new(0.0, 1.0, -1.0).unit_vector();
+        assert_near(edge, direction(&cube_map, 5.0 / 6.0, 0.5));
+        assert_near(edge, direction(&cube_map, 5.0 / 6.0, 0.5 + 1e-6));
+    }
+}
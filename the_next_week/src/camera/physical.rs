@@ -0,0 +1,401 @@
+use std::f32::consts::TAU;
+
+use in_one_weekend::{point::Point3, vec3::Vec3};
+
+use crate::{ray::Ray, sampler::Sampler, textures::Texture};
+
+use super::{CameraBuilder, Frame, LensSystem, Projection};
+
+/// Times `Aperture::Mask` is sampled before giving up on a ray.
+const MASK_ATTEMPTS: usize = 64;
+
+/// The shape of the lens opening, which out of focus highlights take.
+pub enum Aperture {
+    Circle,
+    /// A regular polygon with `blades` corners, turned by `rotation` degrees
+    /// from having one at the top.
+    Polygon {
+        blades: u32,
+        rotation: f32,
+    },
+    /// The bright parts of a texture covering the square around the opening,
+    /// such as a star cut out of card. Mostly dark masks make rays slow to
+    /// find, and some are lost.
+    Mask(Box<dyn Texture + Send + Sync>),
+}
+
+impl Aperture {
+    /// A random point of the opening, scaled to fit in `[-1, 1]²`.
+    fn sample(&self, sampler: &mut dyn Sampler) -> Option<(f32, f32)> {
+        match self {
+            Aperture::Circle => {
+                let p: Vec3 = sampler.in_unit_disk();
+                Some((p.x(), p.y()))
+            }
+            Aperture::Polygon { blades, rotation } => {
+                // A point of a random one of the triangles the polygon splits
+                // into around its centre.
+                let sector: f32 = (sampler.get_1d() * *blades as f32).floor();
+                let step: f32 = TAU / *blades as f32;
+                let corner = |k: f32| {
+                    let angle: f32 = rotation.to_radians() + k * step;
+                    (-angle.sin(), angle.cos())
+                };
+                let (a, b) = (corner(sector), corner(sector + 1.0));
+                let [mut s, mut t]: [f32; 2] = sampler.get_2d();
+                if 1.0 < s + t {
+                    (s, t) = (1.0 - s, 1.0 - t);
+                }
+                Some((s * a.0 + t * b.0, s * a.1 + t * b.1))
+            }
+            Aperture::Mask(texture) => (0..MASK_ATTEMPTS).find_map(|_| {
+                let [x, y]: [f32; 2] = sampler.get_2d();
+                let color = texture.value(x, y, &Point3::new(x, y, 0.0));
+                let luminance: f32 = 0.2126 * color.r() + 0.7152 * color.g() + 0.0722 * color.b();
+                (sampler.get_1d() < luminance).then_some((2.0 * x - 1.0, 2.0 * y - 1.0))
+            }),
+        }
+    }
+}
+
+/// Sets up a `PhysicalCamera` from the settings of a real one, on top of the
+/// placement, aspect ratio, focus distance and shutter times of a
+/// `CameraBuilder`.
+///
+/// The image comes out `shutter · iso / (100 · f_stop²)` times as bright as
+/// the scene, i.e. at its brightness for settings of 0 EV, such as f/1 for 1 s
+/// at ISO 100. Motion blur still spans `time_0` to `time_1`, in scene time.
+pub struct PhysicalCameraBuilder {
+    camera: CameraBuilder,
+    focal_length: f32,
+    sensor_width: f32,
+    f_stop: f32,
+    shutter: f32,
+    iso: f32,
+    exposure_compensation: f32,
+    aperture: Aperture,
+    lens: Option<LensSystem>,
+    units_per_meter: f32,
+}
+
+impl CameraBuilder {
+    /// A physical camera with a 50 mm lens on a 36 mm wide sensor, set to
+    /// f/8, 1/125 s and ISO 100. Without a focus distance, it focuses on
+    /// `look_at`.
+    pub fn physical(self) -> PhysicalCameraBuilder {
+        PhysicalCameraBuilder {
+            camera: self,
+            focal_length: 50.0,
+            sensor_width: 36.0,
+            f_stop: 8.0,
+            shutter: 1.0 / 125.0,
+            iso: 100.0,
+            exposure_compensation: 0.0,
+            aperture: Aperture::Circle,
+            lens: None,
+            units_per_meter: 1.0,
+        }
+    }
+}
+
+impl PhysicalCameraBuilder {
+    /// In millimetres; sets the field of view together with `sensor_width`.
+    pub fn focal_length(mut self, millimetres: f32) -> Self {
+        self.focal_length = millimetres;
+        self
+    }
+
+    /// In millimetres; the height follows from the aspect ratio.
+    pub fn sensor_width(mut self, millimetres: f32) -> Self {
+        self.sensor_width = millimetres;
+        self
+    }
+
+    pub fn f_stop(mut self, f_stop: f32) -> Self {
+        self.f_stop = f_stop;
+        self
+    }
+
+    /// In seconds.
+    pub fn shutter(mut self, seconds: f32) -> Self {
+        self.shutter = seconds;
+        self
+    }
+
+    pub fn iso(mut self, iso: f32) -> Self {
+        self.iso = iso;
+        self
+    }
+
+    /// In stops, added to the exposure the settings give.
+    pub fn exposure_compensation(mut self, stops: f32) -> Self {
+        self.exposure_compensation = stops;
+        self
+    }
+
+    /// Used by the thin lens only; a `LensSystem` has a round stop.
+    pub fn aperture(mut self, aperture: Aperture) -> Self {
+        self.aperture = aperture;
+        self
+    }
+
+    /// Trace through a real lens instead of a thin one. Its own focal length
+    /// replaces `focal_length`, and its stop closes down to `f_stop`.
+    pub fn lens(mut self, lens: LensSystem) -> Self {
+        self.lens = Some(lens);
+        self
+    }
+
+    /// Size of a metre in scene units, to size the lens opening.
+    pub fn units_per_meter(mut self, units: f32) -> Self {
+        self.units_per_meter = units;
+        self
+    }
+
+    /// # Panics
+    ///
+    /// If the lens system lets no light through or cannot focus at the
+    /// focus distance.
+    pub fn build(self) -> PhysicalCamera {
+        let frame: Frame = self.camera.frame();
+        let sensor_height: f32 = self.sensor_width / self.camera.aspect_ratio;
+        let millimetre: f32 = self.units_per_meter / 1000.0;
+        let focus_dist: f32 = match self.camera.focus_dist {
+            0.0 => (self.camera.look_at - self.camera.look_from).len(),
+            distance => distance,
+        };
+        let exposure: f32 = self.shutter * self.iso / (100.0 * self.f_stop * self.f_stop)
+            * self.exposure_compensation.exp2();
+
+        let optics: Optics = match self.lens {
+            None => {
+                let scale: f32 = focus_dist / self.focal_length / 2.0;
+                Optics::Thin {
+                    aperture: self.aperture,
+                    lens_radius: self.focal_length / self.f_stop / 2.0 * millimetre,
+                    focus_dist,
+                    half_width: self.sensor_width * scale,
+                    half_height: sensor_height * scale,
+                }
+            }
+            Some(lens) => {
+                let focal_length: f32 = lens.focal_length().expect("light through the lens");
+                let lens = lens.set_stop_diameter(focal_length / self.f_stop);
+                let film_distance: f32 = lens
+                    .film_distance(focus_dist / millimetre)
+                    .expect("the lens focuses at the focus distance");
+                Optics::System {
+                    lens,
+                    film_distance,
+                    film_width: self.sensor_width,
+                    film_height: sensor_height,
+                    millimetre,
+                }
+            }
+        };
+
+        PhysicalCamera {
+            frame,
+            optics,
+            exposure,
+        }
+    }
+}
+
+enum Optics {
+    /// Sizes in scene units.
+    Thin {
+        aperture: Aperture,
+        lens_radius: f32,
+        focus_dist: f32,
+        /// Of the view at the focus distance.
+        half_width: f32,
+        half_height: f32,
+    },
+    /// Sizes in millimetres, and the size of one in scene units.
+    System {
+        lens: LensSystem,
+        film_distance: f32,
+        film_width: f32,
+        film_height: f32,
+        millimetre: f32,
+    },
+}
+
+/// A camera set up like a real one: the field of view follows from the focal
+/// length and sensor size, the depth of field from the f-stop, and the
+/// brightness of the image from the f-stop, shutter and ISO.
+pub struct PhysicalCamera {
+    frame: Frame,
+    optics: Optics,
+    exposure: f32,
+}
+
+impl Projection for PhysicalCamera {
+    fn get_ray(&self, u: f32, v: f32, sampler: &mut dyn Sampler) -> Option<Ray> {
+        match &self.optics {
+            Optics::Thin {
+                aperture,
+                lens_radius,
+                focus_dist,
+                half_width,
+                half_height,
+            } => {
+                let (x, y) = aperture.sample(sampler)?;
+                let origin: Vec3 = Vec3::new(lens_radius * x, lens_radius * y, 0.0);
+                let target: Vec3 = Vec3::new(
+                    (2.0 * u - 1.0) * half_width,
+                    (2.0 * v - 1.0) * half_height,
+                    *focus_dist,
+                );
+                Some(
+                    self.frame
+                        .ray(self.frame.point(origin), target - origin, sampler),
+                )
+            }
+            Optics::System {
+                lens,
+                film_distance,
+                film_width,
+                film_height,
+                millimetre,
+            } => {
+                // The lens turns the image upside down.
+                let film: Point3 =
+                    Point3::new((0.5 - u) * film_width, (0.5 - v) * film_height, 0.0);
+                let rear: Vec3 = lens.rear_radius() * sampler.in_unit_disk();
+                let towards: Point3 = Point3::new(rear.x(), rear.y(), *film_distance);
+                let (origin, direction) =
+                    lens.trace_from_film(*film_distance, film, towards - film)?;
+                Some(
+                    self.frame
+                        .ray(self.frame.point(*millimetre * origin), direction, sampler),
+                )
+            }
+        }
+    }
+
+    fn exposure(&self) -> f32 {
+        self.exposure
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use in_one_weekend::color::ColorRGBMapTo0_1;
+
+    use super::*;
+    use crate::{camera::Camera, sampler::Independent};
+
+    fn builder() -> CameraBuilder {
+        Camera::builder()
+            .look_from(Point3::new(0.0, 0.0, 0.0))
+            .look_at(Point3::new(0.0, 0.0, -4.0))
+            .aspect_ratio(1.5)
+            .time_0(0.0)
+            .time_1(1.0)
+    }
+
+    /// Lit only on its left half.
+    struct HalfMask;
+
+    impl Texture for HalfMask {
+        fn value(&self, u: f32, _v: f32, _p: &Point3) -> ColorRGBMapTo0_1 {
+            match u < 0.5 {
+                true => ColorRGBMapTo0_1::new(1.0, 1.0, 1.0),
+                false => ColorRGBMapTo0_1::new(0.0, 0.0, 0.0),
+            }
+        }
+    }
+
+    #[test]
+    fn physical_camera_should_follow_its_settings() {
+        let camera = builder()
+            .physical()
+            .focal_length(24.0)
+            .f_stop(2.0)
+            .shutter(0.5)
+            .iso(400.0)
+            .build();
+        assert!((camera.exposure() - 0.5).abs() < 1e-6);
+
+        // Rays through the top edge meet the focus plane 12 / 24 of its
+        // distance above the axis, whichever point of the lens they leave.
+        (0..100).for_each(|_| {
+            let ray: Ray = camera.get_ray(0.5, 1.0, &mut Independent::new()).unwrap();
+            let at_focus: Point3 = ray.at(-4.0 / ray.direction().z());
+            assert!((at_focus - Point3::new(0.0, 2.0, -4.0)).len() < 1e-4);
+            // The opening is 12 mm across.
+            assert!(ray.origin().len() <= 0.006 + 1e-6);
+        });
+
+        let hexagon = builder()
+            .physical()
+            .aperture(Aperture::Polygon {
+                blades: 6,
+                rotation: 0.0,
+            })
+            .units_per_meter(1000.0)
+            .build();
+        let radius: f32 = 50.0 / 8.0 / 2.0;
+        let apothem: f32 = radius * (TAU / 12.0).cos();
+        let mut widest: f32 = 0.0;
+        (0..1000).for_each(|_| {
+            let origin: Point3 = hexagon
+                .get_ray(0.5, 0.5, &mut Independent::new())
+                .unwrap()
+                .origin();
+            assert!(origin.len() <= radius + 1e-4);
+            // One corner is at the top, so the sides are flat.
+            assert!(origin.x().abs() <= apothem + 1e-4);
+            widest = widest.max(origin.y().abs());
+        });
+        assert!(apothem < widest);
+
+        let half = builder()
+            .physical()
+            .aperture(Aperture::Mask(Box::new(HalfMask)))
+            .build();
+        assert!((0..100).all(|_| half
+            .get_ray(0.5, 0.5, &mut Independent::new())
+            .unwrap()
+            .origin()
+            .x()
+            <= 0.0));
+    }
+
+    #[test]
+    fn lens_system_should_focus_and_vignette() {
+        let lens = LensSystem::double_gauss();
+        let focal_length: f32 = lens.focal_length().unwrap();
+        assert!((45.0..55.0).contains(&focal_length), "{focal_length}");
+        let at_infinity: f32 = lens.film_distance(0.0).unwrap();
+        let at_one_metre: f32 = lens.film_distance(1000.0).unwrap();
+        assert!(at_infinity < at_one_metre);
+
+        // Light from the point in focus on the axis converges on the film.
+        let camera = builder()
+            .focus_dist(1.0)
+            .physical()
+            .f_stop(4.0)
+            .lens(LensSystem::double_gauss())
+            .build();
+        (0..100).for_each(|_| {
+            if let Some(ray) = camera.get_ray(0.5, 0.5, &mut Independent::new()) {
+                let at_focus: Point3 = ray.at((-1.0 - ray.origin().z()) / ray.direction().z());
+                assert!(
+                    at_focus.x().abs() < 2e-3 && at_focus.y().abs() < 2e-3,
+                    "{at_focus}"
+                );
+            }
+        });
+
+        // Less light makes it through to the corners.
+        let through = |u: f32, v: f32| {
+            (0..2000)
+                .filter(|_| camera.get_ray(u, v, &mut Independent::new()).is_some())
+                .count()
+        };
+        assert!(through(1.0, 1.0) < through(0.5, 0.5));
+    }
+}
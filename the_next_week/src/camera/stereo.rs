@@ -0,0 +1,277 @@
+use std::f32::consts::{PI, TAU};
+
+use in_one_weekend::vec3::Vec3;
+
+use crate::{ray::Ray, sampler::Sampler};
+
+use super::{Camera, CameraBuilder, Frame, Projection};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Eye {
+    Left,
+    Right,
+}
+
+impl Eye {
+    /// Which way the eye sits from the centre, along the right direction.
+    fn side(self) -> f32 {
+        match self {
+            Eye::Left => -1.0,
+            Eye::Right => 1.0,
+        }
+    }
+}
+
+/// How a `Stereo` image holds the views of both eyes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StereoLayout {
+    /// The left eye on the left half, for an image twice as wide as a view.
+    SideBySide,
+    /// The left eye on the top half, for an image twice as tall as a view.
+    TopBottom,
+}
+
+/// Both views of a pair of eyes in one image, so that it renders like any
+/// other projection.
+pub struct Stereo<P: Projection> {
+    left: P,
+    right: P,
+    layout: StereoLayout,
+}
+
+impl<P: Projection> Stereo<P> {
+    pub fn new(left: P, right: P, layout: StereoLayout) -> Self {
+        Self {
+            left,
+            right,
+            layout,
+        }
+    }
+
+    pub fn eye(&self, eye: Eye) -> &P {
+        match eye {
+            Eye::Left => &self.left,
+            Eye::Right => &self.right,
+        }
+    }
+}
+
+impl<P: Projection> Projection for Stereo<P> {
+    fn get_ray(&self, u: f32, v: f32, sampler: &mut dyn Sampler) -> Option<Ray> {
+        match self.layout {
+            StereoLayout::SideBySide => match u < 0.5 {
+                true => self.left.get_ray(2.0 * u, v, sampler),
+                false => self.right.get_ray(2.0 * u - 1.0, v, sampler),
+            },
+            StereoLayout::TopBottom => match 0.5 <= v {
+                true => self.left.get_ray(u, 2.0 * v - 1.0, sampler),
+                false => self.right.get_ray(u, 2.0 * v, sampler),
+            },
+        }
+    }
+
+    fn exposure(&self) -> f32 {
+        self.left.exposure()
+    }
+}
+
+/// One eye of an omni-directional stereo panorama: an equirectangular view
+/// where every ray leaves from where the eye would be with the head turned
+/// towards it, so that any part of it looked at in a headset has the right
+/// parallax.
+pub struct OmniStereo {
+    frame: Frame,
+    /// Signed distance of the eye from the centre of the head, positive to the
+    /// right.
+    offset: f32,
+}
+
+impl Projection for OmniStereo {
+    fn get_ray(&self, u: f32, v: f32, sampler: &mut dyn Sampler) -> Option<Ray> {
+        let (sin_phi, cos_phi): (f32, f32) = ((u - 0.5) * TAU).sin_cos();
+        let (sin_theta, cos_theta): (f32, f32) = ((v - 0.5) * PI).sin_cos();
+        let eye: Vec3 = self.offset * Vec3::new(cos_phi, 0.0, -sin_phi);
+
+        Some(self.frame.ray(
+            self.frame.point(eye),
+            Vec3::new(cos_theta * sin_phi, sin_theta, cos_theta * cos_phi),
+            sampler,
+        ))
+    }
+}
+
+/// Sets up a pair of eyes around the position and view direction of a
+/// `CameraBuilder`, whose aspect ratio is that of the view of one eye.
+pub struct StereoBuilder {
+    camera: CameraBuilder,
+    interocular_distance: f32,
+    convergence_distance: f32,
+    layout: StereoLayout,
+}
+
+impl CameraBuilder {
+    /// Eyes 64 mm apart, taking scene units as metres, looking in parallel
+    /// and laid out side by side.
+    pub fn stereo(self) -> StereoBuilder {
+        StereoBuilder {
+            camera: self,
+            interocular_distance: 0.064,
+            convergence_distance: 0.0,
+            layout: StereoLayout::SideBySide,
+        }
+    }
+}
+
+impl StereoBuilder {
+    pub fn interocular_distance(mut self, distance: f32) -> Self {
+        self.interocular_distance = distance;
+        self
+    }
+
+    /// Distance at which both eyes see the same point in the middle of their
+    /// view, i.e. the depth of the screen; 0 for infinitely far.
+    pub fn convergence_distance(mut self, distance: f32) -> Self {
+        self.convergence_distance = distance;
+        self
+    }
+
+    pub fn layout(mut self, layout: StereoLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// The camera builder moved to `eye`, still looking in the original view
+    /// direction.
+    pub fn eye(&self, eye: Eye) -> CameraBuilder {
+        let frame: Frame = self.camera.frame();
+        let offset: Vec3 = eye.side() * self.interocular_distance / 2.0 * frame.u;
+
+        CameraBuilder {
+            look_from: self.camera.look_from + offset,
+            look_at: self.camera.look_at + offset,
+            ..self.camera
+        }
+    }
+
+    /// Perspective views converging by shifting the image of each eye
+    /// sideways rather than turning the eyes in, which would skew the views
+    /// against each other.
+    pub fn build(&self) -> Stereo<Camera> {
+        let view = |eye: Eye| {
+            let mut camera: Camera = self.eye(eye).build();
+            if 0.0 < self.convergence_distance {
+                let offset: f32 = eye.side() * self.interocular_distance / 2.0;
+                camera.lower_left_corner -=
+                    offset * self.camera.focus_dist / self.convergence_distance * camera.u;
+            }
+            camera
+        };
+
+        Stereo::new(view(Eye::Left), view(Eye::Right), self.layout)
+    }
+
+    /// Any projection for each eye, built by `build` from the builder of that
+    /// eye. The eyes look in parallel.
+    pub fn build_with<P: Projection>(&self, build: impl Fn(CameraBuilder) -> P) -> Stereo<P> {
+        Stereo::new(
+            build(self.eye(Eye::Left)),
+            build(self.eye(Eye::Right)),
+            self.layout,
+        )
+    }
+
+    /// An omni-directional stereo panorama, best rendered top-bottom with an
+    /// aspect ratio of 1:1.
+    pub fn build_omni_stereo(&self) -> Stereo<OmniStereo> {
+        let eye = |eye: Eye| OmniStereo {
+            frame: self.camera.frame(),
+            offset: eye.side() * self.interocular_distance / 2.0,
+        };
+
+        Stereo::new(eye(Eye::Left), eye(Eye::Right), self.layout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use in_one_weekend::point::Point3;
+
+    use super::*;
+    use crate::sampler::Independent;
+
+    fn builder() -> CameraBuilder {
+        Camera::builder()
+            .look_from(Point3::new(0.0, 1.0, 0.0))
+            .look_at(Point3::new(0.0, 1.0, -1.0))
+            .focus_dist(1.0)
+            .time_0(0.0)
+            .time_1(1.0)
+    }
+
+    #[test]
+    fn stereo_should_lay_out_both_eyes() {
+        let parallel = builder().stereo().build();
+        let left: Ray = parallel
+            .get_ray(0.25, 0.5, &mut Independent::new())
+            .unwrap();
+        let right: Ray = parallel
+            .get_ray(0.75, 0.5, &mut Independent::new())
+            .unwrap();
+        assert!((left.origin() - Point3::new(-0.032, 1.0, 0.0)).len() < 1e-6);
+        assert!((right.origin() - Point3::new(0.032, 1.0, 0.0)).len() < 1e-6);
+        assert!((left.direction() - right.direction()).len() < 1e-6);
+
+        // Converging at 2, the middle of both views meets there.
+        let converging = builder()
+            .stereo()
+            .interocular_distance(0.1)
+            .convergence_distance(2.0)
+            .layout(StereoLayout::TopBottom)
+            .build();
+        let left: Ray = converging
+            .get_ray(0.5, 0.75, &mut Independent::new())
+            .unwrap();
+        let right: Ray = converging
+            .get_ray(0.5, 0.25, &mut Independent::new())
+            .unwrap();
+        assert!(left.origin().x() < 0.0 && 0.0 < right.origin().x());
+        let at_screen = |ray: &Ray| ray.at(-2.0 / ray.direction().z());
+        assert!((at_screen(&left) - Point3::new(0.0, 1.0, -2.0)).len() < 1e-5);
+        assert!((at_screen(&right) - Point3::new(0.0, 1.0, -2.0)).len() < 1e-5);
+
+        let orthographic = builder()
+            .stereo()
+            .build_with(|eye| eye.build_orthographic());
+        assert!(
+            orthographic
+                .get_ray(0.25, 0.5, &mut Independent::new())
+                .unwrap()
+                .origin()
+                .x()
+                < 0.0
+        );
+    }
+
+    #[test]
+    fn omni_stereo_should_keep_the_eyes_across_the_view() {
+        let panorama = builder()
+            .stereo()
+            .layout(StereoLayout::TopBottom)
+            .build_omni_stereo();
+
+        (0..16).for_each(|i| {
+            let u: f32 = i as f32 / 16.0;
+            [(Eye::Left, 0.75), (Eye::Right, 0.25)]
+                .into_iter()
+                .for_each(|(eye, v)| {
+                    let ray: Ray = panorama.get_ray(u, v, &mut Independent::new()).unwrap();
+                    let offset: Vec3 = ray.origin() - Point3::new(0.0, 1.0, 0.0);
+                    assert!((offset.len() - 0.032).abs() < 1e-6);
+                    assert!(offset.dot(ray.direction()).abs() < 1e-6);
+                    // The right eye is on the right of the view direction.
+                    let right_of_view: Vec3 = ray.direction().cross(Vec3::new(0.0, 1.0, 0.0));
+                    assert_eq!(eye == Eye::Right, 0.0 < offset.dot(right_of_view));
+                });
+        });
+    }
+}
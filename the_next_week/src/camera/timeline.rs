@@ -0,0 +1,136 @@
+use in_one_weekend::point::Point3;
+
+use crate::{animation::Track, ray::Ray, sampler::Sampler};
+
+use super::{shutter_time, Camera, CameraBuilder, Projection};
+
+/// A camera that moves over time: where it is, what it looks at, its field
+/// of view and the distance it focuses at each follow a `Track`, and the rest
+/// comes from a `CameraBuilder`.
+#[derive(Clone)]
+pub struct CameraTimeline {
+    camera: CameraBuilder,
+    look_from: Track<Point3>,
+    look_at: Track<Point3>,
+    fov: Track<f32>,
+    focus_dist: Track<f32>,
+}
+
+impl CameraTimeline {
+    /// A timeline where `camera` stands still until a track is set.
+    pub fn new(camera: CameraBuilder) -> Self {
+        Self {
+            look_from: Track::constant(camera.look_from),
+            look_at: Track::constant(camera.look_at),
+            fov: Track::constant(camera.v_fov),
+            focus_dist: Track::constant(camera.focus_dist),
+            camera,
+        }
+    }
+
+    pub fn look_from(mut self, track: Track<Point3>) -> Self {
+        self.look_from = track;
+        self
+    }
+
+    pub fn look_at(mut self, track: Track<Point3>) -> Self {
+        self.look_at = track;
+        self
+    }
+
+    /// Vertical field of view in degrees.
+    pub fn fov(mut self, track: Track<f32>) -> Self {
+        self.fov = track;
+        self
+    }
+
+    pub fn focus_dist(mut self, track: Track<f32>) -> Self {
+        self.focus_dist = track;
+        self
+    }
+
+    /// The camera as it is at `time`.
+    pub fn at(&self, time: f32) -> CameraBuilder {
+        CameraBuilder {
+            look_from: self.look_from.at(time),
+            look_at: self.look_at.at(time),
+            v_fov: self.fov.at(time),
+            focus_dist: self.focus_dist.at(time),
+            ..self.camera
+        }
+    }
+
+    /// A camera with its shutter open from `time_0` to `time_1`, which keeps
+    /// moving while it is, so that its own motion blurs the image too.
+    pub fn build(self, time_0: f32, time_1: f32) -> AnimatedCamera {
+        debug_assert!(time_0 <= time_1);
+        AnimatedCamera {
+            timeline: self,
+            time_0,
+            time_1,
+        }
+    }
+}
+
+pub struct AnimatedCamera {
+    timeline: CameraTimeline,
+    time_0: f32,
+    time_1: f32,
+}
+
+impl Projection for AnimatedCamera {
+    fn get_ray(&self, u: f32, v: f32, sampler: &mut dyn Sampler) -> Option<Ray> {
+        let time: f32 = shutter_time(self.time_0, self.time_1, sampler);
+        let camera: Camera = self.timeline.at(time).time_0(time).time_1(time).build();
+
+        Some(camera.get_ray(u, v, sampler))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{animation::Interpolation, sampler::Independent};
+
+    #[test]
+    fn animated_camera_should_follow_its_tracks() {
+        let timeline = CameraTimeline::new(
+            Camera::builder()
+                .look_at(Point3::new(0.0, 0.0, 0.0))
+                .focus_dist(1.0),
+        )
+        .look_from(
+            Track::new(vec![
+                (0.0, Point3::new(0.0, 0.0, 4.0)),
+                (1.0, Point3::new(4.0, 0.0, 0.0)),
+                (2.0, Point3::new(0.0, 0.0, -4.0)),
+            ])
+            .set_interpolation(Interpolation::CatmullRom),
+        )
+        .fov(Track::new(vec![(0.0, 90.0), (2.0, 30.0)]));
+
+        let halfway: CameraBuilder = timeline.at(1.0);
+        assert_eq!(Point3::new(4.0, 0.0, 0.0), halfway.look_from);
+        assert_eq!(60.0, halfway.v_fov);
+        assert_eq!(1.0, halfway.focus_dist);
+
+        // Each ray is taken at some time while the shutter is open, from where
+        // the camera is then, and always looks at the target in the middle.
+        let camera: AnimatedCamera = timeline.build(0.5, 1.5);
+        (0..32).for_each(|_| {
+            let ray: Ray = camera.get_ray(0.5, 0.5, &mut Independent::new()).unwrap();
+            assert!((0.5..1.5).contains(&ray.time()));
+            assert!(ray.origin().cross(ray.direction()).len() < 1e-4);
+            assert!(2.0 < ray.origin().x());
+        });
+
+        let still: AnimatedCamera = CameraTimeline::new(Camera::builder()).build(2.0, 2.0);
+        assert_eq!(
+            2.0,
+            still
+                .get_ray(0.5, 0.5, &mut Independent::new())
+                .unwrap()
+                .time()
+        );
+    }
+}
@@ -0,0 +1,438 @@
+use std::{
+    collections::VecDeque,
+    io::{self, BufReader, BufWriter, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Condvar, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use in_one_weekend::utils;
+
+use crate::render::{Framebuffer, PixelEstimate, Tile, TileScheduler};
+
+use super::protocol::{Message, RenderJob};
+
+/// How long to wait for results before looking for new workers again.
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long a connection has to say `Hello` before it is dropped.
+const HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The least time a worker has to send back a tile, however cheap the tiles,
+/// for the round trip over the network.
+const MIN_RESULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Tiles kept in flight on a worker for each of its threads, so that it has
+/// the next ones at hand while the results of the last ones are on the way.
+const TILES_PER_THREAD: usize = 2;
+
+/// The most threads of a worker tiles are handed out for, whatever it says it
+/// has.
+const MAX_THREADS: usize = 1024;
+
+/// Hands out the tiles of a render to the workers that connect to it over TCP
+/// and puts the pixel estimates they send back together.
+///
+/// Workers may connect at any time during a render. The tiles of a worker
+/// that drops out are handed to the others, so the render completes as long
+/// as one worker is left or another one connects.
+///
+/// A worker that goes quiet, because it froze or its machine went down
+/// without closing the connection, is taken to have dropped out once it has
+/// not sent back a tile for as long as its tiles in flight should take, going
+/// by `set_sample_timeout`.
+#[derive(Debug)]
+pub struct Coordinator {
+    listener: TcpListener,
+    sample_timeout: Duration,
+}
+
+impl Coordinator {
+    pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        TcpListener::bind(address).map(|listener| Self {
+            listener,
+            sample_timeout: Duration::from_micros(100),
+        })
+    }
+
+    /// The longest a sample may take on a worker, default 100 µs, which the
+    /// time a worker has to send back a tile is scaled from.
+    pub fn set_sample_timeout(mut self, timeout: Duration) -> Self {
+        self.sample_timeout = timeout;
+        self
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Render `job` as a `WIDTH` by `HEIGHT` image in the tiles of
+    /// `scheduler`, and wait until the workers have sent back every one of
+    /// them, logging the progress as they do.
+    pub fn render<const WIDTH: usize, const HEIGHT: usize>(
+        &self,
+        job: &RenderJob,
+        scheduler: &TileScheduler,
+    ) -> io::Result<Framebuffer<WIDTH, HEIGHT>> {
+        let tiles: Vec<Tile> = scheduler.tiles::<WIDTH, HEIGHT>();
+        let samples_per_tile: usize = tiles
+            .iter()
+            .map(Tile::len)
+            .max()
+            .unwrap_or_default()
+            .saturating_mul(job.samples_per_pixel)
+            .saturating_mul(TILES_PER_THREAD);
+        let result_timeout: Duration = MIN_RESULT_TIMEOUT.max(
+            self.sample_timeout
+                .saturating_mul(u32::try_from(samples_per_tile).unwrap_or(u32::MAX)),
+        );
+        let num_pixels: usize = WIDTH * HEIGHT;
+        let mut num_tiles_left: usize = tiles.len();
+        let mut num_pixels_has_rendered: usize = 0;
+
+        let assignments: Assignments = Assignments::new(tiles);
+        let (sender, results): (Sender<Rendered>, Receiver<Rendered>) = mpsc::channel();
+        let mut framebuffer: Framebuffer<WIDTH, HEIGHT> = Framebuffer::new();
+
+        self.listener.set_nonblocking(true)?;
+        thread::scope(|scope| {
+            while num_tiles_left > 0 {
+                match self.listener.accept() {
+                    Ok((stream, address)) => {
+                        let (assignments, sender) = (&assignments, sender.clone());
+                        scope.spawn(move || {
+                            let connection: Connection = Connection {
+                                stream: &stream,
+                                job,
+                                size: (WIDTH, HEIGHT),
+                                result_timeout,
+                            };
+                            serve(&connection, address, assignments, &sender)
+                        });
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(err) => eprintln!("Could not accept a worker: {err}"),
+                }
+
+                match results.recv_timeout(ACCEPT_INTERVAL) {
+                    Ok(Rendered { tile, estimates }) => {
+                        tile.pixels()
+                            .zip(estimates)
+                            .for_each(|((row, column), estimate)| {
+                                *framebuffer.pixel_mut(row, column) = estimate
+                            });
+                        num_tiles_left -= 1;
+                        num_pixels_has_rendered += tile.len();
+                        utils::log_progress(num_pixels_has_rendered as f64 / num_pixels as f64)
+                            .unwrap();
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => unreachable!("the sender is kept here"),
+                }
+            }
+
+            assignments.finish();
+        });
+        self.listener.set_nonblocking(false)?;
+
+        Ok(framebuffer)
+    }
+}
+
+/// The estimates of a tile a worker sent back.
+struct Rendered {
+    tile: Tile,
+    estimates: Vec<PixelEstimate>,
+}
+
+/// The tiles left to hand out, shared by the connections to the workers.
+struct Assignments {
+    queue: Mutex<Queue>,
+    changed: Condvar,
+}
+
+struct Queue {
+    tiles: VecDeque<Tile>,
+    finished: bool,
+}
+
+impl Assignments {
+    fn new(tiles: Vec<Tile>) -> Self {
+        Self {
+            queue: Mutex::new(Queue {
+                tiles: tiles.into(),
+                finished: false,
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// The next tile to hand out, if there is one. With `wait`, block until
+    /// there is, or until every tile is done and `None` is returned.
+    fn next(&self, wait: bool) -> Option<Tile> {
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            if let Some(tile) = queue.tiles.pop_front() {
+                return Some(tile);
+            }
+            if queue.finished || !wait {
+                return None;
+            }
+            queue = self.changed.wait(queue).unwrap();
+        }
+    }
+
+    /// Hand out `tiles` again, ahead of the ones not handed out yet.
+    fn requeue(&self, tiles: Vec<Tile>) {
+        let mut queue = self.queue.lock().unwrap();
+        tiles
+            .into_iter()
+            .rev()
+            .for_each(|tile| queue.tiles.push_front(tile));
+        self.changed.notify_all();
+    }
+
+    fn finish(&self) {
+        self.queue.lock().unwrap().finished = true;
+        self.changed.notify_all();
+    }
+}
+
+/// A worker connected to a render.
+struct Connection<'a> {
+    stream: &'a TcpStream,
+    job: &'a RenderJob,
+    size: (usize, usize),
+    /// How long the worker has to send back each tile.
+    result_timeout: Duration,
+}
+
+/// Hand out tiles to the worker of `connection` until every tile is done, or
+/// hand the ones it has not sent back to the others if it drops out.
+fn serve(
+    connection: &Connection,
+    address: SocketAddr,
+    assignments: &Assignments,
+    results: &Sender<Rendered>,
+) {
+    let mut in_flight: Vec<Tile> = Vec::new();
+    if let Err(err) = exchange(connection, assignments, results, &mut in_flight) {
+        eprintln!(
+            "Worker {address} dropped out ({err}), handing its {} tiles to the others",
+            in_flight.len()
+        );
+        assignments.requeue(in_flight);
+    }
+}
+
+/// The messages with a worker, keeping the tiles it has not sent back in
+/// `in_flight`.
+fn exchange(
+    connection: &Connection,
+    assignments: &Assignments,
+    results: &Sender<Rendered>,
+    in_flight: &mut Vec<Tile>,
+) -> io::Result<()> {
+    let Connection {
+        stream,
+        job,
+        size: (width, height),
+        result_timeout,
+    } = *connection;
+    // Accepted from a nonblocking listener, which some platforms pass on.
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(HELLO_TIMEOUT))?;
+    let mut reader: BufReader<&TcpStream> = BufReader::new(stream);
+    let mut writer: BufWriter<&TcpStream> = BufWriter::new(stream);
+
+    let Message::Hello { threads } = Message::read_from(&mut reader, |_| false)? else {
+        return Err(unexpected("Hello"));
+    };
+    stream.set_read_timeout(Some(result_timeout))?;
+    Message::Setup {
+        job: job.clone(),
+        width,
+        height,
+    }
+    .write_to(&mut writer)?;
+
+    let window: usize = TILES_PER_THREAD * threads.clamp(1, MAX_THREADS);
+    loop {
+        while in_flight.len() < window {
+            let Some(tile) = assignments.next(in_flight.is_empty()) else {
+                break;
+            };
+            Message::Assign(tile).write_to(&mut writer)?;
+            in_flight.push(tile);
+        }
+        if in_flight.is_empty() {
+            Message::Finish.write_to(&mut writer)?;
+            return writer.flush();
+        }
+        writer.flush()?;
+
+        let message: Message = Message::read_from(&mut reader, |tile| in_flight.contains(tile))
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("no tile sent back in {result_timeout:?}"),
+                ),
+                _ => err,
+            })?;
+        let Message::Rendered { tile, estimates } = message else {
+            return Err(unexpected("Rendered"));
+        };
+        let Some(index) = in_flight.iter().position(|&assigned| assigned == tile) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{tile:?} was not assigned to this worker"),
+            ));
+        };
+        in_flight.swap_remove(index);
+        // The receiver only goes once every tile is in.
+        let _ = results.send(Rendered { tile, estimates });
+    }
+}
+
+fn unexpected(expected: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("expected {expected} from the worker"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::SyncSender;
+
+    use in_one_weekend::thread_pool::ThreadPool;
+
+    use super::*;
+    use crate::{
+        distributed::{protocol::is_inside, run_worker},
+        render::{SampleBudget, TileOrder},
+        sampler::{Sampler, Sobol},
+        scene::{Scene, SceneSource},
+    };
+
+    const SCENE: &str = "
+        camera 0 1 -5  0 1 0  40
+        background sky
+        sphere 0 -100 0  100  lambertian 0.5 0.5 0.5
+        sphere 0 1 0  1  metal 0.7 0.6 0.5  0.2
+    ";
+
+    const WIDTH: usize = 8;
+    const HEIGHT: usize = 6;
+
+    fn job() -> RenderJob {
+        RenderJob {
+            scene: SceneSource::Text(SCENE.to_string()),
+            samples_per_pixel: 4,
+            seed: 7,
+        }
+    }
+
+    /// Connect as a worker, take the setup and the first tile, and return the
+    /// connection without rendering anything.
+    fn take_a_tile(address: SocketAddr) -> TcpStream {
+        let stream: TcpStream = TcpStream::connect(address).unwrap();
+        Message::Hello { threads: 1 }
+            .write_to(&mut &stream)
+            .unwrap();
+        let mut reader: BufReader<&TcpStream> = BufReader::new(&stream);
+        assert!(matches!(
+            Message::read_from(&mut reader, |_| false),
+            Ok(Message::Setup { .. })
+        ));
+        assert!(matches!(
+            Message::read_from(&mut reader, |tile| is_inside(tile, (WIDTH, HEIGHT))),
+            Ok(Message::Assign(_))
+        ));
+        drop(reader);
+        stream
+    }
+
+    /// Start `count` workers on `address` and return how many of them saw the
+    /// render through. A worker may only connect once every tile is done and
+    /// be turned away, but at least one renders the tiles left.
+    fn run_workers(address: SocketAddr, count: usize) -> usize {
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..count)
+                .map(|_| scope.spawn(move || run_worker(address, &ThreadPool::new(2))))
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .filter(Result::is_ok)
+                .count()
+        })
+    }
+
+    fn assert_matches_local_render(job: &RenderJob, framebuffer: &Framebuffer<WIDTH, HEIGHT>) {
+        let scene: Scene = job.scene.load(WIDTH as f32 / HEIGHT as f32).unwrap();
+        let mut sampler: Sobol = Sobol::new().set_seed(job.seed);
+        for row in 0..HEIGHT {
+            for column in 0..WIDTH {
+                let expected: PixelEstimate =
+                    SampleBudget::uniform(job.samples_per_pixel).sample(|index| {
+                        sampler.start_pixel_sample(column, row, index);
+                        scene.sample(row, column, WIDTH, HEIGHT, &mut sampler)
+                    });
+                assert_eq!(&expected, framebuffer.pixel(row, column));
+            }
+        }
+    }
+
+    #[test]
+    fn render_should_match_a_local_render_when_a_worker_drops_out() {
+        let job: RenderJob = job();
+        let coordinator: Coordinator = Coordinator::bind("127.0.0.1:0").unwrap();
+        let address: SocketAddr = coordinator.local_addr().unwrap();
+
+        let workers = thread::spawn(move || {
+            // A worker that takes a tile and goes without rendering it.
+            drop(take_a_tile(address));
+            run_workers(address, 2)
+        });
+
+        let framebuffer: Framebuffer<WIDTH, HEIGHT> = coordinator
+            .render(&job, &TileScheduler::new(2, TileOrder::Hilbert))
+            .unwrap();
+        drop(coordinator);
+        assert!(workers.join().unwrap() >= 1);
+        assert_matches_local_render(&job, &framebuffer);
+    }
+
+    #[test]
+    fn render_should_hand_on_the_tiles_of_a_worker_that_goes_quiet() {
+        let job: RenderJob = job();
+        let coordinator: Coordinator = Coordinator::bind("127.0.0.1:0")
+            .unwrap()
+            .set_sample_timeout(Duration::from_micros(1));
+        let address: SocketAddr = coordinator.local_addr().unwrap();
+
+        let (rendered, is_rendered): (SyncSender<()>, Receiver<()>) = mpsc::sync_channel(1);
+        let workers = thread::spawn(move || {
+            // A worker that takes a tile and holds on to the connection
+            // without ever sending it back, until the render is over.
+            let frozen: TcpStream = take_a_tile(address);
+            let count: usize = run_workers(address, 1);
+            is_rendered.recv().unwrap();
+            drop(frozen);
+            count
+        });
+
+        let framebuffer: Framebuffer<WIDTH, HEIGHT> = coordinator
+            .render(&job, &TileScheduler::new(2, TileOrder::Hilbert))
+            .unwrap();
+        rendered.send(()).unwrap();
+        drop(coordinator);
+        assert_eq!(1, workers.join().unwrap());
+        assert_matches_local_render(&job, &framebuffer);
+    }
+}
@@ -0,0 +1,5 @@
+mod coordinator;
+mod protocol;
+mod worker;
+
+pub use self::{coordinator::Coordinator, protocol::RenderJob, worker::run_worker};
@@ -0,0 +1,305 @@
+use std::io::{self, Read, Write};
+
+use crate::{
+    render::{PixelEstimate, Tile},
+    scene::SceneSource,
+};
+
+/// The first bytes a worker sends, with the version of the protocol.
+const MAGIC: &[u8; 8] = b"RTDIST01";
+
+/// What to render, as the coordinator hands it to each worker.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RenderJob {
+    pub scene: SceneSource,
+    pub samples_per_pixel: usize,
+    /// The seed of the `Sobol` samples, for the same image whichever worker
+    /// renders a tile.
+    pub seed: u64,
+}
+
+/// The messages between the coordinator and a worker.
+///
+/// A worker starts with `Hello`, and the coordinator answers with `Setup` and
+/// then sends `Assign` for as many tiles as it likes, each answered with
+/// `Rendered` in whatever order the tiles finish, until it sends `Finish`.
+///
+/// Each message is a tag byte and its fields, with numbers as little-endian
+/// `u64` and strings as their length and UTF-8 bytes.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum Message {
+    Hello {
+        threads: usize,
+    },
+    Setup {
+        job: RenderJob,
+        width: usize,
+        height: usize,
+    },
+    Assign(Tile),
+    /// The estimates of the pixels of a tile, row by row.
+    Rendered {
+        tile: Tile,
+        estimates: Vec<PixelEstimate>,
+    },
+    Finish,
+}
+
+impl Message {
+    pub(super) fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Message::Hello { threads } => {
+                writer.write_all(&[0])?;
+                writer.write_all(MAGIC)?;
+                write_usize(writer, *threads)
+            }
+            Message::Setup { job, width, height } => {
+                writer.write_all(&[1])?;
+                match &job.scene {
+                    SceneSource::Builtin(name) => {
+                        writer.write_all(&[0])?;
+                        write_str(writer, name)?;
+                    }
+                    SceneSource::Text(text) => {
+                        writer.write_all(&[1])?;
+                        write_str(writer, text)?;
+                    }
+                }
+                [job.samples_per_pixel, *width, *height]
+                    .into_iter()
+                    .try_for_each(|value| write_usize(writer, value))?;
+                writer.write_all(&job.seed.to_le_bytes())
+            }
+            Message::Assign(tile) => {
+                writer.write_all(&[2])?;
+                write_tile(writer, tile)
+            }
+            Message::Rendered { tile, estimates } => {
+                debug_assert_eq!(tile.len(), estimates.len());
+                writer.write_all(&[3])?;
+                write_tile(writer, tile)?;
+                estimates
+                    .iter()
+                    .try_for_each(|estimate| writer.write_all(&estimate.to_bytes()))
+            }
+            Message::Finish => writer.write_all(&[4]),
+        }
+    }
+
+    /// The next message of `reader`, with any tile in it checked with
+    /// `expected` before anything the size of the tile is read, so that a
+    /// tile made up by the other end is turned down rather than taking the
+    /// arithmetic over or the memory up.
+    pub(super) fn read_from<R: Read>(
+        reader: &mut R,
+        expected: impl Fn(&Tile) -> bool,
+    ) -> io::Result<Self> {
+        match read_array::<_, 1>(reader)?[0] {
+            0 => {
+                if &read_array::<_, 8>(reader)? != MAGIC {
+                    return Err(invalid_data("not a worker of this version"));
+                }
+                Ok(Message::Hello {
+                    threads: read_usize(reader)?,
+                })
+            }
+            1 => {
+                let scene: SceneSource = match read_array::<_, 1>(reader)?[0] {
+                    0 => SceneSource::Builtin(read_string(reader)?),
+                    1 => SceneSource::Text(read_string(reader)?),
+                    tag => return Err(invalid_data(&format!("unknown scene source {tag}"))),
+                };
+                let [samples_per_pixel, width, height]: [usize; 3] = [
+                    read_usize(reader)?,
+                    read_usize(reader)?,
+                    read_usize(reader)?,
+                ];
+                let seed: u64 = u64::from_le_bytes(read_array(reader)?);
+                Ok(Message::Setup {
+                    job: RenderJob {
+                        scene,
+                        samples_per_pixel,
+                        seed,
+                    },
+                    width,
+                    height,
+                })
+            }
+            2 => Ok(Message::Assign(read_tile(reader, expected)?)),
+            3 => {
+                let tile: Tile = read_tile(reader, expected)?;
+                let len: usize = tile
+                    .height
+                    .checked_mul(tile.width)
+                    .ok_or_else(|| invalid_data(&format!("{tile:?} is too large")))?;
+                let estimates: Vec<PixelEstimate> = (0..len)
+                    .map(|_| read_array(reader).map(|bytes| PixelEstimate::from_bytes(&bytes)))
+                    .collect::<io::Result<_>>()?;
+                Ok(Message::Rendered { tile, estimates })
+            }
+            4 => Ok(Message::Finish),
+            tag => Err(invalid_data(&format!("unknown message {tag}"))),
+        }
+    }
+}
+
+fn write_usize<W: Write>(writer: &mut W, value: usize) -> io::Result<()> {
+    writer.write_all(&(value as u64).to_le_bytes())
+}
+
+fn write_str<W: Write>(writer: &mut W, value: &str) -> io::Result<()> {
+    write_usize(writer, value.len())?;
+    writer.write_all(value.as_bytes())
+}
+
+fn write_tile<W: Write>(writer: &mut W, tile: &Tile) -> io::Result<()> {
+    [tile.row, tile.column, tile.height, tile.width]
+        .into_iter()
+        .try_for_each(|value| write_usize(writer, value))
+}
+
+fn read_array<R: Read, const N: usize>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut bytes: [u8; N] = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_usize<R: Read>(reader: &mut R) -> io::Result<usize> {
+    Ok(u64::from_le_bytes(read_array(reader)?) as usize)
+}
+
+fn read_string<R: Read>(reader: &mut R) -> io::Result<String> {
+    let len: usize = read_usize(reader)?;
+    let mut bytes: Vec<u8> = Vec::new();
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    String::from_utf8(bytes).map_err(|err| invalid_data(&err.to_string()))
+}
+
+fn read_tile<R: Read>(reader: &mut R, expected: impl Fn(&Tile) -> bool) -> io::Result<Tile> {
+    let tile: Tile = Tile {
+        row: read_usize(reader)?,
+        column: read_usize(reader)?,
+        height: read_usize(reader)?,
+        width: read_usize(reader)?,
+    };
+    match expected(&tile) {
+        true => Ok(tile),
+        false => Err(invalid_data(&format!("unexpected {tile:?}"))),
+    }
+}
+
+/// Whether `tile` lies within an image of `width` by `height` pixels.
+pub(super) fn is_inside(tile: &Tile, (width, height): (usize, usize)) -> bool {
+    let end =
+        |start: usize, len: usize, max: usize| start.checked_add(len).is_some_and(|end| end <= max);
+    end(tile.row, tile.height, height) && end(tile.column, tile.width, width)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use in_one_weekend::color::ColorRGBMapTo0_1;
+
+    use super::*;
+
+    #[test]
+    fn messages_should_read_back_as_written() {
+        let tile: Tile = Tile {
+            row: 4,
+            column: 8,
+            height: 1,
+            width: 2,
+        };
+        let mut estimate: PixelEstimate = PixelEstimate::default();
+        estimate.add(ColorRGBMapTo0_1::new(0.25, 0.5, 1.0));
+        let messages: [Message; 6] = [
+            Message::Hello { threads: 64 },
+            Message::Setup {
+                job: RenderJob {
+                    scene: SceneSource::Builtin("cornell_box".to_string()),
+                    samples_per_pixel: 10240,
+                    seed: u64::MAX,
+                },
+                width: 800,
+                height: 600,
+            },
+            Message::Setup {
+                job: RenderJob {
+                    scene: SceneSource::Text("camera 0 0 0  0 0 1  40 # ✓".to_string()),
+                    samples_per_pixel: 1,
+                    seed: 0,
+                },
+                width: 2,
+                height: 2,
+            },
+            Message::Assign(tile),
+            Message::Rendered {
+                tile,
+                estimates: vec![estimate, PixelEstimate::default()],
+            },
+            Message::Finish,
+        ];
+
+        let mut bytes: Vec<u8> = Vec::new();
+        messages
+            .iter()
+            .try_for_each(|message| message.write_to(&mut bytes))
+            .unwrap();
+        let mut reader: &[u8] = &bytes;
+        messages.iter().for_each(|message| {
+            assert_eq!(message, &Message::read_from(&mut reader, |_| true).unwrap())
+        });
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn read_should_reject_other_protocols() {
+        let error: io::Error =
+            Message::read_from(&mut &b"\0RTDIST00\x01\0\0\0\0\0\0\0"[..], |_| true)
+                .err()
+                .unwrap();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        assert!(Message::read_from(&mut &[9_u8][..], |_| true).is_err());
+    }
+
+    #[test]
+    fn read_should_reject_tiles_not_expected_before_reading_their_pixels() {
+        let rendered = |tile: Tile| {
+            let mut bytes: Vec<u8> = vec![3];
+            write_tile(&mut bytes, &tile).unwrap();
+            bytes
+        };
+        let huge: Tile = Tile {
+            row: 0,
+            column: 0,
+            height: usize::MAX,
+            width: usize::MAX,
+        };
+        let outside: Tile = Tile {
+            row: usize::MAX,
+            column: 0,
+            height: 2,
+            width: 2,
+        };
+
+        for tile in [huge, outside] {
+            assert!(!is_inside(&tile, (8, 6)));
+            let error: io::Error =
+                Message::read_from(&mut &rendered(tile)[..], |tile| is_inside(tile, (8, 6)))
+                    .err()
+                    .unwrap();
+            assert_eq!(io::ErrorKind::InvalidData, error.kind(), "{error}");
+        }
+        // Overflowing the number of pixels is an error, not a panic.
+        let error: io::Error = Message::read_from(&mut &rendered(huge)[..], |_| true)
+            .err()
+            .unwrap();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind(), "{error}");
+    }
+}
@@ -0,0 +1,84 @@
+use std::{
+    io::{self, BufReader, BufWriter, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::Mutex,
+};
+
+use in_one_weekend::thread_pool::{JoinHandle, ThreadPool};
+
+use crate::{
+    render::{PixelEstimate, SampleBudget},
+    sampler::{Sampler, Sobol},
+    scene::Scene,
+};
+
+use super::protocol::{is_inside, Message};
+
+/// Connect to the coordinator at `address` and render the tiles it assigns
+/// on `thread_pool`, sending each back as it completes, until the coordinator
+/// says the render is finished.
+///
+/// Every pixel takes the samples of the job from a `Sobol` sampler seeded
+/// with the seed of the job, so a tile comes out the same on any worker.
+pub fn run_worker<A: ToSocketAddrs>(address: A, thread_pool: &ThreadPool) -> io::Result<()> {
+    let stream: TcpStream = TcpStream::connect(address)?;
+    let mut reader: BufReader<&TcpStream> = BufReader::new(&stream);
+    let writer: Mutex<BufWriter<&TcpStream>> = Mutex::new(BufWriter::new(&stream));
+
+    {
+        let mut writer = writer.lock().unwrap();
+        Message::Hello {
+            threads: thread_pool.size(),
+        }
+        .write_to(&mut *writer)?;
+        writer.flush()?;
+    }
+
+    let Message::Setup { job, width, height } = Message::read_from(&mut reader, |_| false)? else {
+        return Err(unexpected("Setup"));
+    };
+    let scene: Scene = job.scene.load(width as f32 / height as f32)?;
+    let budget: SampleBudget = SampleBudget::uniform(job.samples_per_pixel);
+    let sampler: Sobol = Sobol::new().set_seed(job.seed);
+
+    thread_pool.scope(|scope| {
+        let mut rendering: Vec<JoinHandle<io::Result<()>>> = Vec::new();
+        loop {
+            match Message::read_from(&mut reader, |tile| is_inside(tile, (width, height)))? {
+                Message::Assign(tile) => {
+                    let (scene, writer): (&Scene, &Mutex<_>) = (&scene, &writer);
+                    let mut sampler: Sobol = sampler;
+
+                    rendering.push(scope.spawn(move || {
+                        let estimates: Vec<PixelEstimate> = tile
+                            .pixels()
+                            .map(|(row, column)| {
+                                budget.sample(|index| {
+                                    sampler.start_pixel_sample(column, row, index);
+                                    scene.sample(row, column, width, height, &mut sampler)
+                                })
+                            })
+                            .collect();
+
+                        let mut writer = writer.lock().unwrap();
+                        Message::Rendered { tile, estimates }.write_to(&mut *writer)?;
+                        writer.flush()
+                    }));
+                }
+                Message::Finish => break,
+                _ => return Err(unexpected("Assign or Finish")),
+            }
+        }
+
+        rendering
+            .into_iter()
+            .try_for_each(|rendered| rendered.join().map_err(io::Error::other)?)
+    })
+}
+
+fn unexpected(expected: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("expected {expected} from the coordinator"),
+    )
+}
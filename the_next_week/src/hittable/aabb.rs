@@ -0,0 +1,117 @@
+use in_one_weekend::point::Point3;
+
+use crate::ray::Ray;
+
+/// Axis-aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    min: Point3,
+    max: Point3,
+}
+
+impl Aabb {
+    pub fn new(min: Point3, max: Point3) -> Self {
+        Self { min, max }
+    }
+
+    #[inline]
+    pub fn min(&self) -> Point3 {
+        self.min
+    }
+
+    #[inline]
+    pub fn max(&self) -> Point3 {
+        self.max
+    }
+
+    /// The smallest box containing all `points`, `None` if there are none.
+    pub fn from_points<I: IntoIterator<Item = Point3>>(points: I) -> Option<Self> {
+        points
+            .into_iter()
+            .map(|p| Self::new(p, p))
+            .reduce(|bbox, point| bbox.surrounding(&point))
+    }
+
+    pub fn corners(&self) -> [Point3; 8] {
+        [0, 1, 2, 3, 4, 5, 6, 7].map(|i| {
+            Point3::new(
+                if i & 1 == 0 {
+                    self.min.x()
+                } else {
+                    self.max.x()
+                },
+                if i & 2 == 0 {
+                    self.min.y()
+                } else {
+                    self.max.y()
+                },
+                if i & 4 == 0 {
+                    self.min.z()
+                } else {
+                    self.max.z()
+                },
+            )
+        })
+    }
+
+    /// The smallest box containing both boxes.
+    pub fn surrounding(&self, other: &Aabb) -> Self {
+        Self {
+            min: Point3::new(
+                self.min.x().min(other.min.x()),
+                self.min.y().min(other.min.y()),
+                self.min.z().min(other.min.z()),
+            ),
+            max: Point3::new(
+                self.max.x().max(other.max.x()),
+                self.max.y().max(other.max.y()),
+                self.max.z().max(other.max.z()),
+            ),
+        }
+    }
+
+    /// The box common to both boxes, `None` if they do not overlap.
+    pub fn intersection(&self, other: &Aabb) -> Option<Self> {
+        let bbox = Self {
+            min: Point3::new(
+                self.min.x().max(other.min.x()),
+                self.min.y().max(other.min.y()),
+                self.min.z().max(other.min.z()),
+            ),
+            max: Point3::new(
+                self.max.x().min(other.max.x()),
+                self.max.y().min(other.max.y()),
+                self.max.z().min(other.max.z()),
+            ),
+        };
+
+        match (0..3).all(|axis| bbox.min[axis] <= bbox.max[axis]) {
+            true => Some(bbox),
+            false => None,
+        }
+    }
+
+    /// The part `[t_enter, t_exit]` of `[t_min, t_max]` during which the ray is
+    /// inside the box, using the slab method.
+    pub fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<(f32, f32)> {
+        let (mut t_enter, mut t_exit): (f32, f32) = (t_min, t_max);
+
+        for axis in 0..3 {
+            let inv_d: f32 = 1.0 / ray.direction()[axis];
+            let mut t0: f32 = (self.min[axis] - ray.origin()[axis]) * inv_d;
+            let mut t1: f32 = (self.max[axis] - ray.origin()[axis]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            // `max`/`min` ignore the NaN of a ray lying in a slab's plane.
+            t_enter = t_enter.max(t0);
+            t_exit = t_exit.min(t1);
+            if t_exit < t_enter {
+                return None;
+            }
+        }
+
+        Some((t_enter, t_exit))
+    }
+}
@@ -0,0 +1,278 @@
+use in_one_weekend::point::Point3;
+
+use crate::{
+    ray::Ray,
+    sampler::{Independent, Sampler},
+    stats,
+};
+
+use super::{Aabb, HitRecord, Hittable};
+
+/// Objects per leaf below which a node is not split any further.
+const LEAF_SIZE: usize = 4;
+
+/// A bounding volume hierarchy, so that a ray only tests the objects whose
+/// boxes it passes through. Built once, by splitting the objects at the median
+/// of their centres along the axis where those are spread the most.
+///
+/// Objects without a bounding box are kept aside and tested with every ray.
+pub struct Bvh<H: AsRef<dyn Hittable>> {
+    objects: Vec<H>,
+    unbounded: Vec<H>,
+    /// Depth-first order, so that the first child of an interior node directly
+    /// follows it.
+    nodes: Vec<Node>,
+}
+
+enum Node {
+    Leaf {
+        bbox: Aabb,
+        start: usize,
+        count: usize,
+    },
+    Interior {
+        bbox: Aabb,
+        second_child: usize,
+        axis: u8,
+    },
+}
+
+impl Node {
+    fn bbox(&self) -> &Aabb {
+        match self {
+            Node::Leaf { bbox, .. } | Node::Interior { bbox, .. } => bbox,
+        }
+    }
+}
+
+impl<H: AsRef<dyn Hittable>> Bvh<H> {
+    /// Bound the objects over the shutter interval `[time_0, time_1]`.
+    pub fn new(objects: Vec<H>, time_0: f32, time_1: f32) -> Self {
+        let mut bounded: Vec<(H, Aabb)> = Vec::with_capacity(objects.len());
+        let mut unbounded: Vec<H> = Vec::new();
+        for object in objects {
+            match object.as_ref().bounding_box(time_0, time_1) {
+                Some(bbox) => bounded.push((object, bbox)),
+                None => unbounded.push(object),
+            }
+        }
+
+        let mut nodes: Vec<Node> = Vec::new();
+        if !bounded.is_empty() {
+            build(&mut bounded, 0, &mut nodes);
+        }
+
+        Self {
+            objects: bounded.into_iter().map(|(object, _)| object).collect(),
+            unbounded,
+            nodes,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len() + self.unbounded.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn centroid(bbox: &Aabb, axis: u8) -> f32 {
+    (bbox.min()[axis] + bbox.max()[axis]) / 2.0
+}
+
+/// Append the subtree over `objects`, which start at index `offset` of the
+/// final object list, and reorder them to match its leaves.
+fn build<H>(objects: &mut [(H, Aabb)], offset: usize, nodes: &mut Vec<Node>) {
+    let bbox: Aabb = objects
+        .iter()
+        .map(|(_, bbox)| *bbox)
+        .reduce(|a, b| a.surrounding(&b))
+        .expect("a node has objects");
+
+    if objects.len() <= LEAF_SIZE {
+        nodes.push(Node::Leaf {
+            bbox,
+            start: offset,
+            count: objects.len(),
+        });
+        return;
+    }
+
+    let centroids: Aabb = Aabb::from_points(
+        objects
+            .iter()
+            .map(|(_, bbox)| Point3::new(centroid(bbox, 0), centroid(bbox, 1), centroid(bbox, 2))),
+    )
+    .expect("a node has objects");
+    let axis: u8 = (0..3)
+        .max_by(|a, b| {
+            let extent = |axis: u8| centroids.max()[axis] - centroids.min()[axis];
+            extent(*a).total_cmp(&extent(*b))
+        })
+        .expect("three axes");
+
+    let middle: usize = objects.len() / 2;
+    objects.select_nth_unstable_by(middle, |(_, a), (_, b)| {
+        centroid(a, axis).total_cmp(&centroid(b, axis))
+    });
+
+    let index: usize = nodes.len();
+    nodes.push(Node::Interior {
+        bbox,
+        second_child: 0,
+        axis,
+    });
+    let (first, second) = objects.split_at_mut(middle);
+    build(first, offset, nodes);
+    let second_index: usize = nodes.len();
+    build(second, offset + middle, nodes);
+    if let Node::Interior { second_child, .. } = &mut nodes[index] {
+        *second_child = second_index;
+    }
+}
+
+impl<H: AsRef<dyn Hittable> + Send + Sync> Hittable for Bvh<H> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.hit_sampled(ray, t_min, t_max, &mut Independent::seeded(0))
+    }
+
+    fn hit_sampled(
+        &self,
+        ray: &Ray,
+        t_min: f32,
+        t_max: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
+        let mut closest: Option<HitRecord<'_>> = None;
+        let mut t_closest: f32 = t_max;
+
+        for object in &self.unbounded {
+            if let Some(hit_record) = object.as_ref().hit_sampled(ray, t_min, t_closest, sampler) {
+                t_closest = hit_record.t;
+                closest = Some(hit_record);
+            }
+        }
+
+        let mut stack: Vec<usize> = Vec::with_capacity(64);
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(index) = stack.pop() {
+            let node: &Node = &self.nodes[index];
+            stats::bvh_node_visit();
+            if node.bbox().hit(ray, t_min, t_closest).is_none() {
+                continue;
+            }
+
+            match node {
+                Node::Leaf { start, count, .. } => {
+                    for object in &self.objects[*start..start + count] {
+                        if let Some(hit_record) =
+                            object.as_ref().hit_sampled(ray, t_min, t_closest, sampler)
+                        {
+                            t_closest = hit_record.t;
+                            closest = Some(hit_record);
+                        }
+                    }
+                }
+                Node::Interior {
+                    second_child, axis, ..
+                } => {
+                    // Visit the child on the side the ray comes from first.
+                    let (near, far): (usize, usize) = match 0.0 <= ray.direction()[*axis] {
+                        true => (index + 1, *second_child),
+                        false => (*second_child, index + 1),
+                    };
+                    stack.push(far);
+                    stack.push(near);
+                }
+            }
+        }
+
+        closest
+    }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        match self.unbounded.is_empty() {
+            true => self.nodes.first().map(|node| *node.bbox()),
+            false => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use in_one_weekend::{color::ColorRGBMapTo0_1, vec3::Vec3};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::{
+        hittable::{HittableList, Sphere, XZRect},
+        material::{Lambertian, Material},
+        textures::SolidColor,
+    };
+
+    #[test]
+    fn bvh_should_find_the_same_hits_as_a_list() {
+        let material: Arc<dyn Material> = Arc::new(Lambertian::new(SolidColor::from(
+            ColorRGBMapTo0_1::new(0.5, 0.5, 0.5),
+        )));
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut random_point = |scale: f32| {
+            Point3::new(
+                rng.gen_range(-scale..scale),
+                rng.gen_range(-scale..scale),
+                rng.gen_range(-scale..scale),
+            )
+        };
+
+        let spheres: Vec<Arc<dyn Hittable>> = (0..500)
+            .map(|_| {
+                Arc::new(Sphere::new(random_point(10.0), 0.3, material.clone()))
+                    as Arc<dyn Hittable>
+            })
+            .collect();
+        let mut list: HittableList<Arc<dyn Hittable>> = HittableList::default();
+        spheres.iter().for_each(|sphere| list.add(sphere.clone()));
+        let bvh = Bvh::new(spheres, 0.0, 1.0);
+        assert_eq!(500, bvh.len());
+
+        let rays: Vec<Ray> = (0..500)
+            .map(|_| Ray::new(random_point(15.0), random_point(1.0), 0.0))
+            .collect();
+        let hits: usize = rays
+            .iter()
+            .filter(|ray| {
+                let expected = list.hit(ray, 0.001, f32::INFINITY).map(|hit| hit.t);
+                assert_eq!(
+                    expected,
+                    bvh.hit(ray, 0.001, f32::INFINITY).map(|hit| hit.t)
+                );
+                expected.is_some()
+            })
+            .count();
+        assert!(0 < hits);
+
+        let bbox: Aabb = bvh.bounding_box(0.0, 1.0).unwrap();
+        assert!(bbox.min().x() < -9.0 && 9.0 < bbox.max().x());
+    }
+
+    #[test]
+    fn bvh_should_keep_unbounded_objects() {
+        let material: Arc<dyn Material> = Arc::new(Lambertian::new(SolidColor::from(
+            ColorRGBMapTo0_1::new(0.5, 0.5, 0.5),
+        )));
+        // A list without objects has no box.
+        let empty: Box<dyn Hittable> = Box::new(HittableList::<Box<dyn Hittable>>::default());
+        let floor: Box<dyn Hittable> = Box::new(XZRect::new(-1.0..=1.0, -1.0..=1.0, 0.0, material));
+        let bvh = Bvh::new(vec![empty, floor], 0.0, 1.0);
+
+        assert!(bvh.bounding_box(0.0, 1.0).is_none());
+        let down = Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        assert!((bvh.hit(&down, 0.001, f32::INFINITY).unwrap().t - 1.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,331 @@
+use in_one_weekend::point::Point3;
+
+use crate::ray::Ray;
+
+use super::{Aabb, HitRecord, Hittable};
+
+/// Points inside both operands.
+pub struct Intersection<A: AsRef<dyn Hittable>, B: AsRef<dyn Hittable>> {
+    a: A,
+    b: B,
+}
+
+/// Points inside either operand.
+pub struct Union<A: AsRef<dyn Hittable>, B: AsRef<dyn Hittable>> {
+    a: A,
+    b: B,
+}
+
+/// Points inside `a` but not inside `b`, e.g. `a` with a `b` shaped hole.
+pub struct Difference<A: AsRef<dyn Hittable>, B: AsRef<dyn Hittable>> {
+    a: A,
+    b: B,
+}
+
+impl<A: AsRef<dyn Hittable>, B: AsRef<dyn Hittable>> Intersection<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+}
+
+impl<A: AsRef<dyn Hittable>, B: AsRef<dyn Hittable>> Union<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+}
+
+impl<A: AsRef<dyn Hittable>, B: AsRef<dyn Hittable>> Difference<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+}
+
+/// The boundary of the solid made of the points for which `contains` holds,
+/// given whether they are inside `a` and inside `b`. Both operands must be
+/// closed, so that their hits alternate between entering and leaving.
+///
+/// With `flip_b`, the surfaces of `b` bound the result from the outside of `b`
+/// and face the other way.
+fn combine<'a>(
+    a: &'a dyn Hittable,
+    b: &'a dyn Hittable,
+    ray: &Ray,
+    t_min: f32,
+    t_max: f32,
+    contains: fn(bool, bool) -> bool,
+    flip_b: bool,
+) -> Vec<HitRecord<'a>> {
+    // Look past `t_max`, as the first hit tells whether the ray starts inside.
+    let hits_a: Vec<HitRecord<'a>> = a.hit_all(ray, t_min, f32::INFINITY);
+    let hits_b: Vec<HitRecord<'a>> = b.hit_all(ray, t_min, f32::INFINITY);
+    let mut inside_a: bool = hits_a.first().is_some_and(|hit| !hit.front_face);
+    let mut inside_b: bool = hits_b.first().is_some_and(|hit| !hit.front_face);
+    let mut inside: bool = contains(inside_a, inside_b);
+
+    let mut hits_a = hits_a.into_iter().peekable();
+    let mut hits_b = hits_b.into_iter().peekable();
+    let mut hit_records: Vec<HitRecord<'a>> = Vec::new();
+    loop {
+        let from_a: bool = match (hits_a.peek(), hits_b.peek()) {
+            (None, None) => break,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (Some(hit_a), Some(hit_b)) => hit_a.t <= hit_b.t,
+        };
+        let mut hit_record: HitRecord<'a> = match from_a {
+            true => hits_a.next(),
+            false => hits_b.next(),
+        }
+        .expect("peeked");
+        if t_max < hit_record.t {
+            break;
+        }
+
+        match from_a {
+            true => inside_a = hit_record.front_face,
+            false => inside_b = hit_record.front_face,
+        }
+        if contains(inside_a, inside_b) == inside {
+            continue;
+        }
+        inside = !inside;
+
+        if flip_b && !from_a {
+            // The normal keeps facing the ray; only the side it was hit from
+            // changes.
+            hit_record.front_face = !hit_record.front_face;
+        }
+        hit_records.push(hit_record);
+    }
+
+    hit_records
+}
+
+impl<A, B> Hittable for Intersection<A, B>
+where
+    A: AsRef<dyn Hittable> + Send + Sync,
+    B: AsRef<dyn Hittable> + Send + Sync,
+{
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.hit_all(ray, t_min, t_max).into_iter().next()
+    }
+
+    fn hit_all(&self, ray: &Ray, t_min: f32, t_max: f32) -> Vec<HitRecord<'_>> {
+        combine(
+            self.a.as_ref(),
+            self.b.as_ref(),
+            ray,
+            t_min,
+            t_max,
+            |a, b| a && b,
+            false,
+        )
+    }
+
+    fn bounding_box(&self, time_0: f32, time_1: f32) -> Option<Aabb> {
+        match (
+            self.a.as_ref().bounding_box(time_0, time_1),
+            self.b.as_ref().bounding_box(time_0, time_1),
+        ) {
+            // Operands that do not overlap leave nothing, which is bounded: an
+            // empty box halfway between them keeps a `Bvh` from testing the
+            // intersection against every ray as if it were unbounded.
+            (Some(a), Some(b)) => Some(a.intersection(&b).unwrap_or_else(|| {
+                let halfway = |axis: u8| {
+                    (a.min()[axis].max(b.min()[axis]) + a.max()[axis].min(b.max()[axis])) / 2.0
+                };
+                let p: Point3 = Point3::new(halfway(0), halfway(1), halfway(2));
+                Aabb::new(p, p)
+            })),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+impl<A, B> Hittable for Union<A, B>
+where
+    A: AsRef<dyn Hittable> + Send + Sync,
+    B: AsRef<dyn Hittable> + Send + Sync,
+{
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.hit_all(ray, t_min, t_max).into_iter().next()
+    }
+
+    fn hit_all(&self, ray: &Ray, t_min: f32, t_max: f32) -> Vec<HitRecord<'_>> {
+        combine(
+            self.a.as_ref(),
+            self.b.as_ref(),
+            ray,
+            t_min,
+            t_max,
+            |a, b| a || b,
+            false,
+        )
+    }
+
+    fn bounding_box(&self, time_0: f32, time_1: f32) -> Option<Aabb> {
+        Some(
+            self.a
+                .as_ref()
+                .bounding_box(time_0, time_1)?
+                .surrounding(&self.b.as_ref().bounding_box(time_0, time_1)?),
+        )
+    }
+}
+
+impl<A, B> Hittable for Difference<A, B>
+where
+    A: AsRef<dyn Hittable> + Send + Sync,
+    B: AsRef<dyn Hittable> + Send + Sync,
+{
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.hit_all(ray, t_min, t_max).into_iter().next()
+    }
+
+    fn hit_all(&self, ray: &Ray, t_min: f32, t_max: f32) -> Vec<HitRecord<'_>> {
+        combine(
+            self.a.as_ref(),
+            self.b.as_ref(),
+            ray,
+            t_min,
+            t_max,
+            |a, b| a && !b,
+            true,
+        )
+    }
+
+    fn bounding_box(&self, time_0: f32, time_1: f32) -> Option<Aabb> {
+        self.a.as_ref().bounding_box(time_0, time_1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use in_one_weekend::{color::ColorRGBMapTo0_1, vec3::Vec3};
+
+    use super::*;
+    use crate::{
+        hittable::{Bvh, Cuboid, Sphere},
+        material::{Lambertian, Material},
+        textures::SolidColor,
+    };
+
+    fn material() -> Arc<dyn Material> {
+        Arc::new(Lambertian::new(SolidColor::from(ColorRGBMapTo0_1::new(
+            0.5, 0.5, 0.5,
+        ))))
+    }
+
+    fn sphere(center: Point3, radius: f32) -> Box<dyn Hittable> {
+        Box::new(Sphere::new(center, radius, material()))
+    }
+
+    fn summary(hit_records: &[HitRecord<'_>]) -> Vec<(f32, bool)> {
+        hit_records
+            .iter()
+            .map(|hit_record| (hit_record.position().z(), hit_record.is_front_face()))
+            .collect()
+    }
+
+    fn assert_hits(expected: &[(f32, bool)], actual: &[(f32, bool)]) {
+        assert_eq!(expected.len(), actual.len(), "{actual:?}");
+        expected.iter().zip(actual).for_each(|(expected, actual)| {
+            assert!((expected.0 - actual.0).abs() < 1e-3, "{actual:?}");
+            assert_eq!(expected.1, actual.1, "{actual:?}");
+        });
+    }
+
+    #[test]
+    fn csg_should_combine_intervals() {
+        let along_z = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        let a = || sphere(Point3::new(0.0, 0.0, -0.5), 1.0);
+        let b = || sphere(Point3::new(0.0, 0.0, 0.5), 1.0);
+
+        // A lens is the overlap of both spheres.
+        let lens = Intersection::new(a(), b());
+        assert_hits(
+            &[(-0.5, true), (0.5, false)],
+            &summary(&lens.hit_all(&along_z, 0.001, f32::INFINITY)),
+        );
+        let bbox: Aabb = lens.bounding_box(0.0, 1.0).unwrap();
+        assert!((bbox.min().z() + 0.5).abs() < 1e-6 && (bbox.max().z() - 0.5).abs() < 1e-6);
+
+        let union = Union::new(a(), b());
+        assert_hits(
+            &[(-1.5, true), (1.5, false)],
+            &summary(&union.hit_all(&along_z, 0.001, f32::INFINITY)),
+        );
+
+        // The surface of `b` faces into the bite taken out of `a`.
+        let bitten = Difference::new(a(), b());
+        let hit_records = bitten.hit_all(&along_z, 0.001, f32::INFINITY);
+        assert_hits(&[(-1.5, true), (-0.5, false)], &summary(&hit_records));
+        assert!((hit_records[1].normal() - Vec3::new(0.0, 0.0, -1.0)).len() < 1e-5);
+
+        // Starting inside, and stopping at `t_max`.
+        let inside = Ray::new(Point3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        assert_hits(
+            &[(1.5, false)],
+            &summary(&union.hit_all(&inside, 0.001, f32::INFINITY)),
+        );
+        assert!(union.hit(&inside, 0.001, 1.0).is_none());
+    }
+
+    #[test]
+    fn difference_should_carve_a_hole() {
+        let cube = || -> Box<dyn Hittable> {
+            Box::new(Cuboid::new(
+                Point3::new(-1.0, -1.0, -1.0),
+                Point3::new(1.0, 1.0, 1.0),
+                material(),
+            ))
+        };
+
+        let hollow = Difference::new(cube(), sphere(Point3::new(0.0, 0.0, 0.0), 0.5));
+        let ray = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        assert_hits(
+            &[(-1.0, true), (-0.5, false), (0.5, true), (1.0, false)],
+            &summary(&hollow.hit_all(&ray, 0.001, f32::INFINITY)),
+        );
+        assert!((hollow.hit(&ray, 0.001, f32::INFINITY).unwrap().t - 4.0).abs() < 1e-5);
+
+        // A sphere reaching past the faces leaves only the corners, so a ray
+        // along the axis passes straight through.
+        let corners = Difference::new(cube(), sphere(Point3::new(0.0, 0.0, 0.0), 1.5));
+        assert!(corners.hit(&ray, 0.001, f32::INFINITY).is_none());
+        let near_edge = Ray::new(Point3::new(0.9, 0.9, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        let z: f32 = (2.25_f32 - 1.62).sqrt();
+        assert_hits(
+            &[(-1.0, true), (-z, false), (z, true), (1.0, false)],
+            &summary(&corners.hit_all(&near_edge, 0.001, f32::INFINITY)),
+        );
+    }
+
+    #[test]
+    fn intersection_of_disjoint_operands_should_be_bounded_and_empty() {
+        let nothing: Box<dyn Hittable> = Box::new(Intersection::new(
+            sphere(Point3::new(0.0, 0.0, -2.0), 1.0),
+            sphere(Point3::new(0.0, 0.0, 2.0), 1.0),
+        ));
+        let bbox: Aabb = nothing.bounding_box(0.0, 1.0).unwrap();
+        assert_eq!(
+            Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 0.0, 0.0)),
+            bbox
+        );
+
+        let along_z = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        assert!(nothing.hit(&along_z, 0.001, f32::INFINITY).is_none());
+
+        // A `Bvh` only keeps aside objects without a box, so it has one.
+        let bvh = Bvh::new(
+            vec![nothing, sphere(Point3::new(5.0, 0.0, 0.0), 1.0)],
+            0.0,
+            1.0,
+        );
+        let bbox: Aabb = bvh.bounding_box(0.0, 1.0).unwrap();
+        assert_eq!(Point3::new(0.0, -1.0, -1.0), bbox.min());
+        assert!(bvh.hit(&along_z, 0.001, f32::INFINITY).is_none());
+    }
+}
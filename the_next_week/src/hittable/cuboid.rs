@@ -1,69 +1,63 @@
-use in_one_weekend::point::Point3;
+use in_one_weekend::{point::Point3, vec3::Vec3};
 
-use crate::{material::Material, ray::Ray};
+use crate::{material::Material, ray::Ray, sampler::Sampler};
 
-use super::{Hittable, HittableList, XYRect, XZRect, YZRect, HitRecord};
+use super::{Aabb, HitRecord, Hittable, HittableList, Quad};
 
-pub struct Cuboid<M: AsRef<dyn Material>> {
+pub struct Cuboid {
     min: Point3,
     max: Point3,
-    material: M,
     sides: HittableList<Box<dyn Hittable>>,
 }
 
-impl<M: AsRef<dyn Material> + Clone + Send + Sync + 'static> Cuboid<M> {
-    pub fn new(min: Point3, max: Point3, material: M) -> Self {
-        let mut sides: HittableList<Box<dyn Hittable>> = HittableList::default();
-        sides.add(Box::new(XYRect::new(
-            min.x()..=max.x(),
-            min.y()..=max.y(),
-            max.z(),
-            material.clone(),
-        )));
-        sides.add(Box::new(XYRect::new(
-            min.x()..=max.x(),
-            min.y()..=max.y(),
-            min.z(),
-            material.clone(),
-        )));
-
-        sides.add(Box::new(XZRect::new(
-            min.x()..=max.x(),
-            min.z()..=max.z(),
-            max.y(),
-            material.clone(),
-        )));
-        sides.add(Box::new(XZRect::new(
-            min.x()..=max.x(),
-            min.z()..=max.z(),
-            min.y(),
-            material.clone(),
-        )));
+impl Cuboid {
+    pub fn new<M>(min: Point3, max: Point3, material: M) -> Self
+    where
+        M: AsRef<dyn Material> + Clone + Send + Sync + 'static,
+    {
+        let dx: Vec3 = Vec3::new(max.x() - min.x(), 0.0, 0.0);
+        let dy: Vec3 = Vec3::new(0.0, max.y() - min.y(), 0.0);
+        let dz: Vec3 = Vec3::new(0.0, 0.0, max.z() - min.z());
 
-        sides.add(Box::new(YZRect::new(
-            min.y()..=max.y(),
-            min.z()..=max.z(),
-            max.x(),
-            material.clone(),
-        )));
-        sides.add(Box::new(YZRect::new(
-            min.y()..=max.y(),
-            min.z()..=max.z(),
-            min.x(),
-            material.clone(),
-        )));
-
-        Self {
-            min,
-            max,
-            material,
-            sides,
-        }
+        // Every side is spanned so that `u × v` points out of the cuboid.
+        let mut sides: HittableList<Box<dyn Hittable>> = HittableList::default();
+        [
+            // front, +z
+            (Point3::new(min.x(), min.y(), max.z()), dx, dy),
+            // right, +x
+            (Point3::new(max.x(), min.y(), max.z()), -dz, dy),
+            // back, -z
+            (Point3::new(max.x(), min.y(), min.z()), -dx, dy),
+            // left, -x
+            (Point3::new(min.x(), min.y(), min.z()), dz, dy),
+            // top, +y
+            (Point3::new(min.x(), max.y(), max.z()), dx, -dz),
+            // bottom, -y
+            (Point3::new(min.x(), min.y(), min.z()), dx, dz),
+        ]
+        .into_iter()
+        .for_each(|(q, u, v)| sides.add(Box::new(Quad::new(q, u, v, material.clone()))));
+
+        Self { min, max, sides }
     }
 }
 
-impl<M: AsRef<dyn Material> + Send + Sync> Hittable for Cuboid<M> {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+impl Hittable for Cuboid {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
         self.sides.hit(ray, t_min, t_max)
     }
+
+    fn hit_sampled(
+        &self,
+        ray: &Ray,
+        t_min: f32,
+        t_max: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
+        self.sides.hit_sampled(ray, t_min, t_max, sampler)
+    }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        Some(Aabb::new(self.min, self.max))
+    }
 }
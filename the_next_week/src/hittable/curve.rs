@@ -0,0 +1,518 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+
+use in_one_weekend::{point::Point3, vec3::Vec3};
+
+use crate::{material::Material, ray::Ray, stats};
+
+use super::{Aabb, HitRecord, Hittable};
+
+/// Deepest the subdivision of a segment goes, however curved it is.
+const MAX_DEPTH: u32 = 10;
+
+/// How a `Curve` looks across its width.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CurveShape {
+    /// A flat ribbon that always turns to face the ray, e.g. for grass seen
+    /// from afar.
+    Flat,
+    /// A round tube, shaded as such although it is intersected as a ribbon.
+    Tube,
+}
+
+/// A cubic Bézier segment swept with a width that varies linearly from
+/// `widths[0]` at the first control point to `widths[1]` at the last, for
+/// hair, fur and grass.
+///
+/// The curve is intersected as a ribbon facing the ray, by subdividing it in a
+/// frame looking along the ray until the pieces are about straight, so the
+/// width should be small compared to the curvature.
+///
+/// u: along the curve; v: across it, from 0 to 1.
+/// The hit record carries the direction of the curve as its tangent.
+pub struct Curve<M: AsRef<dyn Material>> {
+    control_points: [Point3; 4],
+    widths: [f32; 2],
+    shape: CurveShape,
+    material: M,
+}
+
+impl<M: AsRef<dyn Material>> Curve<M> {
+    pub fn new(control_points: [Point3; 4], widths: [f32; 2], material: M) -> Self {
+        debug_assert!(0.0 <= widths[0] && 0.0 <= widths[1]);
+        Self {
+            control_points,
+            widths,
+            shape: CurveShape::Tube,
+            material,
+        }
+    }
+
+    pub fn set_shape(mut self, shape: CurveShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    fn width(&self, u: f32) -> f32 {
+        (1.0 - u) * self.widths[0] + u * self.widths[1]
+    }
+}
+
+fn blossom(cp: &[Point3; 4], u: f32) -> (Point3, Vec3) {
+    let lerp = |a: Point3, b: Point3| (1.0 - u) * a + u * b;
+    let (a, b, c) = (lerp(cp[0], cp[1]), lerp(cp[1], cp[2]), lerp(cp[2], cp[3]));
+    let (d, e) = (lerp(a, b), lerp(b, c));
+    let derivative: Vec3 = match (e - d).is_near_zero() {
+        // Control points on top of each other at an end of the segment.
+        true => match u < 0.5 {
+            true => cp[2] - cp[0],
+            false => cp[3] - cp[1],
+        },
+        false => 3.0 * (e - d),
+    };
+
+    (lerp(d, e), derivative)
+}
+
+/// The two halves of the segment, split in the middle.
+fn subdivide(cp: &[Point3; 4]) -> ([Point3; 4], [Point3; 4]) {
+    let mid = |a: Point3, b: Point3| (a + b) / 2.0;
+    let (a, b, c) = (mid(cp[0], cp[1]), mid(cp[1], cp[2]), mid(cp[2], cp[3]));
+    let (d, e) = (mid(a, b), mid(b, c));
+    let f: Point3 = mid(d, e);
+
+    ([cp[0], a, d, f], [f, e, c, cp[3]])
+}
+
+/// Two unit vectors completing `w` to an orthonormal frame.
+fn perpendiculars(w: Vec3) -> (Vec3, Vec3) {
+    let u: Vec3 = match w.x().abs() > w.y().abs() {
+        true => Vec3::new(-w.z(), 0.0, w.x()),
+        false => Vec3::new(0.0, w.z(), -w.y()),
+    }
+    .unit_vector();
+
+    (u, w.cross(u))
+}
+
+/// Where the ray crosses the curve, before shading.
+struct Crossing {
+    t: f32,
+    u: f32,
+}
+
+/// What the subdivision needs to know about the ray, with the control points
+/// moved into a frame where the ray starts at the origin and runs along `+z`.
+struct Search<'a, M: AsRef<dyn Material>> {
+    curve: &'a Curve<M>,
+    max_half_width: f32,
+    speed: f32,
+    z_min: f32,
+}
+
+impl<M: AsRef<dyn Material>> Search<'_, M> {
+    fn recurse(
+        &self,
+        cp: &[Point3; 4],
+        u_range: [f32; 2],
+        depth: u32,
+        z_max: &mut f32,
+    ) -> Option<Crossing> {
+        let bbox: Aabb = Aabb::from_points(cp.iter().copied()).expect("four points");
+        let pad: f32 = self.max_half_width;
+        if 0.0 < bbox.min().x() - pad
+            || bbox.max().x() + pad < 0.0
+            || 0.0 < bbox.min().y() - pad
+            || bbox.max().y() + pad < 0.0
+            || *z_max < bbox.min().z() - pad
+            || bbox.max().z() + pad < self.z_min
+        {
+            return None;
+        }
+
+        if 0 < depth {
+            let (first, second) = subdivide(cp);
+            let u_mid: f32 = (u_range[0] + u_range[1]) / 2.0;
+            let near = self.recurse(&first, [u_range[0], u_mid], depth - 1, z_max);
+            let far = self.recurse(&second, [u_mid, u_range[1]], depth - 1, z_max);
+            return far.or(near);
+        }
+
+        // The ray must pass between the lines through the ends of the piece
+        // perpendicular to it.
+        let start_edge: f32 =
+            (cp[1].y() - cp[0].y()) * -cp[0].y() + cp[0].x() * (cp[0].x() - cp[1].x());
+        let end_edge: f32 =
+            (cp[2].y() - cp[3].y()) * -cp[3].y() + cp[3].x() * (cp[3].x() - cp[2].x());
+        if start_edge < 0.0 || end_edge < 0.0 {
+            return None;
+        }
+
+        // The closest point of the about straight piece to the ray.
+        let direction: Vec3 = Vec3::new(cp[3].x() - cp[0].x(), cp[3].y() - cp[0].y(), 0.0);
+        let length_squared: f32 = direction.len_squared();
+        if length_squared == 0.0 {
+            return None;
+        }
+        let w: f32 = (-(cp[0].x() * direction.x() + cp[0].y() * direction.y()) / length_squared)
+            .clamp(0.0, 1.0);
+        let u: f32 = (1.0 - w) * u_range[0] + w * u_range[1];
+        let half_width: f32 = self.curve.width(u) / 2.0;
+
+        let (point, _) = blossom(cp, w);
+        if half_width * half_width < point.x() * point.x() + point.y() * point.y()
+            || point.z() < self.z_min
+            || *z_max < point.z()
+        {
+            return None;
+        }
+
+        *z_max = point.z();
+        Some(Crossing {
+            t: point.z() / self.speed,
+            u,
+        })
+    }
+}
+
+impl<M: AsRef<dyn Material> + Send + Sync> Hittable for Curve<M> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        stats::primitive_test();
+        let speed: f32 = ray.direction().len();
+        let forward: Vec3 = ray.direction() / speed;
+        let (side, up) = perpendiculars(forward);
+        let to_ray_space = |p: Point3| {
+            let p: Vec3 = p - ray.origin();
+            Point3::new(p.dot(side), p.dot(up), p.dot(forward))
+        };
+        let cp: [Point3; 4] = self.control_points.map(to_ray_space);
+
+        // Enough subdivisions for the pieces to deviate from straight lines by
+        // a small fraction of the width.
+        let bend: f32 = (0..2)
+            .flat_map(|i| {
+                let second_difference: Vec3 = cp[i] - 2.0 * cp[i + 1] + cp[i + 2];
+                [
+                    second_difference.x().abs(),
+                    second_difference.y().abs(),
+                    second_difference.z().abs(),
+                ]
+            })
+            .fold(0.0, f32::max);
+        let epsilon: f32 = self.widths[0].max(self.widths[1]) * 0.05;
+        let depth: u32 = match 0.0 < bend && 0.0 < epsilon {
+            true => ((std::f32::consts::SQRT_2 * 6.0 * bend / (8.0 * epsilon)).log2() / 2.0)
+                .ceil()
+                .clamp(0.0, MAX_DEPTH as f32) as u32,
+            false => 0,
+        };
+
+        let search = Search {
+            curve: self,
+            max_half_width: self.widths[0].max(self.widths[1]) / 2.0,
+            speed,
+            z_min: t_min * speed,
+        };
+        let mut z_max: f32 = t_max * speed;
+        let crossing: Crossing = search.recurse(&cp, [0.0, 1.0], depth, &mut z_max)?;
+
+        let (axis, derivative) = blossom(&self.control_points, crossing.u);
+        let tangent: Vec3 = derivative.unit_vector();
+        let half_width: f32 = self.width(crossing.u) / 2.0;
+        let across: Vec3 = tangent.cross(forward);
+        // Looking down the curve, any direction across it will do.
+        let (across, facing): (Vec3, Vec3) = match across.is_near_zero() {
+            true => (side, -forward),
+            false => {
+                let across: Vec3 = across.unit_vector();
+                (across, across.cross(tangent))
+            }
+        };
+        let facing: Vec3 = match facing.dot(forward) < 0.0 {
+            true => facing,
+            false => -facing,
+        };
+        let h: f32 = match half_width {
+            0.0 => 0.0,
+            half_width => ((ray.at(crossing.t) - axis).dot(across) / half_width).clamp(-1.0, 1.0),
+        };
+
+        // Where the ray enters the tube around the axis, before the ribbon.
+        let bulge: f32 = (1.0 - h * h).sqrt();
+        let sin: f32 = (1.0 - tangent.dot(forward).powi(2)).sqrt().max(1e-3);
+        let t_tube: f32 = crossing.t - bulge * half_width / (sin * speed);
+        // Rays starting inside the fibre, such as those a hair material
+        // scatters through it, leave without hitting it again.
+        if t_tube < t_min {
+            return None;
+        }
+
+        let (t, outward_normal): (f32, Vec3) = match self.shape {
+            CurveShape::Flat => (crossing.t, facing),
+            CurveShape::Tube => (t_tube, (h * across + bulge * facing).unit_vector()),
+        };
+
+        let mut hit_record = HitRecord {
+            p: ray.at(t),
+            normal: outward_normal,
+            t,
+            front_face: true,
+            material: self.material.as_ref(),
+            uv: [crossing.u, (h + 1.0) / 2.0],
+            tangent: Some(tangent),
+            weight: Vec3::new(1.0, 1.0, 1.0),
+        };
+        hit_record.set_face_normal(ray, outward_normal);
+
+        Some(hit_record)
+    }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        let pad: f32 = self.widths[0].max(self.widths[1]) / 2.0;
+        let hull: Aabb = Aabb::from_points(self.control_points)?;
+
+        Some(Aabb::new(hull.min() - pad, hull.max() + pad))
+    }
+}
+
+/// One strand of a `Strands` file: the points its centre line passes through,
+/// from root to tip, and its width there.
+pub struct Strand {
+    points: Vec<Point3>,
+    root_width: f32,
+    tip_width: f32,
+}
+
+impl Strand {
+    pub fn points(&self) -> &[Point3] {
+        &self.points
+    }
+
+    pub fn root_width(&self) -> f32 {
+        self.root_width
+    }
+
+    pub fn tip_width(&self) -> f32 {
+        self.tip_width
+    }
+
+    /// The smooth Catmull-Rom spline through the points, as one `Curve` per
+    /// pair of neighbouring points.
+    fn curves<M: AsRef<dyn Material> + Clone>(&self, material: &M) -> Vec<Curve<M>> {
+        let points: &[Point3] = &self.points;
+        let last: usize = points.len() - 1;
+        let width = |i: usize| {
+            let fraction: f32 = i as f32 / last as f32;
+            (1.0 - fraction) * self.root_width + fraction * self.tip_width
+        };
+
+        (0..last)
+            .map(|i| {
+                let before: Point3 = points[i.saturating_sub(1)];
+                let after: Point3 = points[(i + 2).min(last)];
+                Curve::new(
+                    [
+                        points[i],
+                        points[i] + (points[i + 1] - before) / 6.0,
+                        points[i + 1] - (after - points[i]) / 6.0,
+                        points[i + 1],
+                    ],
+                    [width(i), width(i + 1)],
+                    material.clone(),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Hair, fur or grass read from a text file with one strand per line:
+///
+/// ```text
+/// # root_width tip_width x y z x y z ...
+/// 0.02 0.005 0 0 0  0 1 0.1  0.1 2 0.3
+/// ```
+///
+/// Each strand needs at least two points. Blank lines and everything after a
+/// `#` are ignored.
+pub struct Strands {
+    strands: Vec<Strand>,
+}
+
+impl Strands {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn read<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut strands: Vec<Strand> = Vec::new();
+        for (number, line) in reader.lines().enumerate() {
+            let line: String = line?;
+            let data: &str = line.split('#').next().unwrap_or_default();
+            if data.trim().is_empty() {
+                continue;
+            }
+
+            let values: Vec<f32> = data
+                .split_whitespace()
+                .map(|value| value.parse::<f32>())
+                .collect::<Result<_, _>>()
+                .map_err(|err| invalid_data(&format!("line {}: {err}", number + 1)))?;
+            if values.len() < 8 || !(values.len() - 2).is_multiple_of(3) {
+                return Err(invalid_data(&format!(
+                    "line {}: expected two widths and at least two points",
+                    number + 1
+                )));
+            }
+
+            strands.push(Strand {
+                root_width: values[0],
+                tip_width: values[1],
+                points: values[2..]
+                    .chunks_exact(3)
+                    .map(|p| Point3::new(p[0], p[1], p[2]))
+                    .collect(),
+            });
+        }
+
+        Ok(Self { strands })
+    }
+
+    pub fn strands(&self) -> &[Strand] {
+        &self.strands
+    }
+
+    /// Every strand as Bézier segments sharing `material`. With many strands,
+    /// put them in a `Bvh`.
+    pub fn curves<M: AsRef<dyn Material> + Clone>(&self, material: M) -> Vec<Curve<M>> {
+        self.strands
+            .iter()
+            .flat_map(|strand| strand.curves(&material))
+            .collect()
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use in_one_weekend::color::ColorRGBMapTo0_1;
+
+    use super::*;
+    use crate::{hittable::Bvh, material::Lambertian, textures::SolidColor};
+
+    fn material() -> Arc<dyn Material> {
+        Arc::new(Lambertian::new(SolidColor::from(ColorRGBMapTo0_1::new(
+            0.5, 0.5, 0.5,
+        ))))
+    }
+
+    fn straight(widths: [f32; 2]) -> Curve<Arc<dyn Material>> {
+        Curve::new(
+            [
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+                Point3::new(0.0, 2.0, 0.0),
+                Point3::new(0.0, 3.0, 0.0),
+            ],
+            widths,
+            material(),
+        )
+    }
+
+    #[test]
+    fn curve_should_look_like_a_tube() {
+        let tube = straight([0.2, 0.2]);
+        let ray = |x: f32| Ray::new(Point3::new(x, 1.5, -5.0), Vec3::new(0.0, 0.0, 2.0), 0.0);
+
+        // Through the middle it is hit where a cylinder of the same radius is.
+        let hit_record = tube.hit(&ray(0.0), 0.001, f32::INFINITY).unwrap();
+        assert!((hit_record.t - 4.9 / 2.0).abs() < 1e-4);
+        assert!((hit_record.normal() - Vec3::new(0.0, 0.0, -1.0)).len() < 1e-4);
+        assert!((hit_record.u() - 0.5).abs() < 1e-3 && (hit_record.v() - 0.5).abs() < 1e-3);
+        assert!((hit_record.tangent().unwrap() - Vec3::new(0.0, 1.0, 0.0)).len() < 1e-5);
+
+        // Off centre the normal turns to the side.
+        let hit_record = tube.hit(&ray(0.06), 0.001, f32::INFINITY).unwrap();
+        let z: f32 = -(0.01_f32 - 0.0036).sqrt();
+        assert!((hit_record.position().z() - z).abs() < 1e-4);
+        assert!((hit_record.normal() - Vec3::new(0.6, 0.0, z / 0.1)).len() < 1e-3);
+        assert!(tube.hit(&ray(0.11), 0.001, f32::INFINITY).is_none());
+
+        // A ribbon always faces the ray.
+        let ribbon = straight([0.2, 0.2]).set_shape(CurveShape::Flat);
+        let hit_record = ribbon.hit(&ray(0.06), 0.001, f32::INFINITY).unwrap();
+        assert!((hit_record.t - 2.5).abs() < 1e-4);
+        assert!((hit_record.normal() - Vec3::new(0.0, 0.0, -1.0)).len() < 1e-5);
+
+        // The width tapers from root to tip.
+        let tapered = straight([0.4, 0.0]);
+        let near_root = Ray::new(Point3::new(0.15, 0.3, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        let near_tip = Ray::new(Point3::new(0.15, 2.7, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        assert!(tapered.hit(&near_root, 0.001, f32::INFINITY).is_some());
+        assert!(tapered.hit(&near_tip, 0.001, f32::INFINITY).is_none());
+    }
+
+    #[test]
+    fn curve_should_follow_its_control_points() {
+        // An arc bending away from the Y axis and back.
+        let arc = Curve::new(
+            [
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(1.0, 1.0, 0.0),
+                Point3::new(1.0, 2.0, 0.0),
+                Point3::new(0.0, 3.0, 0.0),
+            ],
+            [0.05, 0.05],
+            material(),
+        );
+        let (middle, _) = blossom(&arc.control_points, 0.5);
+        assert!((middle.x() - 0.75).abs() < 1e-6);
+
+        let through = Ray::new(Point3::new(0.75, 1.5, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        let hit_record = arc.hit(&through, 0.001, f32::INFINITY).unwrap();
+        assert!((hit_record.u() - 0.5).abs() < 1e-2);
+        let bbox: Aabb = arc.bounding_box(0.0, 1.0).unwrap();
+        assert!((bbox.max().x() - 1.025).abs() < 1e-6);
+
+        let axis = Ray::new(Point3::new(0.0, 1.5, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        assert!(arc.hit(&axis, 0.001, f32::INFINITY).is_none());
+        assert!(arc.hit(&through, 0.001, 4.0).is_none());
+    }
+
+    #[test]
+    fn strands_should_load_as_curves() {
+        let text: &str = "# two strands\n\
+            0.02 0.01  0 0 0  0 1 0  0 2 0\n\
+            \n\
+            0.01 0.01  1 0 0  1 1 0 # a comment\n";
+        let strands = Strands::read(text.as_bytes()).unwrap();
+        assert_eq!(2, strands.strands().len());
+        assert_eq!(3, strands.strands()[0].points().len());
+        assert_eq!(0.02, strands.strands()[0].root_width());
+
+        let curves = strands.curves(material());
+        assert_eq!(3, curves.len());
+        // Neighbouring segments meet, and the width halves along the strand.
+        assert_eq!(curves[0].control_points[3], curves[1].control_points[0]);
+        assert!((curves[0].widths[1] - 0.015).abs() < 1e-6);
+        assert!((curves[1].control_points[1] - Point3::new(0.0, 4.0 / 3.0, 0.0)).len() < 1e-6);
+
+        let curves: Vec<Box<dyn Hittable>> = curves
+            .into_iter()
+            .map(|curve| Box::new(curve) as Box<dyn Hittable>)
+            .collect();
+        let bvh = Bvh::new(curves, 0.0, 1.0);
+        let ray = Ray::new(Point3::new(1.0, 0.5, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        assert!((bvh.hit(&ray, 0.001, f32::INFINITY).unwrap().t - 4.995).abs() < 1e-3);
+
+        assert!(Strands::read("0.1 0.1 0 0 0".as_bytes()).is_err());
+        assert!(Strands::read("0.1 0.1 0 0 0 1 x 1".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,418 @@
+use std::path::Path;
+
+use image::DynamicImage;
+use in_one_weekend::{point::Point3, vec3::Vec3};
+
+use crate::{material::Material, ray::Ray, stats};
+
+use super::{Aabb, HitRecord, Hittable};
+
+/// Terrain over a regular grid of height samples, two triangles per cell.
+///
+/// Sample `(i, j)` lies at `min + (i dx, heights[j][i], j dz)`, so columns run
+/// along +X and rows along +Z. UVs put sample `(i, j)` at the centre of pixel
+/// `(i, j)` of an `ImageTexture` with the same resolution, which lines colour
+/// maps up with the height data.
+///
+/// Rays descend a quadtree of min/max heights, only visiting the cells whose
+/// height range they pass through.
+pub struct Heightfield<M: AsRef<dyn Material>> {
+    /// Row-major world heights, `columns` per row.
+    heights: Vec<f32>,
+    normals: Vec<Vec3>,
+    columns: usize,
+    rows: usize,
+    min: Point3,
+    cell: [f32; 2],
+    /// `(min, max)` height per node; level 0 holds the cells and every level
+    /// above halves the resolution.
+    pyramid: Vec<Level>,
+    material: M,
+}
+
+struct Level {
+    columns: usize,
+    rows: usize,
+    ranges: Vec<(f32, f32)>,
+}
+
+impl<M: AsRef<dyn Material>> Heightfield<M> {
+    /// `heights` in [0,1] are scaled by `size.y()`, and the grid is stretched
+    /// over `size.x()` by `size.z()` starting at `min`.
+    pub fn new(
+        heights: Vec<f32>,
+        columns: usize,
+        rows: usize,
+        min: Point3,
+        size: Vec3,
+        material: M,
+    ) -> Self {
+        assert!(2 <= columns && 2 <= rows, "a heightfield needs 2x2 samples");
+        assert_eq!(columns * rows, heights.len());
+
+        let heights: Vec<f32> = heights
+            .into_iter()
+            .map(|height| min.y() + height * size.y())
+            .collect();
+        let cell: [f32; 2] = [
+            size.x() / (columns - 1) as f32,
+            size.z() / (rows - 1) as f32,
+        ];
+
+        let at = |i: usize, j: usize| heights[j * columns + i];
+        // Central differences, one-sided on the borders.
+        let normals: Vec<Vec3> = (0..rows)
+            .flat_map(|j| (0..columns).map(move |i| (i, j)))
+            .map(|(i, j)| {
+                let [i0, i1] = [i.saturating_sub(1), (i + 1).min(columns - 1)];
+                let [j0, j1] = [j.saturating_sub(1), (j + 1).min(rows - 1)];
+                let slope_x: f32 = (at(i1, j) - at(i0, j)) / ((i1 - i0) as f32 * cell[0]);
+                let slope_z: f32 = (at(i, j1) - at(i, j0)) / ((j1 - j0) as f32 * cell[1]);
+
+                Vec3::new(-slope_x, 1.0, -slope_z).unit_vector()
+            })
+            .collect();
+
+        let mut pyramid: Vec<Level> = vec![Level {
+            columns: columns - 1,
+            rows: rows - 1,
+            ranges: (0..rows - 1)
+                .flat_map(|j| (0..columns - 1).map(move |i| (i, j)))
+                .map(|(i, j)| {
+                    let corners = [at(i, j), at(i + 1, j), at(i, j + 1), at(i + 1, j + 1)];
+                    (
+                        corners.into_iter().fold(f32::INFINITY, f32::min),
+                        corners.into_iter().fold(f32::NEG_INFINITY, f32::max),
+                    )
+                })
+                .collect(),
+        }];
+        while let Some(below) = pyramid
+            .last()
+            .filter(|level| 1 < level.columns.max(level.rows))
+        {
+            let (columns, rows): (usize, usize) =
+                (below.columns.div_ceil(2), below.rows.div_ceil(2));
+            let ranges: Vec<(f32, f32)> = (0..rows)
+                .flat_map(|j| (0..columns).map(move |i| (i, j)))
+                .map(|(i, j)| {
+                    children(i, j, below.columns, below.rows)
+                        .map(|(ci, cj)| below.ranges[cj * below.columns + ci])
+                        .fold(
+                            (f32::INFINITY, f32::NEG_INFINITY),
+                            |(lo, hi), (min, max)| (lo.min(min), hi.max(max)),
+                        )
+                })
+                .collect();
+            pyramid.push(Level {
+                columns,
+                rows,
+                ranges,
+            });
+        }
+
+        Self {
+            heights,
+            normals,
+            columns,
+            rows,
+            min,
+            cell,
+            pyramid,
+            material,
+        }
+    }
+
+    /// Heights from the luminance of `image`, black the lowest and white the
+    /// highest, one sample per pixel.
+    pub fn from_image(image: &DynamicImage, min: Point3, size: Vec3, material: M) -> Self {
+        let luma = image.to_luma16();
+        let heights: Vec<f32> = luma
+            .pixels()
+            .map(|pixel| pixel.0[0] as f32 / u16::MAX as f32)
+            .collect();
+
+        Self::new(
+            heights,
+            luma.width() as usize,
+            luma.height() as usize,
+            min,
+            size,
+            material,
+        )
+    }
+
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        min: Point3,
+        size: Vec3,
+        material: M,
+    ) -> Result<Self, String> {
+        let image: DynamicImage = image::open(path).map_err(|err| err.to_string())?;
+
+        Ok(Self::from_image(&image, min, size, material))
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> Point3 {
+        Point3::new(
+            self.min.x() + i as f32 * self.cell[0],
+            self.heights[j * self.columns + i],
+            self.min.z() + j as f32 * self.cell[1],
+        )
+    }
+
+    /// The box of node `(i, j)` of pyramid level `level`.
+    fn node_bbox(&self, level: usize, i: usize, j: usize) -> Aabb {
+        let Level {
+            columns, ranges, ..
+        } = &self.pyramid[level];
+        let (low, high): (f32, f32) = ranges[j * columns + i];
+        let span: usize = 1 << level;
+        let [i1, j1] = [
+            ((i + 1) * span).min(self.columns - 1),
+            ((j + 1) * span).min(self.rows - 1),
+        ];
+
+        Aabb::new(
+            Point3::new(
+                self.min.x() + (i * span) as f32 * self.cell[0],
+                low,
+                self.min.z() + (j * span) as f32 * self.cell[1],
+            ),
+            Point3::new(
+                self.min.x() + i1 as f32 * self.cell[0],
+                high,
+                self.min.z() + j1 as f32 * self.cell[1],
+            ),
+        )
+    }
+
+    /// The nearest hit under node `(i, j)`, visiting children front to back so
+    /// that the first hit found is the nearest.
+    fn hit_node(
+        &self,
+        level: usize,
+        i: usize,
+        j: usize,
+        ray: &Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<HitRecord<'_>> {
+        self.node_bbox(level, i, j).hit(ray, t_min, t_max)?;
+        if level == 0 {
+            return self.hit_cell(i, j, ray, t_min, t_max);
+        }
+
+        let below: &Level = &self.pyramid[level - 1];
+        let mut children: Vec<(usize, usize)> = children(i, j, below.columns, below.rows).collect();
+        // Along each axis, the child nearer the ray origin comes first.
+        children.sort_by_key(|(ci, cj)| {
+            let far_x: usize = match 0.0 <= ray.direction().x() {
+                true => ci - 2 * i,
+                false => 1 - (ci - 2 * i),
+            };
+            let far_z: usize = match 0.0 <= ray.direction().z() {
+                true => cj - 2 * j,
+                false => 1 - (cj - 2 * j),
+            };
+            far_x + far_z
+        });
+
+        children
+            .into_iter()
+            .find_map(|(ci, cj)| self.hit_node(level - 1, ci, cj, ray, t_min, t_max))
+    }
+
+    fn hit_cell(
+        &self,
+        i: usize,
+        j: usize,
+        ray: &Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<HitRecord<'_>> {
+        stats::primitive_test();
+        // Split along the diagonal from (i, j) to (i + 1, j + 1).
+        [
+            [(i, j), (i + 1, j), (i + 1, j + 1)],
+            [(i, j), (i + 1, j + 1), (i, j + 1)],
+        ]
+        .into_iter()
+        .filter_map(|triangle| {
+            let [a, b, c] = triangle.map(|(i, j)| self.vertex(i, j));
+            let (t, [beta, gamma]) = intersect_triangle(ray, a, b, c)?;
+            match (t_min..=t_max).contains(&t) {
+                true => Some((t, triangle, [beta, gamma])),
+                false => None,
+            }
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(t, triangle, [beta, gamma])| {
+            let [n_a, n_b, n_c] = triangle.map(|(i, j)| self.normals[j * self.columns + i]);
+            let outward_normal: Vec3 =
+                ((1.0 - beta - gamma) * n_a + beta * n_b + gamma * n_c).unit_vector();
+
+            let p: Point3 = ray.at(t);
+            let [x, z]: [f32; 2] = [
+                (p.x() - self.min.x()) / self.cell[0],
+                (p.z() - self.min.z()) / self.cell[1],
+            ];
+            let uv: [f32; 2] = [
+                (x + 0.5) / self.columns as f32,
+                1.0 - (z + 0.5) / self.rows as f32,
+            ];
+
+            let mut hit_record = HitRecord {
+                p,
+                normal: outward_normal,
+                t,
+                front_face: true,
+                material: self.material.as_ref(),
+                uv,
+                tangent: None,
+                weight: Vec3::new(1.0, 1.0, 1.0),
+            };
+            hit_record.set_face_normal(ray, outward_normal);
+
+            hit_record
+        })
+    }
+}
+
+/// The nodes of the level below covered by node `(i, j)`.
+fn children(
+    i: usize,
+    j: usize,
+    columns: usize,
+    rows: usize,
+) -> impl Iterator<Item = (usize, usize)> {
+    [(0, 0), (1, 0), (0, 1), (1, 1)]
+        .into_iter()
+        .map(move |(di, dj)| (2 * i + di, 2 * j + dj))
+        .filter(move |(ci, cj)| *ci < columns && *cj < rows)
+}
+
+/// Möller–Trumbore: the ray parameter and the barycentric weights of `b` and
+/// `c` where the ray crosses triangle `abc`.
+fn intersect_triangle(ray: &Ray, a: Point3, b: Point3, c: Point3) -> Option<(f32, [f32; 2])> {
+    let (ab, ac): (Vec3, Vec3) = (b - a, c - a);
+    let p: Vec3 = ray.direction().cross(ac);
+    let determinant: f32 = ab.dot(p);
+    if determinant.abs() < 1e-12 {
+        return None;
+    }
+
+    let inverse: f32 = 1.0 / determinant;
+    let s: Vec3 = ray.origin() - a;
+    let beta: f32 = s.dot(p) * inverse;
+    if !(0.0..=1.0).contains(&beta) {
+        return None;
+    }
+    let q: Vec3 = s.cross(ab);
+    let gamma: f32 = ray.direction().dot(q) * inverse;
+    if gamma < 0.0 || 1.0 < beta + gamma {
+        return None;
+    }
+
+    Some((ac.dot(q) * inverse, [beta, gamma]))
+}
+
+impl<M: AsRef<dyn Material> + Send + Sync> Hittable for Heightfield<M> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.hit_node(self.pyramid.len() - 1, 0, 0, ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        let bbox: Aabb = self.node_bbox(self.pyramid.len() - 1, 0, 0);
+        // Keep a flat terrain from having a flat box.
+        let pad: Vec3 = Vec3::new(0.0, 0.0001, 0.0);
+
+        Some(Aabb::new(bbox.min() - pad, bbox.max() + pad))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use image::{GrayImage, Luma};
+    use in_one_weekend::color::ColorRGBMapTo0_1;
+
+    use super::*;
+    use crate::{material::Lambertian, textures::SolidColor};
+
+    fn material() -> Arc<dyn Material> {
+        Arc::new(Lambertian::new(SolidColor::from(ColorRGBMapTo0_1::new(
+            0.5, 0.5, 0.5,
+        ))))
+    }
+
+    #[test]
+    fn heightfield_should_hit_the_interpolated_surface() {
+        // A ramp rising along x, with 5 columns and 3 rows.
+        let heights: Vec<f32> = (0..3)
+            .flat_map(|_| (0..5).map(|i| i as f32 / 4.0))
+            .collect();
+        let ramp = Heightfield::new(
+            heights,
+            5,
+            3,
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(4.0, 2.0, 2.0),
+            material(),
+        );
+
+        let down =
+            |x: f32, z: f32| Ray::new(Point3::new(x, 10.0, z), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        let hit_record = ramp.hit(&down(1.3, 0.7), 0.001, f32::INFINITY).unwrap();
+        assert!((hit_record.position().y() - 0.65).abs() < 1e-5);
+        let expected: Vec3 = Vec3::new(-0.5, 1.0, 0.0).unit_vector();
+        assert!((hit_record.normal() - expected).len() < 1e-5);
+        assert!(hit_record.is_front_face());
+        assert!(ramp.hit(&down(4.1, 0.7), 0.001, f32::INFINITY).is_none());
+
+        // Samples sit at the centres of the pixels of a matching colour map.
+        let hit_record = ramp.hit(&down(0.0, 0.0), 0.001, f32::INFINITY).unwrap();
+        assert!((hit_record.u() - 0.1).abs() < 1e-5);
+        assert!((hit_record.v() - (1.0 - 1.0 / 6.0)).abs() < 1e-5);
+
+        // A ray skimming just above the slope never meets it.
+        let along = Ray::new(Point3::new(-1.0, -0.49, 1.0), Vec3::new(1.0, 0.5, 0.0), 0.0);
+        assert!(ramp.hit(&along, 0.001, f32::INFINITY).is_none());
+        let from_below = Ray::new(Point3::new(2.0, -1.0, 1.0), Vec3::new(0.0, 1.0, 0.0), 0.0);
+        assert!(!ramp
+            .hit(&from_below, 0.001, f32::INFINITY)
+            .unwrap()
+            .is_front_face());
+    }
+
+    #[test]
+    fn heightfield_should_find_the_nearest_peak() {
+        // Two peaks on flat ground, the nearer one lower.
+        let mut image = GrayImage::new(37, 20);
+        image.put_pixel(10, 7, Luma([100]));
+        image.put_pixel(30, 7, Luma([255]));
+        let terrain = Heightfield::from_image(
+            &DynamicImage::ImageLuma8(image),
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(36.0, 10.0, 19.0),
+            material(),
+        );
+        assert_eq!(7, terrain.pyramid.len());
+        let bbox: Aabb = terrain.bounding_box(0.0, 1.0).unwrap();
+        assert!((bbox.max().y() - 10.0).abs() < 1e-3);
+
+        let ray = Ray::new(Point3::new(0.0, 1.0, 7.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let hit_record = terrain.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((9.0..10.0).contains(&hit_record.position().x()));
+
+        let ray = Ray::new(Point3::new(40.0, 1.0, 7.0), Vec3::new(-1.0, 0.0, 0.0), 0.0);
+        let hit_record = terrain.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((30.0..31.0).contains(&hit_record.position().x()));
+
+        // Above the lower peak only the higher one is in the way.
+        let ray = Ray::new(Point3::new(0.0, 5.0, 7.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let hit_record = terrain.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((29.0..30.0).contains(&hit_record.position().x()));
+    }
+}
@@ -1,6 +1,6 @@
-use crate::ray::Ray;
+use crate::{ray::Ray, sampler::Sampler};
 
-use super::{HitRecord, Hittable};
+use super::{Aabb, HitRecord, Hittable};
 
 pub struct HittableList<H: AsRef<dyn Hittable>> {
     objects: Vec<H>,
@@ -32,7 +32,7 @@ impl<H: AsRef<dyn Hittable>> Default for HittableList<H> {
 }
 
 impl<H: AsRef<dyn Hittable> + Send + Sync> Hittable for HittableList<H> {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
         self.objects
             .iter()
             .filter_map(|object| object.as_ref().hit(ray, t_min, t_max))
@@ -44,4 +44,30 @@ impl<H: AsRef<dyn Hittable> + Send + Sync> Hittable for HittableList<H> {
                 }
             })
     }
+
+    fn hit_sampled(
+        &self,
+        ray: &Ray,
+        t_min: f32,
+        t_max: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
+        self.objects
+            .iter()
+            .filter_map(|object| object.as_ref().hit_sampled(ray, t_min, t_max, sampler))
+            .reduce(|closest_hit_record, current_hit_record| {
+                if current_hit_record.t < closest_hit_record.t {
+                    current_hit_record
+                } else {
+                    closest_hit_record
+                }
+            })
+    }
+
+    fn bounding_box(&self, time_0: f32, time_1: f32) -> Option<Aabb> {
+        self.objects
+            .iter()
+            .map(|object| object.as_ref().bounding_box(time_0, time_1))
+            .reduce(|bbox, object_bbox| Some(bbox?.surrounding(&object_bbox?)))?
+    }
 }
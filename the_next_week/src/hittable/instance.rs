@@ -1,16 +1,54 @@
-use in_one_weekend::{point::Point3, vec3::Vec3};
+use in_one_weekend::{
+    point::Point3,
+    transform::{AnimatedTransform, Keyframe, Transform},
+    vec3::Vec3,
+};
 
-use crate::{hittable::Hittable, ray::Ray};
+use crate::{
+    hittable::Hittable,
+    ray::Ray,
+    sampler::{Independent, Sampler},
+};
 
-use super::HitRecord;
+use super::{Aabb, HitRecord};
+
+/// Poses sampled between keyframes when bounding an animated instance.
+const ANIMATION_BOUND_STEPS: usize = 8;
 
 pub enum Instance<H: AsRef<dyn Hittable>> {
-    Translate { prototype: H, displacement: Vec3 },
-    RotateY { prototype: H, radians: f32 },
+    Translate {
+        prototype: H,
+        displacement: Vec3,
+    },
+    RotateY {
+        prototype: H,
+        radians: f32,
+    },
+    /// Any invertible affine transform from the prototype's space to the world.
+    Transform {
+        prototype: H,
+        transform: Transform,
+    },
+    /// A keyframed transform evaluated at each ray's time, which motion blurs
+    /// the prototype over the camera shutter.
+    Animated {
+        prototype: H,
+        animation: AnimatedTransform,
+    },
 }
 
 impl<H: AsRef<dyn Hittable> + Send + Sync> Hittable for Instance<H> {
-    fn hit(&self, ray: &crate::ray::Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+    fn hit(&self, ray: &crate::ray::Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.hit_sampled(ray, t_min, t_max, &mut Independent::seeded(0))
+    }
+
+    fn hit_sampled(
+        &self,
+        ray: &Ray,
+        t_min: f32,
+        t_max: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
         match self {
             Instance::Translate {
                 prototype,
@@ -19,10 +57,10 @@ impl<H: AsRef<dyn Hittable> + Send + Sync> Hittable for Instance<H> {
                 let ray_moved = Ray::new(ray.origin() - displacement, ray.direction(), ray.time());
                 prototype
                     .as_ref()
-                    .hit(&ray_moved, t_min, t_max)
+                    .hit_sampled(&ray_moved, t_min, t_max, sampler)
                     .map(|mut hit_record| {
                         hit_record.p += displacement;
-                        hit_record.set_face_normal(&ray_moved, hit_record.normal);
+                        hit_record.set_face_normal(&ray_moved, hit_record.outward_normal());
                         hit_record
                     })
             }
@@ -39,31 +77,293 @@ impl<H: AsRef<dyn Hittable> + Send + Sync> Hittable for Instance<H> {
 
                 prototype
                     .as_ref()
-                    .hit(
+                    .hit_sampled(
                         &Ray::new(origin_new, direction_new, ray.time()),
                         t_min,
                         t_max,
+                        sampler,
                     )
                     .map(|mut hit_record| {
-                        let HitRecord {
-                            mut p, mut normal, ..
-                        } = hit_record;
+                        let mut p: Point3 = hit_record.p;
+                        let local_normal: Vec3 = hit_record.outward_normal();
+                        let mut normal: Vec3 = local_normal;
 
                         *p.x_mut() = cos_theta * hit_record.p.x() + sin_theta * hit_record.p.z();
                         *p.z_mut() = -sin_theta * hit_record.p.x() + cos_theta * hit_record.p.z();
 
                         *normal.x_mut() =
-                            cos_theta * hit_record.normal.x() + sin_theta * hit_record.normal.z();
+                            cos_theta * local_normal.x() + sin_theta * local_normal.z();
                         *normal.z_mut() =
-                            -sin_theta * hit_record.normal.x() + cos_theta * hit_record.normal.z();
+                            -sin_theta * local_normal.x() + cos_theta * local_normal.z();
 
                         hit_record.p = p;
-                        hit_record.normal = normal;
                         hit_record.set_face_normal(ray, normal);
+                        hit_record.tangent = hit_record.tangent.map(|tangent| {
+                            Vec3::new(
+                                cos_theta * tangent.x() + sin_theta * tangent.z(),
+                                tangent.y(),
+                                -sin_theta * tangent.x() + cos_theta * tangent.z(),
+                            )
+                        });
 
                         hit_record
                     })
             }
+            Instance::Transform {
+                prototype,
+                transform,
+            } => hit_transformed(prototype.as_ref(), transform, ray, t_min, t_max, sampler),
+            Instance::Animated {
+                prototype,
+                animation,
+            } => hit_transformed(
+                prototype.as_ref(),
+                &animation.at(ray.time()),
+                ray,
+                t_min,
+                t_max,
+                sampler,
+            ),
         }
     }
+
+    fn bounding_box(&self, time_0: f32, time_1: f32) -> Option<Aabb> {
+        match self {
+            Instance::Translate {
+                prototype,
+                displacement,
+            } => {
+                let bbox: Aabb = prototype.as_ref().bounding_box(time_0, time_1)?;
+                Some(Aabb::new(
+                    bbox.min() + *displacement,
+                    bbox.max() + *displacement,
+                ))
+            }
+            Instance::RotateY { prototype, radians } => {
+                let bbox: Aabb = prototype.as_ref().bounding_box(time_0, time_1)?;
+                let (cos_theta, sin_theta): (f32, f32) = (radians.cos(), radians.sin());
+                Aabb::from_points(bbox.corners().map(|corner| {
+                    Point3::new(
+                        cos_theta * corner.x() + sin_theta * corner.z(),
+                        corner.y(),
+                        -sin_theta * corner.x() + cos_theta * corner.z(),
+                    )
+                }))
+            }
+            Instance::Transform {
+                prototype,
+                transform,
+            } => {
+                let bbox: Aabb = prototype.as_ref().bounding_box(time_0, time_1)?;
+                Aabb::from_points(bbox.corners().map(|corner| transform.point(corner)))
+            }
+            Instance::Animated {
+                prototype,
+                animation,
+            } => animated_bounding_box(
+                prototype.as_ref().bounding_box(time_0, time_1)?,
+                animation,
+                time_0,
+                time_1,
+            ),
+        }
+    }
+}
+
+fn hit_transformed<'a>(
+    prototype: &'a dyn Hittable,
+    transform: &Transform,
+    ray: &Ray,
+    t_min: f32,
+    t_max: f32,
+    sampler: &mut dyn Sampler,
+) -> Option<HitRecord<'a>> {
+    // The ray parameter `t` is the same in both spaces because the direction is
+    // transformed without being normalised.
+    let inverse: Transform = transform.inverse();
+    let ray_local = Ray::new(
+        inverse.point(ray.origin()),
+        inverse.vector(ray.direction()),
+        ray.time(),
+    );
+
+    prototype
+        .hit_sampled(&ray_local, t_min, t_max, sampler)
+        .map(|mut hit_record| {
+            hit_record.p = transform.point(hit_record.p);
+            let normal: Vec3 = transform.normal(hit_record.outward_normal());
+            hit_record.set_face_normal(ray, normal.unit_vector());
+            hit_record.tangent = hit_record
+                .tangent
+                .map(|tangent| transform.vector(tangent).unit_vector());
+
+            hit_record
+        })
+}
+
+/// Bound `bbox` over every pose the animation takes during `[time_0, time_1]`.
+///
+/// The interval is split at keyframes, so translation and scale are linear and
+/// the rotation turns at a constant rate between two consecutive samples `a`
+/// and `b`. Replacing the rotation by the one at `a` then moves a corner `c` by
+/// at most `2 sin(θ/2) |S c|`, where θ is the angle between the samples, and
+/// the pose with that fixed rotation lies on the segment between the samples.
+/// Padding the sampled corners by twice that bound keeps the box conservative.
+fn animated_bounding_box(
+    bbox: Aabb,
+    animation: &AnimatedTransform,
+    time_0: f32,
+    time_1: f32,
+) -> Option<Aabb> {
+    let mut breaks: Vec<f32> = vec![time_0];
+    breaks.extend(
+        animation
+            .keyframes()
+            .iter()
+            .map(|keyframe| keyframe.time)
+            .filter(|time| time_0 < *time && *time < time_1),
+    );
+    breaks.push(time_1);
+
+    let corners: [Point3; 8] = bbox.corners();
+    let radius = |keyframe: &Keyframe| -> f32 {
+        corners
+            .iter()
+            .map(|corner| {
+                Vec3::new(
+                    corner.x() * keyframe.scale.x(),
+                    corner.y() * keyframe.scale.y(),
+                    corner.z() * keyframe.scale.z(),
+                )
+                .len()
+            })
+            .fold(0.0, f32::max)
+    };
+
+    let mut points: Vec<Point3> = Vec::new();
+    let mut padding: f32 = 0.0;
+    for window in breaks.windows(2) {
+        let (start, end): (f32, f32) = (window[0], window[1]);
+        let poses: Vec<Keyframe> = (0..=ANIMATION_BOUND_STEPS)
+            .map(|step| {
+                animation
+                    .keyframe_at(start + (end - start) * step as f32 / ANIMATION_BOUND_STEPS as f32)
+            })
+            .collect();
+
+        poses.windows(2).for_each(|pair| {
+            let theta: f32 = pair[0].rotation.angle_to(&pair[1].rotation);
+            let bound: f32 = 2.0 * (theta / 2.0).sin() * radius(&pair[0]).max(radius(&pair[1]));
+            padding = padding.max(2.0 * bound);
+        });
+        poses.iter().for_each(|pose| {
+            let transform: Transform = pose.to_transform();
+            points.extend(corners.map(|corner| transform.point(corner)));
+        });
+    }
+
+    let bbox: Aabb = Aabb::from_points(points)?;
+    let padding: Vec3 = Vec3::new(padding, padding, padding);
+    Some(Aabb::new(bbox.min() - padding, bbox.max() + padding))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use in_one_weekend::{color::ColorRGBMapTo0_1, quaternion::Quaternion};
+
+    use super::*;
+    use crate::{
+        hittable::{Cuboid, Sphere},
+        material::{Lambertian, Material},
+        textures::SolidColor,
+    };
+
+    #[test]
+    fn transform_instance_should_hit_the_transformed_prototype() {
+        let material: Arc<dyn Material> = Arc::new(Lambertian::new(SolidColor::from(
+            ColorRGBMapTo0_1::new(0.5, 0.5, 0.5),
+        )));
+        let ellipsoid = Instance::Transform {
+            prototype: Box::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, material))
+                as Box<dyn Hittable>,
+            transform: Transform::scale(Vec3::new(2.0, 1.0, 1.0))
+                .then(Transform::rotate_z(90_f32.to_radians()))
+                .then(Transform::translate(Vec3::new(0.0, 0.0, 5.0))),
+        };
+
+        // The long axis now points along y.
+        let ray = Ray::new(Point3::new(0.0, -10.0, 5.0), Vec3::new(0.0, 2.0, 0.0), 0.0);
+        let hit_record = ellipsoid.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((hit_record.t - 4.0).abs() < 1e-4);
+        assert!((hit_record.position() - Point3::new(0.0, -2.0, 5.0)).len() < 1e-4);
+        assert!((hit_record.normal() - Vec3::new(0.0, -1.0, 0.0)).len() < 1e-4);
+        assert!(hit_record.is_front_face());
+
+        // A tilted ray hitting the side, where the normal needs the inverse transpose.
+        let p: Point3 = Point3::new(0.84_f32.sqrt(), 0.8, 5.0);
+        let ray = Ray::new(Point3::new(10.0, 0.8, 5.0), Vec3::new(-1.0, 0.0, 0.0), 0.0);
+        let hit_record = ellipsoid.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((hit_record.position() - p).len() < 1e-4);
+        let expected_normal: Vec3 = Vec3::new(p.x(), p.y() / 4.0, 0.0).unit_vector();
+        assert!((hit_record.normal() - expected_normal).len() < 1e-4);
+
+        let bbox: Aabb = ellipsoid.bounding_box(0.0, 1.0).unwrap();
+        assert!((bbox.min() - Point3::new(-1.0, -2.0, 4.0)).len() < 1e-4);
+        assert!((bbox.max() - Point3::new(1.0, 2.0, 6.0)).len() < 1e-4);
+    }
+
+    #[test]
+    fn animated_instance_should_move_within_its_bounding_box() {
+        let material: Arc<dyn Material> = Arc::new(Lambertian::new(SolidColor::from(
+            ColorRGBMapTo0_1::new(0.5, 0.5, 0.5),
+        )));
+        let y: Vec3 = Vec3::new(0.0, 1.0, 0.0);
+        let cuboid = Instance::Animated {
+            prototype: Box::new(Cuboid::new(
+                Point3::new(-1.0, -1.0, -1.0),
+                Point3::new(1.0, 1.0, 1.0),
+                material,
+            )) as Box<dyn Hittable>,
+            animation: AnimatedTransform::new(vec![
+                Keyframe::new(
+                    0.0,
+                    Vec3::new(0.0, 0.0, 0.0),
+                    Quaternion::identity(),
+                    Vec3::new(1.0, 1.0, 1.0),
+                ),
+                Keyframe::new(
+                    1.0,
+                    Vec3::new(0.0, 0.0, 4.0),
+                    Quaternion::from_axis_angle(y, 45_f32.to_radians()),
+                    Vec3::new(1.0, 1.0, 1.0),
+                ),
+            ]),
+        };
+
+        // The cube moves along z, so the same ray hits it at different depths.
+        let ray_at =
+            |time: f32| Ray::new(Point3::new(0.0, 0.0, -10.0), Vec3::new(0.0, 0.0, 1.0), time);
+        assert!((cuboid.hit(&ray_at(0.0), 0.001, f32::INFINITY).unwrap().t - 9.0).abs() < 1e-4);
+        let t_end: f32 = 14.0 - 2_f32.sqrt();
+        assert!((cuboid.hit(&ray_at(1.0), 0.001, f32::INFINITY).unwrap().t - t_end).abs() < 1e-4);
+
+        let bbox: Aabb = cuboid.bounding_box(0.0, 1.0).unwrap();
+        let corners: [Point3; 8] =
+            Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0)).corners();
+        (0..=1000).for_each(|i| {
+            let transform: Transform = match &cuboid {
+                Instance::Animated { animation, .. } => animation.at(i as f32 / 1000.0),
+                _ => unreachable!(),
+            };
+            corners.iter().for_each(|corner| {
+                let p: Point3 = transform.point(*corner);
+                (0..3).for_each(|axis| {
+                    assert!(bbox.min()[axis] <= p[axis] && p[axis] <= bbox.max()[axis]);
+                })
+            });
+        });
+        assert!(bbox.max().z() < 6.0);
+    }
 }
@@ -1,32 +1,56 @@
-use in_one_weekend::vec3::Vec3;
-use rand::random;
+use in_one_weekend::{color::ColorRGBMapTo0_1, point::Point3, vec3::Vec3};
 
-use crate::{material::Isotropic, ray::Ray, textures::Texture};
+use crate::{
+    material::{Attenuation, Emit, EmitRecord, Scatter, ScatterRecord, Volumetric},
+    phase::{IsotropicPhase, PhaseFunction},
+    ray::Ray,
+    sampler::{Independent, Sampler},
+    stats,
+    textures::{SolidColor, Texture},
+    volume::{MajorantGrid, VoxelGrid, BLOCK_SIZE},
+};
 
-use super::{HitRecord, Hittable};
+use super::{Aabb, HitRecord, Hittable, HittableList};
 
-pub struct ConstantMedium<H: Hittable, T: Texture> {
+pub struct ConstantMedium<H: Hittable, T: Texture, P: PhaseFunction = IsotropicPhase> {
     boundary: H,
-    phase_function: Isotropic<T>,
+    material: Volumetric<T, P>,
     neg_inv_density: f32,
 }
 
 impl<H: Hittable, T: Texture> ConstantMedium<H, T> {
     pub fn new(boundary: H, texture: T, density: f32) -> Self {
+        Self::with_phase_function(boundary, texture, density, IsotropicPhase)
+    }
+}
+
+impl<H: Hittable, T: Texture, P: PhaseFunction> ConstantMedium<H, T, P> {
+    pub fn with_phase_function(boundary: H, texture: T, density: f32, phase_function: P) -> Self {
         Self {
             boundary,
-            phase_function: Isotropic::new(texture),
+            material: Volumetric::new(texture, phase_function),
             neg_inv_density: -1.0 / density,
         }
     }
 }
 
-impl<H, T> Hittable for ConstantMedium<H, T>
+impl<H, T, P> Hittable for ConstantMedium<H, T, P>
 where
     H: Hittable,
     T: Texture + Send + Sync,
+    P: PhaseFunction,
 {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.hit_sampled(ray, t_min, t_max, &mut Independent::seeded(0))
+    }
+
+    fn hit_sampled(
+        &self,
+        ray: &Ray,
+        t_min: f32,
+        t_max: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
         let rec1 = self.boundary.hit(ray, f32::NEG_INFINITY, f32::INFINITY)?;
         let rec2 = self.boundary.hit(ray, rec1.t + 0.0001, f32::INFINITY)?;
 
@@ -48,7 +72,7 @@ where
 
         let length_per_unit = ray.direction().len();
         let distance_inside = (t2 - t1) * length_per_unit;
-        let hit_distance = self.neg_inv_density * random::<f32>().ln();
+        let hit_distance = self.neg_inv_density * (1.0 - sampler.get_1d()).ln();
 
         if hit_distance > distance_inside {
             return None;
@@ -61,8 +85,607 @@ where
             normal: Vec3::new(1.0, 0.0, 0.0), // useless,
             t: hit_point_unit,
             front_face: true, // useless
-            material: &self.phase_function,
+            material: &self.material,
+            uv: [0.0, 0.0], // useless
+            tangent: None,
+            weight: Vec3::new(1.0, 1.0, 1.0),
+        })
+    }
+
+    fn bounding_box(&self, time_0: f32, time_1: f32) -> Option<Aabb> {
+        self.boundary.bounding_box(time_0, time_1)
+    }
+}
+
+/// A homogeneous medium whose absorption and scattering coefficients differ per
+/// RGB channel, and which may glow where it absorbs (fire, neon haze, coloured
+/// fog).
+///
+/// Free-flight distances are sampled with the extinction of one "hero" channel
+/// picked uniformly per traversal, and every channel is weighted by its own
+/// transmittance over the mean sampling density of all channels (spectral MIS),
+/// so the estimate stays unbiased even where the channels disagree. When no
+/// collision happens the ray still stops at the exit of the boundary, or at the
+/// first of the `contents` in the way, to pick up its transmittance weight. Like
+/// `ConstantMedium` the boundary has to be convex, and objects inside it belong
+/// in the contents rather than next to the medium in the world, which would
+/// see them lit through the mean transmittance of the channels.
+pub struct ChromaticMedium<H: Hittable, E: Texture, P: PhaseFunction = IsotropicPhase> {
+    boundary: H,
+    extinction: Vec3,
+    interaction: MediumInteraction<E, P>,
+    pass_through: PassThrough,
+    contents: HittableList<Box<dyn Hittable>>,
+}
+
+impl<H: Hittable> ChromaticMedium<H, SolidColor, IsotropicPhase> {
+    /// `absorption` and `scattering` are the per-channel coefficients per unit
+    /// length, the medium does not emit and scatters isotropically.
+    pub fn new(boundary: H, absorption: Vec3, scattering: Vec3) -> Self {
+        Self {
+            boundary,
+            extinction: absorption + scattering,
+            interaction: MediumInteraction {
+                absorption,
+                scattering,
+                emission: SolidColor::from(ColorRGBMapTo0_1::new(0.0, 0.0, 0.0)),
+                luminance: 0.0,
+                phase_function: IsotropicPhase,
+            },
+            pass_through: PassThrough,
+            contents: HittableList::default(),
+        }
+    }
+}
+
+impl<H: Hittable, E: Texture, P: PhaseFunction> ChromaticMedium<H, E, P> {
+    /// Make the medium emit `texture * luminance` per unit of absorption.
+    pub fn set_emission<F: Texture>(self, texture: F, luminance: f32) -> ChromaticMedium<H, F, P> {
+        let MediumInteraction {
+            absorption,
+            scattering,
+            phase_function,
+            ..
+        } = self.interaction;
+
+        ChromaticMedium {
+            boundary: self.boundary,
+            extinction: self.extinction,
+            interaction: MediumInteraction {
+                absorption,
+                scattering,
+                emission: texture,
+                luminance,
+                phase_function,
+            },
+            pass_through: self.pass_through,
+            contents: self.contents,
+        }
+    }
+
+    /// The objects inside the boundary, seen through the medium.
+    pub fn set_contents(mut self, contents: HittableList<Box<dyn Hittable>>) -> Self {
+        self.contents = contents;
+        self
+    }
+
+    pub fn set_phase_function<Q: PhaseFunction>(
+        self,
+        phase_function: Q,
+    ) -> ChromaticMedium<H, E, Q> {
+        let MediumInteraction {
+            absorption,
+            scattering,
+            emission,
+            luminance,
+            ..
+        } = self.interaction;
+
+        ChromaticMedium {
+            boundary: self.boundary,
+            extinction: self.extinction,
+            interaction: MediumInteraction {
+                absorption,
+                scattering,
+                emission,
+                luminance,
+                phase_function,
+            },
+            pass_through: self.pass_through,
+            contents: self.contents,
+        }
+    }
+
+    fn transmittance(&self, distance: f32) -> Vec3 {
+        Vec3::new(
+            (-self.extinction.x() * distance).exp(),
+            (-self.extinction.y() * distance).exp(),
+            (-self.extinction.z() * distance).exp(),
+        )
+    }
+}
+
+impl<H, E, P> Hittable for ChromaticMedium<H, E, P>
+where
+    H: Hittable,
+    E: Texture + Send + Sync,
+    P: PhaseFunction,
+{
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.hit_sampled(ray, t_min, t_max, &mut Independent::seeded(0))
+    }
+
+    fn hit_sampled(
+        &self,
+        ray: &Ray,
+        t_min: f32,
+        t_max: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
+        let inside: Option<HitRecord<'_>> = self.contents.hit_sampled(ray, t_min, t_max, sampler);
+        let Some((rec1, rec2)) = self
+            .boundary
+            .hit(ray, f32::NEG_INFINITY, f32::INFINITY)
+            .and_then(|rec1| {
+                let rec2 = self.boundary.hit(ray, rec1.t + 0.0001, f32::INFINITY)?;
+                Some((rec1, rec2))
+            })
+        else {
+            return inside;
+        };
+
+        let [mut t1, mut t2] = [rec1.t, rec2.t];
+
+        if t1 < t_min {
+            t1 = t_min
+        };
+        // The ray stops at the first of the contents it hits, if before the exit.
+        let t_stop: f32 = inside.as_ref().map_or(t_max, |hit_record| hit_record.t);
+        let exits_inside_range: bool = t2 <= t_stop;
+        if !exits_inside_range {
+            t2 = t_stop
+        };
+        if t1 >= t2 {
+            return inside;
+        }
+
+        if t1 < 0.0 {
+            t1 = 0.0;
+        }
+
+        let length_per_unit = ray.direction().len();
+        let distance_inside = (t2 - t1) * length_per_unit;
+
+        let hero: u8 = ((sampler.get_1d() * 3.0) as u8).min(2);
+        let hero_extinction: f32 = self.extinction[hero];
+        let hit_distance = match 0.0 < hero_extinction {
+            true => -(1.0 - sampler.get_1d()).ln() / hero_extinction,
+            false => f32::INFINITY,
+        };
+
+        let transmittance: Vec3 = self.transmittance(hit_distance.min(distance_inside));
+        if hit_distance > distance_inside {
+            // The probability of flying through is the mean transmittance.
+            let pdf: f32 = (transmittance.x() + transmittance.y() + transmittance.z()) / 3.0;
+            if pdf <= 0.0 {
+                return None;
+            }
+            if !exits_inside_range {
+                return inside.map(|hit_record| HitRecord {
+                    weight: hit_record.weight * transmittance / pdf,
+                    ..hit_record
+                });
+            }
+
+            return Some(HitRecord {
+                p: ray.at(t2),
+                normal: rec2.normal,
+                t: t2,
+                front_face: rec2.front_face,
+                material: &self.pass_through,
+                uv: [0.0, 0.0], // useless
+                tangent: None,
+                weight: transmittance / pdf,
+            });
+        }
+
+        // The collision density is the mean over channels of σt·T.
+        let density: Vec3 = self.extinction * transmittance;
+        let pdf: f32 = (density.x() + density.y() + density.z()) / 3.0;
+        let hit_point_unit = t1 + hit_distance / length_per_unit;
+
+        Some(HitRecord {
+            p: ray.at(hit_point_unit),
+            normal: Vec3::new(1.0, 0.0, 0.0), // useless,
+            t: hit_point_unit,
+            front_face: true, // useless
+            material: &self.interaction,
+            uv: [0.0, 0.0], // useless
+            tangent: None,
+            weight: density / pdf,
+        })
+    }
+
+    fn bounding_box(&self, time_0: f32, time_1: f32) -> Option<Aabb> {
+        self.boundary.bounding_box(time_0, time_1)
+    }
+}
+
+/// What happens at a collision inside a `ChromaticMedium`. The hit record weight
+/// already carries `σt·T / pdf`, so absorption and scattering only contribute
+/// their share `σa / σt` and `σs / σt` of the extinction.
+struct MediumInteraction<E: Texture, P: PhaseFunction> {
+    absorption: Vec3,
+    scattering: Vec3,
+    emission: E,
+    luminance: f32,
+    phase_function: P,
+}
+
+impl<E: Texture, P: PhaseFunction> MediumInteraction<E, P> {
+    fn fraction_of_extinction(&self, coefficient: Vec3) -> Vec3 {
+        let extinction: Vec3 = self.absorption + self.scattering;
+        let fraction = |c: f32, t: f32| if 0.0 < t { c / t } else { 0.0 };
+
+        Vec3::new(
+            fraction(coefficient.x(), extinction.x()),
+            fraction(coefficient.y(), extinction.y()),
+            fraction(coefficient.z(), extinction.z()),
+        )
+    }
+}
+
+impl<E: Texture, P: PhaseFunction> Emit for MediumInteraction<E, P> {
+    fn emitted(&self, u: f32, v: f32, p: &Point3) -> Option<EmitRecord> {
+        if self.luminance <= 0.0 {
+            return None;
+        }
+
+        let absorbed: Vec3 = self.fraction_of_extinction(self.absorption);
+        let color: ColorRGBMapTo0_1 = self.emission.value(u, v, p);
+
+        Some(EmitRecord::new(
+            ColorRGBMapTo0_1::new(
+                color.r() * absorbed.x(),
+                color.g() * absorbed.y(),
+                color.b() * absorbed.z(),
+            ),
+            self.luminance,
+        ))
+    }
+}
+
+impl<E: Texture, P: PhaseFunction> Scatter for MediumInteraction<E, P> {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
+        stats::scatter("medium");
+        Some(ScatterRecord::new(
+            Ray::new(
+                hit_record.position(),
+                self.phase_function.sample(ray_in.direction(), sampler),
+                ray_in.time(),
+            ),
+            Attenuation::new(self.fraction_of_extinction(self.scattering)),
+        ))
+    }
+}
+
+/// Continues the ray unchanged where it leaves a `ChromaticMedium`.
+struct PassThrough;
+
+impl Emit for PassThrough {}
+
+impl Scatter for PassThrough {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
+        Some(ScatterRecord::new(
+            Ray::new(hit_record.position(), ray_in.direction(), ray_in.time()),
+            Attenuation::new(Vec3::new(1.0, 1.0, 1.0)),
+        ))
+    }
+}
+
+/// A heterogeneous medium whose density is a `VoxelGrid` scaled by
+/// `density_scale`, bounded by the grid's box.
+///
+/// Any other placement, such as a rotation, comes from wrapping it in an
+/// `Instance::Transform`. The density is then per unit of length in the
+/// grid's own space, so that scaling the medium keeps how opaque it is.
+///
+/// Collisions are found with delta tracking: the ray walks the cells of a
+/// `MajorantGrid` with a 3D DDA and, inside each cell, takes exponential steps
+/// against that cell's majorant, accepting a step as a real collision with
+/// probability density / majorant. Thin and empty cells are crossed in a few
+/// steps however finely the grid is sampled.
+pub struct GridMedium<T: Texture, P: PhaseFunction = IsotropicPhase> {
+    grid: VoxelGrid,
+    majorants: MajorantGrid,
+    density_scale: f32,
+    material: Volumetric<T, P>,
+}
+
+impl<T: Texture> GridMedium<T> {
+    pub fn new(grid: VoxelGrid, texture: T, density_scale: f32) -> Self {
+        Self::with_phase_function(grid, texture, density_scale, IsotropicPhase)
+    }
+}
+
+impl<T: Texture, P: PhaseFunction> GridMedium<T, P> {
+    pub fn with_phase_function(
+        grid: VoxelGrid,
+        texture: T,
+        density_scale: f32,
+        phase_function: P,
+    ) -> Self {
+        Self {
+            majorants: MajorantGrid::new(&grid),
+            grid,
+            density_scale,
+            material: Volumetric::new(texture, phase_function),
+        }
+    }
+
+    /// Ray parameter of the first real collision in `[t_enter, t_exit]`.
+    fn track(
+        &self,
+        ray: &Ray,
+        t_enter: f32,
+        t_exit: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<f32> {
+        let [nx, ny, nz]: [usize; 3] = self.majorants.resolution();
+        let resolution: [f32; 3] = [nx as f32, ny as f32, nz as f32];
+        let length_per_unit: f32 = ray.direction().len();
+
+        // Walk the majorant cells in voxel space, where a cell is BLOCK_SIZE wide.
+        let voxel_size: Vec3 = self.grid.voxel_size();
+        let origin: [f32; 3] = self.grid.to_voxel_space(&ray.origin());
+        let direction: [f32; 3] = [0, 1, 2].map(|axis| ray.direction()[axis] / voxel_size[axis]);
+        let cell_size: f32 = BLOCK_SIZE as f32;
+
+        let mut cell: [f32; 3] = [0, 1, 2].map(|axis| {
+            ((origin[axis] + t_enter * direction[axis]) / cell_size)
+                .floor()
+                .clamp(0.0, resolution[axis] - 1.0)
+        });
+        let step: [f32; 3] = direction.map(|d| if d < 0.0 { -1.0 } else { 1.0 });
+        let t_delta: [f32; 3] = direction.map(|d| (cell_size / d).abs());
+        let mut t_next: [f32; 3] = [0, 1, 2].map(|axis| {
+            let boundary: f32 = (cell[axis] + step[axis].max(0.0)) * cell_size;
+            match direction[axis] {
+                0.0 => f32::INFINITY,
+                d => (boundary - origin[axis]) / d,
+            }
+        });
+
+        let mut t: f32 = t_enter;
+        loop {
+            let axis: usize = (0..3)
+                .min_by(|&a, &b| t_next[a].total_cmp(&t_next[b]))
+                .unwrap_or_default();
+            let cell_exit: f32 = t_next[axis].min(t_exit);
+
+            let majorant: f32 = self.density_scale
+                * self
+                    .majorants
+                    .majorant(cell[0] as usize, cell[1] as usize, cell[2] as usize);
+            if 0.0 < majorant {
+                loop {
+                    t -= (1.0 - sampler.get_1d()).ln() / (majorant * length_per_unit);
+                    if t >= cell_exit {
+                        break;
+                    }
+                    let density: f32 = self.density_scale * self.grid.density(&ray.at(t));
+                    if sampler.get_1d() * majorant < density {
+                        return Some(t);
+                    }
+                }
+            }
+
+            // Free flight is memoryless, so restart at the cell boundary.
+            t = cell_exit;
+            if t_exit <= t {
+                return None;
+            }
+            cell[axis] += step[axis];
+            if !(0.0..resolution[axis]).contains(&cell[axis]) {
+                return None;
+            }
+            t_next[axis] += t_delta[axis];
+        }
+    }
+}
+
+impl<T, P> Hittable for GridMedium<T, P>
+where
+    T: Texture + Send + Sync,
+    P: PhaseFunction,
+{
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        self.hit_sampled(ray, t_min, t_max, &mut Independent::seeded(0))
+    }
+
+    fn hit_sampled(
+        &self,
+        ray: &Ray,
+        t_min: f32,
+        t_max: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
+        let (t_enter, t_exit) = self.grid.bounds().hit(ray, t_min.max(0.0), t_max)?;
+        let t: f32 = self.track(ray, t_enter, t_exit, sampler)?;
+
+        Some(HitRecord {
+            p: ray.at(t),
+            normal: Vec3::new(1.0, 0.0, 0.0), // useless,
+            t,
+            front_face: true, // useless
+            material: &self.material,
             uv: [0.0, 0.0], // useless
+            tangent: None,
+            weight: Vec3::new(1.0, 1.0, 1.0),
         })
     }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        Some(self.grid.bounds())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use in_one_weekend::{point::Point3, transform::Transform};
+
+    use super::*;
+    use crate::{
+        hittable::{Instance, Sphere},
+        material::{Lambertian, Material},
+    };
+
+    #[test]
+    fn chromatic_medium_transmittance_should_be_unbiased() {
+        let boundary = Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            1.0,
+            Arc::new(Lambertian::new(SolidColor::from(ColorRGBMapTo0_1::new(
+                0.5, 0.5, 0.5,
+            )))) as Arc<dyn Material>,
+        );
+        let absorption = Vec3::new(0.1, 0.5, 2.0);
+        let medium = ChromaticMedium::new(boundary, absorption, Vec3::new(0.0, 0.0, 0.0));
+        let ray = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+
+        // Light only gets through the 2 units of medium without colliding.
+        const SAMPLES: usize = 200_000;
+        let mut sampler: Independent = Independent::new().set_seed(1);
+        let estimate: Vec3 = (0..SAMPLES)
+            .filter_map(|_| medium.hit_sampled(&ray, 0.001, f32::INFINITY, &mut sampler))
+            .filter(|hit_record| (hit_record.t - 6.0).abs() < 1e-3)
+            .fold(Vec3::default(), |sum, hit_record| sum + hit_record.weight())
+            / SAMPLES as f32;
+
+        let expected: Vec3 = medium.transmittance(2.0);
+        assert!(
+            (estimate - expected).len() < 0.01,
+            "{estimate} != {expected}"
+        );
+    }
+
+    #[test]
+    fn chromatic_medium_should_weight_its_contents_by_each_channel() {
+        let material = || {
+            Arc::new(Lambertian::new(SolidColor::from(ColorRGBMapTo0_1::new(
+                0.5, 0.5, 0.5,
+            )))) as Arc<dyn Material>
+        };
+        let mut contents: HittableList<Box<dyn Hittable>> = HittableList::default();
+        contents.add(Box::new(Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            0.5,
+            material(),
+        )));
+        let medium = ChromaticMedium::new(
+            Sphere::new(Point3::new(0.0, 0.0, 0.0), 2.0, material()),
+            Vec3::new(0.1, 0.5, 2.0),
+            Vec3::new(0.0, 0.0, 0.0),
+        )
+        .set_contents(contents);
+        let ray = Ray::new(Point3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+
+        // The inner sphere is seen through 1.5 units of medium, at t = 4.5.
+        const SAMPLES: usize = 200_000;
+        let mut sampler: Independent = Independent::new().set_seed(1);
+        let estimate: Vec3 = (0..SAMPLES)
+            .filter_map(|_| medium.hit_sampled(&ray, 0.001, f32::INFINITY, &mut sampler))
+            .filter(|hit_record| (hit_record.t - 4.5).abs() < 1e-3)
+            .fold(Vec3::default(), |sum, hit_record| sum + hit_record.weight())
+            / SAMPLES as f32;
+
+        let expected: Vec3 = medium.transmittance(1.5);
+        assert!(
+            (estimate - expected).len() < 0.01,
+            "{estimate} != {expected}"
+        );
+    }
+
+    #[test]
+    fn grid_medium_should_match_constant_density() {
+        // 20 voxels across 4 units, so the ray crosses several majorant cells.
+        let grid = VoxelGrid::dense([20, 20, 20], 0.25).set_bounds(Aabb::new(
+            Point3::new(-2.0, -2.0, -2.0),
+            Point3::new(2.0, 2.0, 2.0),
+        ));
+        let medium = GridMedium::new(
+            grid,
+            SolidColor::from(ColorRGBMapTo0_1::new(1.0, 1.0, 1.0)),
+            2.0,
+        );
+        let ray = Ray::new(Point3::new(-5.0, 0.3, 0.1), Vec3::new(2.0, 0.0, 0.0), 0.0);
+
+        const SAMPLES: usize = 100_000;
+        let mut sampler: Independent = Independent::new().set_seed(1);
+        let collisions: usize = (0..SAMPLES)
+            .filter(|_| {
+                medium
+                    .hit_sampled(&ray, 0.001, f32::INFINITY, &mut sampler)
+                    .is_some()
+            })
+            .count();
+
+        // Density 0.5 over 4 units of medium.
+        let expected: f32 = 1.0 - (-2.0_f32).exp();
+        assert!((collisions as f32 / SAMPLES as f32 - expected).abs() < 0.01);
+    }
+
+    #[test]
+    fn grid_medium_should_follow_a_transform_around_it() {
+        let grid = VoxelGrid::dense([20, 20, 20], 0.25).set_bounds(Aabb::new(
+            Point3::new(-1.0, -1.0, -1.0),
+            Point3::new(1.0, 1.0, 1.0),
+        ));
+        let medium: Box<dyn Hittable> = Box::new(GridMedium::new(
+            grid,
+            SolidColor::from(ColorRGBMapTo0_1::new(1.0, 1.0, 1.0)),
+            2.0,
+        ));
+        // Turned into a diamond in the xz plane, |x| + |z| <= √2.
+        let diamond = Instance::Transform {
+            prototype: medium,
+            transform: Transform::rotate_y(45_f32.to_radians())
+                .then(Transform::translate(Vec3::new(0.0, 3.0, 0.0))),
+        };
+
+        const SAMPLES: usize = 100_000;
+        let mut sampler: Independent = Independent::new().set_seed(1);
+        let mut collision_rate = |z: f32| -> f32 {
+            let ray = Ray::new(Point3::new(-5.0, 3.0, z), Vec3::new(1.0, 0.0, 0.0), 0.0);
+            (0..SAMPLES)
+                .filter(|_| {
+                    diamond
+                        .hit_sampled(&ray, 0.001, f32::INFINITY, &mut sampler)
+                        .is_some()
+                })
+                .count() as f32
+                / SAMPLES as f32
+        };
+
+        // Density 0.5 across the 2√2 units of the diagonal, rather than the 2
+        // of the grid's own box.
+        let expected: f32 = 1.0 - (-2_f32.sqrt()).exp();
+        assert!((collision_rate(0.0) - expected).abs() < 0.01);
+        // Outside the box of the untransformed grid, but inside the diamond.
+        assert!(0.0 < collision_rate(1.2));
+        // Inside the box around the diamond, but outside the diamond.
+        assert_eq!(0.0, collision_rate(1.5));
+    }
 }
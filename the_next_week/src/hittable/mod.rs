@@ -1,24 +1,46 @@
+mod aabb;
+mod bvh;
+mod csg;
 mod cuboid;
+mod curve;
+mod heightfield;
 mod hittable_list;
 mod instance;
 mod medium;
+mod planar;
+mod quadric;
 mod rect;
+mod sdf;
 mod sphere;
+mod torus;
 
 use std::sync::Arc;
 
 pub use self::{
+    aabb::Aabb,
+    bvh::Bvh,
+    csg::{Difference, Intersection, Union},
     cuboid::Cuboid,
+    curve::{Curve, CurveShape, Strand, Strands},
+    heightfield::Heightfield,
     hittable_list::HittableList,
     instance::Instance,
-    medium::ConstantMedium,
+    medium::{ChromaticMedium, ConstantMedium, GridMedium},
+    planar::{shape, AreaSample, Ellipse, Planar, PlanarShape, Quad, Triangle},
+    quadric::{Capsule, Cone, Cylinder},
     rect::{XYRect, XZRect, YZRect},
+    sdf::DistanceField,
     sphere::{MovingSphere, Sphere},
+    torus::Torus,
 };
 
 use in_one_weekend::{point::Point3, vec3::Vec3};
 
-use crate::{material::Material, ray::Ray};
+use crate::{material::Material, ray::Ray, sampler::Sampler};
+
+/// How far past an intersection, relative to its `t`, the default
+/// `Hittable::hit_all` looks for the next one.
+const HIT_ALL_EPSILON: f32 = 1e-4;
 
 pub struct HitRecord<'a> {
     p: Point3,
@@ -27,6 +49,14 @@ pub struct HitRecord<'a> {
     front_face: bool,
     material: &'a dyn Material,
     uv: [f32; 2],
+    /// The unit direction in which `u` increases along a curve, for materials
+    /// that shade fibres. `None` on other surfaces.
+    tangent: Option<Vec3>,
+    /// Per-channel factor the caller multiplies the light leaving this hit by.
+    /// Always `(1, 1, 1)` on surfaces out in the open; media with a different
+    /// extinction per channel use it to keep their estimator unbiased, on their
+    /// own hits and on those of the surfaces inside them.
+    weight: Vec3,
 }
 
 impl<'a> HitRecord<'a> {
@@ -50,6 +80,15 @@ impl<'a> HitRecord<'a> {
         self.front_face
     }
 
+    /// The normal pointing out of the object, whichever side the ray hit.
+    #[inline]
+    pub fn outward_normal(&self) -> Vec3 {
+        match self.front_face {
+            true => self.normal,
+            false => -self.normal,
+        }
+    }
+
     #[inline]
     pub fn u(&self) -> f32 {
         self.uv[0]
@@ -60,6 +99,16 @@ impl<'a> HitRecord<'a> {
         self.uv[1]
     }
 
+    #[inline]
+    pub fn tangent(&self) -> Option<Vec3> {
+        self.tangent
+    }
+
+    #[inline]
+    pub fn weight(&self) -> Vec3 {
+        self.weight
+    }
+
     pub fn set_face_normal(&mut self, ray: &Ray, outward_normal: Vec3) {
         self.front_face = ray.direction().dot(outward_normal) < 0.0;
         debug_assert!((0.98..1.02).contains(&outward_normal.len()));
@@ -71,11 +120,67 @@ impl<'a> HitRecord<'a> {
 }
 
 pub trait Hittable: Send + Sync {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord>;
+    /// The nearest intersection in `[t_min, t_max]`. Objects that draw random
+    /// numbers, such as participating media, take them from an `Independent`
+    /// sampler of a fixed seed here, so that a hit is repeatable; rendering
+    /// goes through `hit_sampled`.
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>>;
+
+    /// `hit`, drawing any random numbers it needs, such as where a ray
+    /// scatters inside a participating medium, from `sampler`.
+    ///
+    /// By default this is `hit`, which suits objects with no randomness.
+    fn hit_sampled(
+        &self,
+        ray: &Ray,
+        t_min: f32,
+        t_max: f32,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
+        self.hit(ray, t_min, t_max)
+    }
+
+    /// Every intersection in `[t_min, t_max]`, nearest first. Along a ray
+    /// through a closed object they alternate between entering, the front
+    /// face, and leaving.
+    ///
+    /// By default this repeats `hit` just past each intersection found.
+    fn hit_all(&self, ray: &Ray, t_min: f32, t_max: f32) -> Vec<HitRecord<'_>> {
+        let mut hit_records: Vec<HitRecord<'_>> = Vec::new();
+        let mut t_from: f32 = t_min;
+        while let Some(hit_record) = self.hit(ray, t_from, t_max) {
+            t_from = hit_record.t + HIT_ALL_EPSILON * hit_record.t.abs().max(1.0);
+            hit_records.push(hit_record);
+        }
+
+        hit_records
+    }
+
+    /// A box containing the object during the shutter interval
+    /// `[time_0, time_1]`, `None` if the object is unbounded.
+    fn bounding_box(&self, time_0: f32, time_1: f32) -> Option<Aabb>;
 }
 
 impl<T: Hittable> Hittable for Arc<T> {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
         self.as_ref().hit(ray, t_min, t_max)
     }
+
+    fn hit_sampled(
+        &self,
+        ray: &Ray,
+        t_min: f32,
+        t_max: f32,
+        sampler: &mut dyn Sampler,
+    ) -> Option<HitRecord<'_>> {
+        self.as_ref().hit_sampled(ray, t_min, t_max, sampler)
+    }
+
+    fn hit_all(&self, ray: &Ray, t_min: f32, t_max: f32) -> Vec<HitRecord<'_>> {
+        self.as_ref().hit_all(ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self, time_0: f32, time_1: f32) -> Option<Aabb> {
+        self.as_ref().bounding_box(time_0, time_1)
+    }
 }
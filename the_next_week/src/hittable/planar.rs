@@ -0,0 +1,376 @@
+use std::{f32::consts::PI, marker::PhantomData};
+
+use in_one_weekend::{point::Point3, vec3::Vec3};
+
+use crate::{material::Material, ray::Ray, sampler::Sampler, stats};
+
+use super::{Aabb, HitRecord, Hittable};
+
+/// Half the thickness of the bounding box of a planar primitive.
+const PAD: f32 = 0.0001;
+
+/// A surface that can be sampled by area, so that it can be aimed at as a light.
+pub trait AreaSample: Hittable {
+    fn area(&self) -> f32;
+
+    /// A point distributed uniformly over the surface, and the surface normal
+    /// there, drawn from `sampler`.
+    fn sample_point(&self, sampler: &mut dyn Sampler) -> (Point3, Vec3);
+
+    /// The solid angle density of `random_direction` for `direction` seen from
+    /// `origin`, zero if the direction misses.
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f32 {
+        stats::shadow_ray();
+        match self.hit(&Ray::new(origin, direction, 0.0), 0.001, f32::INFINITY) {
+            None => 0.0,
+            Some(hit_record) => {
+                let distance_squared: f32 = hit_record.t.powi(2) * direction.len_squared();
+                let cosine: f32 = (direction.dot(hit_record.normal) / direction.len()).abs();
+                let projected_area: f32 = cosine * self.area();
+
+                // Seen edge on, or too small for `f32`, the surface has no
+                // density to divide by.
+                match projected_area > 0.0 {
+                    true => distance_squared / projected_area,
+                    false => 0.0,
+                }
+            }
+        }
+    }
+
+    /// A direction from `origin` towards a uniformly chosen point of the surface.
+    fn random_direction(&self, origin: Point3, sampler: &mut dyn Sampler) -> Vec3 {
+        self.sample_point(sampler).0 - origin
+    }
+}
+
+/// Which part of the plane spanned by `q + α u + β v` a [`Planar`] covers.
+pub trait PlanarShape: Send + Sync {
+    /// The area relative to the parallelogram spanned by `u` and `v`.
+    const AREA_FACTOR: f32;
+
+    /// The texture coordinates of the plane coordinates `(α, β)`, `None` when
+    /// they are outside of the shape.
+    fn uv(alpha: f32, beta: f32) -> Option<[f32; 2]>;
+
+    /// Plane coordinates distributed uniformly over the shape, from two uniform
+    /// numbers in `[0, 1)`.
+    fn sample(xi: [f32; 2]) -> [f32; 2];
+
+    /// Plane coordinates of points whose convex hull contains the shape.
+    fn hull() -> &'static [[f32; 2]];
+}
+
+pub mod shape {
+    use super::*;
+
+    /// `α, β ∈ [0, 1]`, with `(α, β)` as UV.
+    pub struct Parallelogram;
+
+    /// `α, β ≥ 0, α + β ≤ 1`, with `(α, β)` as UV.
+    pub struct Triangle;
+
+    /// `α² + β² ≤ 1` around `q`, with `u` and `v` as the semi-axes. UV maps the
+    /// bounding square to `[0, 1]²`.
+    pub struct Ellipse;
+
+    impl PlanarShape for Parallelogram {
+        const AREA_FACTOR: f32 = 1.0;
+
+        fn uv(alpha: f32, beta: f32) -> Option<[f32; 2]> {
+            match (0.0..=1.0).contains(&alpha) && (0.0..=1.0).contains(&beta) {
+                true => Some([alpha, beta]),
+                false => None,
+            }
+        }
+
+        fn sample(xi: [f32; 2]) -> [f32; 2] {
+            xi
+        }
+
+        fn hull() -> &'static [[f32; 2]] {
+            &[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]]
+        }
+    }
+
+    impl PlanarShape for Triangle {
+        const AREA_FACTOR: f32 = 0.5;
+
+        fn uv(alpha: f32, beta: f32) -> Option<[f32; 2]> {
+            match 0.0 <= alpha && 0.0 <= beta && alpha + beta <= 1.0 {
+                true => Some([alpha, beta]),
+                false => None,
+            }
+        }
+
+        fn sample([xi_0, xi_1]: [f32; 2]) -> [f32; 2] {
+            let root: f32 = xi_0.sqrt();
+            [root * (1.0 - xi_1), root * xi_1]
+        }
+
+        fn hull() -> &'static [[f32; 2]] {
+            &[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]]
+        }
+    }
+
+    impl PlanarShape for Ellipse {
+        const AREA_FACTOR: f32 = PI;
+
+        fn uv(alpha: f32, beta: f32) -> Option<[f32; 2]> {
+            match alpha * alpha + beta * beta <= 1.0 {
+                true => Some([(alpha + 1.0) / 2.0, (beta + 1.0) / 2.0]),
+                false => None,
+            }
+        }
+
+        fn sample([xi_0, xi_1]: [f32; 2]) -> [f32; 2] {
+            let (radius, phi): (f32, f32) = (xi_0.sqrt(), 2.0 * PI * xi_1);
+            [radius * phi.cos(), radius * phi.sin()]
+        }
+
+        fn hull() -> &'static [[f32; 2]] {
+            &[[-1.0, -1.0], [1.0, -1.0], [-1.0, 1.0], [1.0, 1.0]]
+        }
+    }
+}
+
+/// A flat primitive in the plane through `q` spanned by `u` and `v`. The
+/// outward normal is along `u × v`.
+pub struct Planar<S: PlanarShape, M: AsRef<dyn Material>> {
+    q: Point3,
+    u: Vec3,
+    v: Vec3,
+    /// `n / (n · n)` for the unnormalised normal `n = u × v`, which turns a
+    /// point of the plane into its `(α, β)` coordinates.
+    w: Vec3,
+    normal: Vec3,
+    d: f32,
+    area: f32,
+    material: M,
+    shape: PhantomData<S>,
+}
+
+pub type Quad<M> = Planar<shape::Parallelogram, M>;
+pub type Triangle<M> = Planar<shape::Triangle, M>;
+pub type Ellipse<M> = Planar<shape::Ellipse, M>;
+
+impl<S: PlanarShape, M: AsRef<dyn Material>> Planar<S, M> {
+    fn from_basis(q: Point3, u: Vec3, v: Vec3, material: M) -> Self {
+        let n: Vec3 = u.cross(v);
+        debug_assert!(!n.is_near_zero(), "the edge vectors must not be parallel");
+        let normal: Vec3 = n.unit_vector();
+
+        Self {
+            q,
+            u,
+            v,
+            w: n / n.len_squared(),
+            normal,
+            d: normal.dot(q),
+            area: n.len() * S::AREA_FACTOR,
+            material,
+            shape: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub fn normal(&self) -> Vec3 {
+        self.normal
+    }
+
+    fn point(&self, [alpha, beta]: [f32; 2]) -> Point3 {
+        self.q + alpha * self.u + beta * self.v
+    }
+}
+
+impl<M: AsRef<dyn Material>> Quad<M> {
+    /// The parallelogram with corners `q`, `q + u`, `q + v` and `q + u + v`.
+    pub fn new(q: Point3, u: Vec3, v: Vec3, material: M) -> Self {
+        Self::from_basis(q, u, v, material)
+    }
+}
+
+impl<M: AsRef<dyn Material>> Triangle<M> {
+    /// The triangle with corners `q`, `q + u` and `q + v`.
+    pub fn new(q: Point3, u: Vec3, v: Vec3, material: M) -> Self {
+        Self::from_basis(q, u, v, material)
+    }
+
+    pub fn from_vertices(a: Point3, b: Point3, c: Point3, material: M) -> Self {
+        Self::from_basis(a, b - a, c - a, material)
+    }
+}
+
+impl<M: AsRef<dyn Material>> Ellipse<M> {
+    /// The ellipse around `center` with semi-axes `u` and `v`, which should be
+    /// perpendicular.
+    pub fn new(center: Point3, u: Vec3, v: Vec3, material: M) -> Self {
+        Self::from_basis(center, u, v, material)
+    }
+
+    /// A circular disk facing `normal`.
+    pub fn disk(center: Point3, normal: Vec3, radius: f32, material: M) -> Self {
+        let normal: Vec3 = normal.unit_vector();
+        let helper: Vec3 = match normal.x().abs() < 0.9 {
+            true => Vec3::new(1.0, 0.0, 0.0),
+            false => Vec3::new(0.0, 1.0, 0.0),
+        };
+        let u: Vec3 = helper.cross(normal).unit_vector();
+        let v: Vec3 = normal.cross(u);
+
+        Self::from_basis(center, radius * u, radius * v, material)
+    }
+}
+
+impl<S: PlanarShape, M: AsRef<dyn Material> + Send + Sync> Hittable for Planar<S, M> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        stats::primitive_test();
+        let denominator: f32 = self.normal.dot(ray.direction());
+        // The ray is parallel to the plane.
+        if denominator.abs() < 1e-8 {
+            return None;
+        }
+
+        let t: f32 = (self.d - self.normal.dot(ray.origin())) / denominator;
+        if !(t_min..=t_max).contains(&t) {
+            return None;
+        }
+
+        let p: Point3 = ray.at(t);
+        let planar: Vec3 = p - self.q;
+        let alpha: f32 = self.w.dot(planar.cross(self.v));
+        let beta: f32 = self.w.dot(self.u.cross(planar));
+        let uv: [f32; 2] = S::uv(alpha, beta)?;
+
+        let mut hit_record = HitRecord {
+            p,
+            normal: self.normal,
+            t,
+            front_face: true,
+            material: self.material.as_ref(),
+            uv,
+            tangent: None,
+            weight: Vec3::new(1.0, 1.0, 1.0),
+        };
+        hit_record.set_face_normal(ray, self.normal);
+
+        Some(hit_record)
+    }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        let bbox: Aabb = Aabb::from_points(S::hull().iter().map(|ab| self.point(*ab)))?;
+
+        // Pad every dimension so that the box has a non-zero width when the
+        // plane is axis-aligned.
+        let pad: Vec3 = Vec3::new(PAD, PAD, PAD);
+        Some(Aabb::new(bbox.min() - pad, bbox.max() + pad))
+    }
+}
+
+impl<S: PlanarShape, M: AsRef<dyn Material> + Send + Sync> AreaSample for Planar<S, M> {
+    fn area(&self) -> f32 {
+        self.area
+    }
+
+    fn sample_point(&self, sampler: &mut dyn Sampler) -> (Point3, Vec3) {
+        (self.point(S::sample(sampler.get_2d())), self.normal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use in_one_weekend::color::ColorRGBMapTo0_1;
+
+    use super::*;
+    use crate::{material::Lambertian, sampler::Independent, textures::SolidColor};
+
+    fn material() -> Arc<dyn Material> {
+        Arc::new(Lambertian::new(SolidColor::from(ColorRGBMapTo0_1::new(
+            0.5, 0.5, 0.5,
+        ))))
+    }
+
+    #[test]
+    fn planar_primitives_should_hit_inside_their_shape() {
+        // A tilted quad, in the plane x = z.
+        let quad = Quad::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(2.0, 0.0, 2.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            material(),
+        );
+        let ray = Ray::new(Point3::new(1.0, 0.25, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        let hit_record = quad.hit(&ray, 0.001, f32::INFINITY).unwrap();
+        assert!((hit_record.t - 6.0).abs() < 1e-5);
+        assert!((hit_record.u() - 0.5).abs() < 1e-5 && (hit_record.v() - 0.25).abs() < 1e-5);
+        assert!(!hit_record.is_front_face());
+        assert!((quad.area() - 8_f32.sqrt()).abs() < 1e-5);
+
+        let triangle = Triangle::from_vertices(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+            material(),
+        );
+        let hits = |x: f32, y: f32| {
+            let ray = Ray::new(Point3::new(x, y, 1.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+            triangle.hit(&ray, 0.001, f32::INFINITY).is_some()
+        };
+        assert!(hits(0.2, 0.2) && !hits(0.6, 0.6) && !hits(-0.1, 0.5));
+        assert!((triangle.area() - 0.5).abs() < 1e-6);
+
+        let disk = Ellipse::disk(
+            Point3::new(0.0, 3.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+            2.0,
+            material(),
+        );
+        let hits = |x: f32, z: f32| {
+            let ray = Ray::new(Point3::new(x, 0.0, z), Vec3::new(0.0, 1.0, 0.0), 0.0);
+            disk.hit(&ray, 0.001, f32::INFINITY)
+                .map(|hit| hit.is_front_face())
+        };
+        assert_eq!(Some(true), hits(1.4, 1.4));
+        assert_eq!(None, hits(1.5, 1.5));
+        assert!((disk.area() - 4.0 * PI).abs() < 1e-5);
+        let bbox: Aabb = disk.bounding_box(0.0, 1.0).unwrap();
+        assert!((bbox.max() - Point3::new(2.0, 3.0, 2.0)).len() < 1e-3);
+    }
+
+    #[test]
+    fn area_sampling_should_match_the_solid_angle_pdf() {
+        let light = Quad::new(
+            Point3::new(-1.0, 2.0, -1.0),
+            Vec3::new(0.0, 0.0, 2.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            material(),
+        );
+        let origin: Point3 = Point3::new(0.0, 0.0, 0.0);
+
+        // Straight below the centre the density is distance² / area.
+        assert!((light.pdf_value(origin, Vec3::new(0.0, 1.0, 0.0)) - 1.0).abs() < 1e-5);
+        assert_eq!(0.0, light.pdf_value(origin, Vec3::new(0.0, -1.0, 0.0)));
+
+        // Integrating 1 / pdf over sampled directions estimates the solid angle
+        // subtended by the light; compare with the analytic value of a
+        // rectangle seen from above its centre.
+        const N: usize = 20_000;
+        let mut sampler: Independent = Independent::new().set_seed(1);
+        let estimate: f32 = (0..N)
+            .map(|_| {
+                let direction: Vec3 = light.random_direction(origin, &mut sampler);
+                let (p, normal) = light.sample_point(&mut sampler);
+                assert!((p.y() - 2.0).abs() < 1e-5 && normal.y().abs() > 0.999);
+                1.0 / light.pdf_value(origin, direction)
+            })
+            .sum::<f32>()
+            / N as f32;
+        let expected: f32 = 4.0 * 0.2_f32.asin();
+        assert!(
+            (estimate - expected).abs() < 0.01 * expected,
+            "{estimate} != {expected}"
+        );
+    }
+}
@@ -0,0 +1,544 @@
+use std::f32::consts::PI;
+
+use in_one_weekend::{point::Point3, vec3::Vec3};
+
+use crate::{material::Material, ray::Ray, stats};
+
+use super::{Aabb, HitRecord, Hittable};
+
+/// A point a ray may hit: the ray parameter, the outward normal and the UV.
+pub(super) type Candidate = (f32, Vec3, [f32; 2]);
+
+/// The angle around the Y axis from X=-1, mapped to [0,1] as for `Sphere`.
+pub(super) fn azimuth(x: f32, z: f32) -> f32 {
+    (f32::atan2(-z, x) + PI) / (2.0 * PI)
+}
+
+/// The roots of `a t² + 2 half_b t + c`, the smaller first. A vanishing `a`
+/// leaves the single root of the linear equation, twice.
+pub(super) fn quadratic_roots(a: f32, half_b: f32, c: f32) -> Option<[f32; 2]> {
+    if a.abs() < 1e-12 {
+        return match half_b == 0.0 {
+            true => None,
+            false => Some([-c / (2.0 * half_b); 2]),
+        };
+    }
+
+    let discriminant: f32 = half_b * half_b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let dis_sqrt: f32 = discriminant.sqrt();
+    let [t0, t1]: [f32; 2] = [(-half_b - dis_sqrt) / a, (-half_b + dis_sqrt) / a];
+    Some([t0.min(t1), t0.max(t1)])
+}
+
+/// The hit record of the nearest candidate in `[t_min, t_max]`.
+pub(super) fn nearest_hit<'a, I: IntoIterator<Item = Option<Candidate>>>(
+    candidates: I,
+    ray: &Ray,
+    t_min: f32,
+    t_max: f32,
+    material: &'a dyn Material,
+) -> Option<HitRecord<'a>> {
+    let (t, outward_normal, uv): Candidate = candidates
+        .into_iter()
+        .flatten()
+        .filter(|(t, _, _)| (t_min..=t_max).contains(t))
+        .min_by(|a, b| a.0.total_cmp(&b.0))?;
+
+    let mut hit_record = HitRecord {
+        p: ray.at(t),
+        normal: outward_normal,
+        t,
+        front_face: true,
+        material,
+        uv,
+        tangent: None,
+        weight: Vec3::new(1.0, 1.0, 1.0),
+    };
+    hit_record.set_face_normal(ray, outward_normal);
+
+    Some(hit_record)
+}
+
+/// UV on a disk of the given radius around the Y axis, mapping its bounding
+/// square to [0,1]².
+fn disk_uv(x: f32, z: f32, radius: f32) -> [f32; 2] {
+    [(x / radius + 1.0) / 2.0, (z / radius + 1.0) / 2.0]
+}
+
+/// A cylinder around the Y axis from `base` up to `base + (0, height, 0)`.
+///
+/// u: angle around the Y axis from X=-1, as for `Sphere`.
+///
+/// v: height along the side, or the position on the cap as in `Ellipse`.
+pub struct Cylinder<M: AsRef<dyn Material>> {
+    base: Point3,
+    radius: f32,
+    height: f32,
+    capped: bool,
+    material: M,
+}
+
+impl<M: AsRef<dyn Material>> Cylinder<M> {
+    /// A closed cylinder, see `set_capped`.
+    pub fn new(base: Point3, radius: f32, height: f32, material: M) -> Self {
+        Self {
+            base,
+            radius,
+            height,
+            capped: true,
+            material,
+        }
+    }
+
+    /// Whether the flat ends are part of the surface; an uncapped cylinder is
+    /// an open tube.
+    pub fn set_capped(mut self, capped: bool) -> Self {
+        self.capped = capped;
+        self
+    }
+}
+
+impl<M: AsRef<dyn Material> + Send + Sync> Hittable for Cylinder<M> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        stats::primitive_test();
+        let (o, d): (Vec3, Vec3) = (ray.origin() - self.base, ray.direction());
+        let (radius, height): (f32, f32) = (self.radius, self.height);
+
+        let side: [Option<Candidate>; 2] = match quadratic_roots(
+            d.x() * d.x() + d.z() * d.z(),
+            o.x() * d.x() + o.z() * d.z(),
+            o.x() * o.x() + o.z() * o.z() - radius * radius,
+        ) {
+            None => [None, None],
+            Some(roots) => roots.map(|t| {
+                let p: Vec3 = o + t * d;
+                match (0.0..=height).contains(&p.y()) {
+                    true => Some((
+                        t,
+                        Vec3::new(p.x() / radius, 0.0, p.z() / radius),
+                        [azimuth(p.x(), p.z()), p.y() / height],
+                    )),
+                    false => None,
+                }
+            }),
+        };
+
+        let caps: [Option<Candidate>; 2] = [(0.0, -1.0), (height, 1.0)].map(|(y, normal_y)| {
+            if !self.capped || d.y() == 0.0 {
+                return None;
+            }
+            let t: f32 = (y - o.y()) / d.y();
+            let p: Vec3 = o + t * d;
+            match p.x() * p.x() + p.z() * p.z() <= radius * radius {
+                true => Some((
+                    t,
+                    Vec3::new(0.0, normal_y, 0.0),
+                    disk_uv(p.x(), p.z(), radius),
+                )),
+                false => None,
+            }
+        });
+
+        nearest_hit(
+            side.into_iter().chain(caps),
+            ray,
+            t_min,
+            t_max,
+            self.material.as_ref(),
+        )
+    }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        Some(Aabb::new(
+            self.base - Vec3::new(self.radius, 0.0, self.radius),
+            self.base + Vec3::new(self.radius, self.height, self.radius),
+        ))
+    }
+}
+
+/// A cone around the Y axis with its base disk at `base` and its apex at
+/// `base + (0, height, 0)`.
+///
+/// u: angle around the Y axis from X=-1, as for `Sphere`.
+///
+/// v: height along the side, or the position on the base as in `Ellipse`.
+pub struct Cone<M: AsRef<dyn Material>> {
+    base: Point3,
+    radius: f32,
+    height: f32,
+    capped: bool,
+    material: M,
+}
+
+impl<M: AsRef<dyn Material>> Cone<M> {
+    /// A cone closed by its base disk, see `set_capped`.
+    pub fn new(base: Point3, radius: f32, height: f32, material: M) -> Self {
+        Self {
+            base,
+            radius,
+            height,
+            capped: true,
+            material,
+        }
+    }
+
+    /// Whether the base disk is part of the surface.
+    pub fn set_capped(mut self, capped: bool) -> Self {
+        self.capped = capped;
+        self
+    }
+}
+
+impl<M: AsRef<dyn Material> + Send + Sync> Hittable for Cone<M> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        stats::primitive_test();
+        let (o, d): (Vec3, Vec3) = (ray.origin() - self.base, ray.direction());
+        let (radius, height): (f32, f32) = (self.radius, self.height);
+        // The surface is x² + z² = k² (height - y)².
+        let k_squared: f32 = (radius / height).powi(2);
+        let to_apex: f32 = height - o.y();
+
+        let side: [Option<Candidate>; 2] = match quadratic_roots(
+            d.x() * d.x() + d.z() * d.z() - k_squared * d.y() * d.y(),
+            o.x() * d.x() + o.z() * d.z() + k_squared * to_apex * d.y(),
+            o.x() * o.x() + o.z() * o.z() - k_squared * to_apex * to_apex,
+        ) {
+            None => [None, None],
+            Some(roots) => roots.map(|t| {
+                let p: Vec3 = o + t * d;
+                if !(0.0..=height).contains(&p.y()) {
+                    return None;
+                }
+                // The gradient of the implicit surface, which vanishes at the apex.
+                let gradient: Vec3 = Vec3::new(p.x(), k_squared * (height - p.y()), p.z());
+                let outward_normal: Vec3 = match gradient.is_near_zero() {
+                    true => Vec3::new(0.0, 1.0, 0.0),
+                    false => gradient.unit_vector(),
+                };
+                Some((t, outward_normal, [azimuth(p.x(), p.z()), p.y() / height]))
+            }),
+        };
+
+        let cap: Option<Candidate> = match self.capped && d.y() != 0.0 {
+            false => None,
+            true => {
+                let t: f32 = -o.y() / d.y();
+                let p: Vec3 = o + t * d;
+                match p.x() * p.x() + p.z() * p.z() <= radius * radius {
+                    true => Some((t, Vec3::new(0.0, -1.0, 0.0), disk_uv(p.x(), p.z(), radius))),
+                    false => None,
+                }
+            }
+        };
+
+        nearest_hit(
+            side.into_iter().chain([cap]),
+            ray,
+            t_min,
+            t_max,
+            self.material.as_ref(),
+        )
+    }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        Some(Aabb::new(
+            self.base - Vec3::new(self.radius, 0.0, self.radius),
+            self.base + Vec3::new(self.radius, self.height, self.radius),
+        ))
+    }
+}
+
+/// A cylinder around the Y axis from `base` up to `base + (0, height, 0)`,
+/// closed by a hemisphere at each end.
+///
+/// u: angle around the Y axis from X=-1, as for `Sphere`.
+///
+/// v: height from the bottom to the top of the whole capsule.
+pub struct Capsule<M: AsRef<dyn Material>> {
+    base: Point3,
+    radius: f32,
+    height: f32,
+    material: M,
+}
+
+impl<M: AsRef<dyn Material>> Capsule<M> {
+    pub fn new(base: Point3, radius: f32, height: f32, material: M) -> Self {
+        Self {
+            base,
+            radius,
+            height,
+            material,
+        }
+    }
+}
+
+impl<M: AsRef<dyn Material> + Send + Sync> Hittable for Capsule<M> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        stats::primitive_test();
+        let (o, d): (Vec3, Vec3) = (ray.origin() - self.base, ray.direction());
+        let (radius, height): (f32, f32) = (self.radius, self.height);
+        let v = |y: f32| (y + radius) / (height + 2.0 * radius);
+
+        let side: [Option<Candidate>; 2] = match quadratic_roots(
+            d.x() * d.x() + d.z() * d.z(),
+            o.x() * d.x() + o.z() * d.z(),
+            o.x() * o.x() + o.z() * o.z() - radius * radius,
+        ) {
+            None => [None, None],
+            Some(roots) => roots.map(|t| {
+                let p: Vec3 = o + t * d;
+                match (0.0..=height).contains(&p.y()) {
+                    true => Some((
+                        t,
+                        Vec3::new(p.x() / radius, 0.0, p.z() / radius),
+                        [azimuth(p.x(), p.z()), v(p.y())],
+                    )),
+                    false => None,
+                }
+            }),
+        };
+
+        // Each end sphere only contributes the hemisphere beyond the side.
+        let ends = [(0.0, -1.0), (height, 1.0)].map(|(center_y, side): (f32, f32)| {
+            let oc: Vec3 = o - Vec3::new(0.0, center_y, 0.0);
+            match quadratic_roots(
+                d.len_squared(),
+                oc.dot(d),
+                oc.len_squared() - radius * radius,
+            ) {
+                None => [None, None],
+                Some(roots) => roots.map(|t| {
+                    let p: Vec3 = o + t * d;
+                    match 0.0 <= side * (p.y() - center_y) {
+                        true => Some((t, (oc + t * d) / radius, [azimuth(p.x(), p.z()), v(p.y())])),
+                        false => None,
+                    }
+                }),
+            }
+        });
+
+        nearest_hit(
+            side.into_iter().chain(ends.into_iter().flatten()),
+            ray,
+            t_min,
+            t_max,
+            self.material.as_ref(),
+        )
+    }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        Some(Aabb::new(
+            self.base - Vec3::new(self.radius, self.radius, self.radius),
+            self.base + Vec3::new(self.radius, self.height + self.radius, self.radius),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use in_one_weekend::color::ColorRGBMapTo0_1;
+
+    use super::*;
+    use crate::{material::Lambertian, textures::SolidColor};
+
+    fn material() -> Arc<dyn Material> {
+        Arc::new(Lambertian::new(SolidColor::from(ColorRGBMapTo0_1::new(
+            0.5, 0.5, 0.5,
+        ))))
+    }
+
+    fn ray(origin: Point3, direction: Vec3) -> Ray {
+        Ray::new(origin, direction, 0.0)
+    }
+
+    #[test]
+    fn cylinder_should_handle_tangent_and_grazing_rays() {
+        let cylinder = Cylinder::new(Point3::new(0.0, 0.0, 0.0), 1.0, 2.0, material());
+        let along_z = Vec3::new(0.0, 0.0, 1.0);
+
+        let hit_record = cylinder
+            .hit(
+                &ray(Point3::new(0.0, 1.0, -5.0), along_z),
+                0.001,
+                f32::INFINITY,
+            )
+            .unwrap();
+        assert!((hit_record.t - 4.0).abs() < 1e-5);
+        assert!((hit_record.normal() - Vec3::new(0.0, 0.0, -1.0)).len() < 1e-5);
+        assert!((hit_record.u() - 0.75).abs() < 1e-5 && (hit_record.v() - 0.5).abs() < 1e-5);
+
+        // Tangent to the side: the double root touches at x = 1.
+        let hit_record = cylinder
+            .hit(
+                &ray(Point3::new(1.0, 1.0, -5.0), along_z),
+                0.001,
+                f32::INFINITY,
+            )
+            .unwrap();
+        assert!((hit_record.position() - Point3::new(1.0, 1.0, 0.0)).len() < 1e-5);
+        assert!(cylinder
+            .hit(
+                &ray(Point3::new(1.001, 1.0, -5.0), along_z),
+                0.001,
+                f32::INFINITY
+            )
+            .is_none());
+
+        // Grazing the top cap in its own plane only hits the rim of the side.
+        let hit_record = cylinder
+            .hit(
+                &ray(Point3::new(0.0, 2.0, -5.0), along_z),
+                0.001,
+                f32::INFINITY,
+            )
+            .unwrap();
+        assert!((hit_record.t - 4.0).abs() < 1e-5);
+
+        // Along the axis only the caps are hit, and an open tube lets it through.
+        let up = ray(Point3::new(0.2, -3.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let hit_record = cylinder.hit(&up, 0.001, f32::INFINITY).unwrap();
+        assert!((hit_record.t - 3.0).abs() < 1e-5 && hit_record.is_front_face());
+        assert!((hit_record.normal() - Vec3::new(0.0, -1.0, 0.0)).len() < 1e-5);
+        let tube =
+            Cylinder::new(Point3::new(0.0, 0.0, 0.0), 1.0, 2.0, material()).set_capped(false);
+        assert!(tube.hit(&up, 0.001, f32::INFINITY).is_none());
+
+        // From inside the tube the far wall is hit from behind.
+        let hit_record = tube
+            .hit(
+                &ray(Point3::new(0.0, 1.0, 0.0), along_z),
+                0.001,
+                f32::INFINITY,
+            )
+            .unwrap();
+        assert!(!hit_record.is_front_face());
+        assert!((hit_record.normal() - Vec3::new(0.0, 0.0, -1.0)).len() < 1e-5);
+    }
+
+    #[test]
+    fn cone_should_handle_tangent_and_grazing_rays() {
+        let cone = Cone::new(Point3::new(0.0, 0.0, 0.0), 1.0, 1.0, material());
+        let along_z = Vec3::new(0.0, 0.0, 1.0);
+
+        let hit_record = cone
+            .hit(
+                &ray(Point3::new(0.0, 0.5, -5.0), along_z),
+                0.001,
+                f32::INFINITY,
+            )
+            .unwrap();
+        assert!((hit_record.t - 4.5).abs() < 1e-5);
+        let expected: Vec3 = Vec3::new(0.0, 0.5, -0.5).unit_vector();
+        assert!((hit_record.normal() - expected).len() < 1e-5);
+
+        // Tangent where the cone is 0.5 wide.
+        let hit_record = cone
+            .hit(
+                &ray(Point3::new(0.5, 0.5, -5.0), along_z),
+                0.001,
+                f32::INFINITY,
+            )
+            .unwrap();
+        assert!((hit_record.position() - Point3::new(0.5, 0.5, 0.0)).len() < 1e-3);
+        assert!(cone
+            .hit(
+                &ray(Point3::new(0.501, 0.5, -5.0), along_z),
+                0.001,
+                f32::INFINITY
+            )
+            .is_none());
+
+        // Parallel to a line of the surface, so the quadratic degenerates.
+        let hit_record = cone
+            .hit(
+                &ray(Point3::new(0.5, 0.0, 0.0), Vec3::new(-1.0, 1.0, 0.0)),
+                0.001,
+                f32::INFINITY,
+            )
+            .unwrap();
+        assert!((hit_record.t - 0.75).abs() < 1e-5);
+        assert!(!hit_record.is_front_face());
+
+        // The tip and the base.
+        assert!(cone
+            .hit(
+                &ray(Point3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0)),
+                0.001,
+                f32::INFINITY
+            )
+            .is_some_and(|hit_record| (hit_record.t - 4.0).abs() < 1e-5));
+        let hit_record = cone
+            .hit(
+                &ray(Point3::new(0.0, -1.0, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+                0.001,
+                f32::INFINITY,
+            )
+            .unwrap();
+        assert!((hit_record.t - 1.0).abs() < 1e-5 && hit_record.is_front_face());
+    }
+
+    #[test]
+    fn capsule_should_handle_tangent_and_grazing_rays() {
+        let capsule = Capsule::new(Point3::new(0.0, 0.0, 0.0), 1.0, 2.0, material());
+        let along_x = Vec3::new(1.0, 0.0, 0.0);
+
+        let hit_record = capsule
+            .hit(
+                &ray(Point3::new(0.0, -5.0, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+                0.001,
+                f32::INFINITY,
+            )
+            .unwrap();
+        assert!((hit_record.t - 4.0).abs() < 1e-5);
+        assert!((hit_record.normal() - Vec3::new(0.0, -1.0, 0.0)).len() < 1e-5);
+        assert!(hit_record.v().abs() < 1e-5);
+
+        let hit_record = capsule
+            .hit(
+                &ray(Point3::new(-5.0, 2.5, 0.0), along_x),
+                0.001,
+                f32::INFINITY,
+            )
+            .unwrap();
+        let x: f32 = -(0.75_f32.sqrt());
+        assert!((hit_record.position() - Point3::new(x, 2.5, 0.0)).len() < 1e-5);
+        assert!((hit_record.normal() - Vec3::new(x, 0.5, 0.0)).len() < 1e-5);
+
+        // Where the side meets a hemisphere.
+        let hit_record = capsule
+            .hit(
+                &ray(Point3::new(-5.0, 2.0, 0.0), along_x),
+                0.001,
+                f32::INFINITY,
+            )
+            .unwrap();
+        assert!((hit_record.t - 4.0).abs() < 1e-5);
+
+        // Tangent to the top.
+        let hit_record = capsule
+            .hit(
+                &ray(Point3::new(-5.0, 3.0, 0.0), along_x),
+                0.001,
+                f32::INFINITY,
+            )
+            .unwrap();
+        assert!((hit_record.position() - Point3::new(0.0, 3.0, 0.0)).len() < 1e-3);
+        assert!(capsule
+            .hit(
+                &ray(Point3::new(-5.0, 3.001, 0.0), along_x),
+                0.001,
+                f32::INFINITY
+            )
+            .is_none());
+
+        let bbox: Aabb = capsule.bounding_box(0.0, 1.0).unwrap();
+        assert_eq!(Point3::new(-1.0, -1.0, -1.0), bbox.min());
+        assert_eq!(Point3::new(1.0, 3.0, 1.0), bbox.max());
+    }
+}
@@ -2,9 +2,12 @@ use in_one_weekend::{point::Point3, vec3::Vec3};
 
 use std::ops::RangeInclusive;
 
-use crate::{material::Material, ray::Ray};
+use crate::{material::Material, ray::Ray, stats};
 
-use super::{HitRecord, Hittable};
+use super::{Aabb, HitRecord, Hittable};
+
+/// Half the thickness of the bounding box of a rectangle.
+const PAD: f32 = 0.0001;
 
 pub struct XYRect<M: AsRef<dyn Material>> {
     x_range: RangeInclusive<f32>,
@@ -30,7 +33,8 @@ impl<M: AsRef<dyn Material>> XYRect<M> {
 }
 
 impl<M: AsRef<dyn Material> + Send + Sync> Hittable for XYRect<M> {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        stats::primitive_test();
         let t = (self.k - ray.origin().z()) / ray.direction().z();
         if !(t_min..=t_max).contains(&t) {
             return None;
@@ -54,11 +58,21 @@ impl<M: AsRef<dyn Material> + Send + Sync> Hittable for XYRect<M> {
             front_face: true,
             material: self.material.as_ref(),
             uv,
+            tangent: None,
+            weight: Vec3::new(1.0, 1.0, 1.0),
         };
         hit_record.set_face_normal(ray, outward_normal);
 
         Some(hit_record)
     }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        // Pad the flat dimension so that the box has a non-zero width.
+        Some(Aabb::new(
+            Point3::new(*self.x_range.start(), *self.y_range.start(), self.k - PAD),
+            Point3::new(*self.x_range.end(), *self.y_range.end(), self.k + PAD),
+        ))
+    }
 }
 
 pub struct XZRect<M: AsRef<dyn Material>> {
@@ -85,7 +99,8 @@ impl<M: AsRef<dyn Material>> XZRect<M> {
 }
 
 impl<M: AsRef<dyn Material> + Send + Sync> Hittable for XZRect<M> {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        stats::primitive_test();
         let t = (self.k - ray.origin().y()) / ray.direction().y();
         if !(t_min..=t_max).contains(&t) {
             return None;
@@ -109,11 +124,21 @@ impl<M: AsRef<dyn Material> + Send + Sync> Hittable for XZRect<M> {
             front_face: true,
             material: self.material.as_ref(),
             uv,
+            tangent: None,
+            weight: Vec3::new(1.0, 1.0, 1.0),
         };
         hit_record.set_face_normal(ray, outward_normal);
 
         Some(hit_record)
     }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        // Pad the flat dimension so that the box has a non-zero width.
+        Some(Aabb::new(
+            Point3::new(*self.x_range.start(), self.k - PAD, *self.z_range.start()),
+            Point3::new(*self.x_range.end(), self.k + PAD, *self.z_range.end()),
+        ))
+    }
 }
 
 pub struct YZRect<M: AsRef<dyn Material>> {
@@ -140,7 +165,8 @@ impl<M: AsRef<dyn Material>> YZRect<M> {
 }
 
 impl<M: AsRef<dyn Material> + Send + Sync> Hittable for YZRect<M> {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        stats::primitive_test();
         let t = (self.k - ray.origin().x()) / ray.direction().x();
         if !(t_min..=t_max).contains(&t) {
             return None;
@@ -164,9 +190,19 @@ impl<M: AsRef<dyn Material> + Send + Sync> Hittable for YZRect<M> {
             front_face: true,
             material: self.material.as_ref(),
             uv,
+            tangent: None,
+            weight: Vec3::new(1.0, 1.0, 1.0),
         };
         hit_record.set_face_normal(ray, outward_normal);
 
         Some(hit_record)
     }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        // Pad the flat dimension so that the box has a non-zero width.
+        Some(Aabb::new(
+            Point3::new(self.k - PAD, *self.y_range.start(), *self.z_range.start()),
+            Point3::new(self.k + PAD, *self.y_range.end(), *self.z_range.end()),
+        ))
+    }
 }
@@ -0,0 +1,213 @@
+use std::f32::consts::PI;
+
+use in_one_weekend::{point::Point3, vec3::Vec3};
+
+use crate::{material::Material, ray::Ray, sdf::Sdf, stats};
+
+use super::{quadric::azimuth, Aabb, HitRecord, Hittable};
+
+/// A surface given by the zero set of a signed distance field, found by sphere
+/// tracing inside `bbox`.
+///
+/// `bbox` must contain the whole surface; the field is never evaluated outside
+/// of it.
+///
+/// u, v: the direction of the normal, mapped as on `Sphere`.
+pub struct DistanceField<S: Sdf, M: AsRef<dyn Material>> {
+    sdf: S,
+    bbox: Aabb,
+    material: M,
+    step_scale: f32,
+    epsilon: f32,
+    max_steps: u32,
+}
+
+impl<S: Sdf, M: AsRef<dyn Material>> DistanceField<S, M> {
+    pub fn new(sdf: S, bbox: Aabb, material: M) -> Self {
+        Self {
+            sdf,
+            bbox,
+            material,
+            step_scale: 1.0,
+            epsilon: 1e-4,
+            max_steps: 256,
+        }
+    }
+
+    /// Fraction of the distance estimate stepped at a time, below one for
+    /// fields that overestimate the distance.
+    pub fn set_step_scale(mut self, step_scale: f32) -> Self {
+        debug_assert!(0.0 < step_scale && step_scale <= 1.0);
+        self.step_scale = step_scale;
+        self
+    }
+
+    /// Distance below which a point counts as on the surface.
+    pub fn set_epsilon(mut self, epsilon: f32) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    pub fn set_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// The normalised gradient of the field, from the tetrahedron of samples
+    /// around `p`.
+    fn normal(&self, p: Point3) -> Vec3 {
+        let h: f32 = self.epsilon;
+        let gradient: Vec3 = [
+            Vec3::new(1.0, -1.0, -1.0),
+            Vec3::new(-1.0, -1.0, 1.0),
+            Vec3::new(-1.0, 1.0, -1.0),
+            Vec3::new(1.0, 1.0, 1.0),
+        ]
+        .into_iter()
+        .map(|k| self.sdf.distance(p + h * k) * k)
+        .fold(Vec3::new(0.0, 0.0, 0.0), |sum, v| sum + v);
+
+        match gradient.is_near_zero() {
+            true => Vec3::new(0.0, 1.0, 0.0),
+            false => gradient.unit_vector(),
+        }
+    }
+}
+
+impl<S: Sdf, M: AsRef<dyn Material> + Send + Sync> Hittable for DistanceField<S, M> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        stats::primitive_test();
+        let (t_enter, t_exit): (f32, f32) = self.bbox.hit(ray, t_min, t_max)?;
+        let speed: f32 = ray.direction().len();
+
+        // Inside the surface the trace looks for the way out instead.
+        let mut t: f32 = t_enter;
+        let sign: f32 = self.sdf.distance(ray.at(t)).signum();
+        for _ in 0..self.max_steps {
+            let distance: f32 = sign * self.sdf.distance(ray.at(t));
+            if distance < self.epsilon {
+                break;
+            }
+            t += self.step_scale * distance / speed;
+            if t_exit < t {
+                return None;
+            }
+        }
+        // Out of steps without converging, e.g. grazing the surface.
+        if self.epsilon <= self.sdf.distance(ray.at(t)).abs() {
+            return None;
+        }
+
+        let p: Point3 = ray.at(t);
+        let outward_normal: Vec3 = self.normal(p);
+        let mut hit_record = HitRecord {
+            p,
+            normal: outward_normal,
+            t,
+            front_face: true,
+            material: self.material.as_ref(),
+            uv: [
+                azimuth(outward_normal.x(), outward_normal.z()),
+                (-outward_normal.y()).acos() / PI,
+            ],
+            tangent: None,
+            weight: Vec3::new(1.0, 1.0, 1.0),
+        };
+        hit_record.set_face_normal(ray, outward_normal);
+
+        Some(hit_record)
+    }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        Some(self.bbox)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use in_one_weekend::color::ColorRGBMapTo0_1;
+
+    use super::*;
+    use crate::{
+        hittable::{HittableList, Sphere},
+        material::Lambertian,
+        sdf::{self, Mandelbulb, RoundBox, SmoothUnion, Translate},
+        textures::SolidColor,
+    };
+
+    fn material() -> Arc<dyn Material> {
+        Arc::new(Lambertian::new(SolidColor::from(ColorRGBMapTo0_1::new(
+            0.5, 0.5, 0.5,
+        ))))
+    }
+
+    fn unit_box() -> Aabb {
+        Aabb::new(Point3::new(-1.5, -1.5, -1.5), Point3::new(1.5, 1.5, 1.5))
+    }
+
+    #[test]
+    fn distance_field_should_match_analytic_shapes() {
+        let traced = DistanceField::new(sdf::Sphere::new(1.0), unit_box(), material());
+        let analytic = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, material());
+
+        [
+            Ray::new(Point3::new(0.3, 0.2, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.0),
+            Ray::new(Point3::new(4.0, 3.0, 2.0), Vec3::new(-2.0, -1.6, -1.0), 0.0),
+            // From inside the surface.
+            Ray::new(Point3::new(0.1, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), 0.0),
+        ]
+        .iter()
+        .for_each(|ray| {
+            let expected = analytic.hit(ray, 0.001, f32::INFINITY).unwrap();
+            let actual = traced.hit(ray, 0.001, f32::INFINITY).unwrap();
+            assert!((expected.t - actual.t).abs() < 1e-3);
+            assert!((expected.normal() - actual.normal()).len() < 1e-2);
+            assert_eq!(expected.is_front_face(), actual.is_front_face());
+        });
+
+        let miss = Ray::new(Point3::new(1.01, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0), 0.0);
+        assert!(traced.hit(&miss, 0.001, f32::INFINITY).is_none());
+    }
+
+    #[test]
+    fn distance_field_should_sit_next_to_analytic_objects() {
+        let mut world: HittableList<Box<dyn Hittable>> = HittableList::default();
+        world.add(Box::new(DistanceField::new(
+            SmoothUnion::new(
+                RoundBox::new(Vec3::new(1.0, 1.0, 1.0), 0.2),
+                Translate::new(sdf::Sphere::new(0.5), Vec3::new(0.0, 1.2, 0.0)),
+                0.3,
+            ),
+            Aabb::new(Point3::new(-1.5, -1.5, -1.5), Point3::new(1.5, 2.5, 1.5)),
+            material(),
+        )));
+        world.add(Box::new(
+            DistanceField::new(
+                Translate::new(Mandelbulb::default(), Vec3::new(0.0, 0.0, -5.0)),
+                Aabb::new(Point3::new(-1.5, -1.5, -6.5), Point3::new(1.5, 1.5, -3.5)),
+                material(),
+            )
+            .set_epsilon(1e-3),
+        ));
+        world.add(Box::new(Sphere::new(
+            Point3::new(0.0, 0.0, 10.0),
+            1.0,
+            material(),
+        )));
+
+        let down = Ray::new(Point3::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), 0.0);
+        let hit_record = world.hit(&down, 0.001, f32::INFINITY).unwrap();
+        assert!((hit_record.position().y() - 1.7).abs() < 1e-3);
+
+        let side = Ray::new(Point3::new(0.0, 0.0, 20.0), Vec3::new(0.0, 0.0, -1.0), 0.0);
+        let hit_record = world.hit(&side, 0.001, f32::INFINITY).unwrap();
+        assert!((hit_record.t - 9.0).abs() < 1e-4);
+
+        // The Mandelbulb spans about 1.1 along the X axis.
+        let sideways = Ray::new(Point3::new(-5.0, 0.0, -5.0), Vec3::new(1.0, 0.0, 0.0), 0.0);
+        let hit_record = world.hit(&sideways, 0.001, f32::INFINITY).unwrap();
+        assert!((-1.3..-0.9).contains(&hit_record.position().x()));
+    }
+}
@@ -1,8 +1,8 @@
 use in_one_weekend::{point::Point3, vec3::Vec3};
 
-use crate::{material::Material, ray::Ray};
+use crate::{material::Material, ray::Ray, stats};
 
-use super::{HitRecord, Hittable};
+use super::{Aabb, HitRecord, Hittable};
 
 pub struct Sphere<M: AsRef<dyn Material>> {
     center: Point3,
@@ -44,7 +44,8 @@ impl<M: AsRef<dyn Material>> Sphere<M> {
 }
 
 impl<M: AsRef<dyn Material> + Send + Sync> Hittable for Sphere<M> {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        stats::primitive_test();
         let oc: Vec3 = ray.origin() - self.center;
         let a: f32 = ray.direction().len_squared();
         let half_b: f32 = ray.direction().dot(oc);
@@ -77,11 +78,19 @@ impl<M: AsRef<dyn Material> + Send + Sync> Hittable for Sphere<M> {
             front_face: true,
             material: self.material.as_ref(),
             uv: Self::uv(&outward_normal),
+            tangent: None,
+            weight: Vec3::new(1.0, 1.0, 1.0),
         };
         hit_record.set_face_normal(ray, outward_normal);
 
         Some(hit_record)
     }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        let radius = Vec3::new(self.radius, self.radius, self.radius);
+
+        Some(Aabb::new(self.center - radius, self.center + radius))
+    }
 }
 
 pub struct MovingSphere<M: AsRef<dyn Material>> {
@@ -146,7 +155,8 @@ impl<M: AsRef<dyn Material>> MovingSphere<M> {
 }
 
 impl<M: AsRef<dyn Material> + Send + Sync> Hittable for MovingSphere<M> {
-    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        stats::primitive_test();
         let oc: Vec3 = ray.origin() - self.center(ray.time());
         let a: f32 = ray.direction().len_squared();
         let half_b: f32 = ray.direction().dot(oc);
@@ -179,9 +189,22 @@ impl<M: AsRef<dyn Material> + Send + Sync> Hittable for MovingSphere<M> {
             front_face: true,
             material: self.material.as_ref(),
             uv: Self::uv(&outward_normal),
+            tangent: None,
+            weight: Vec3::new(1.0, 1.0, 1.0),
         };
         hit_record.set_face_normal(ray, outward_normal);
 
         Some(hit_record)
     }
+
+    fn bounding_box(&self, time_0: f32, time_1: f32) -> Option<Aabb> {
+        let radius = Vec3::new(self.radius, self.radius, self.radius);
+        let [center_0, center_1]: [Point3; 2] =
+            [time_0, time_1].map(|time| self.center(time.clamp(self.time_0, self.time_1)));
+
+        Some(
+            Aabb::new(center_0 - radius, center_0 + radius)
+                .surrounding(&Aabb::new(center_1 - radius, center_1 + radius)),
+        )
+    }
 }
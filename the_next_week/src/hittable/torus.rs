@@ -0,0 +1,250 @@
+use std::f32::consts::PI;
+
+use in_one_weekend::{point::Point3, vec3::Vec3};
+
+use crate::{material::Material, ray::Ray, stats};
+
+use super::{
+    quadric::{azimuth, nearest_hit, Candidate},
+    Aabb, HitRecord, Hittable,
+};
+
+/// A torus around the Y axis: a tube of `minor_radius` swept along the circle
+/// of `major_radius` in the XZ plane through `center`.
+///
+/// u: angle around the Y axis from X=-1, as for `Sphere`.
+///
+/// v: angle around the tube, from its inner equator through its top.
+pub struct Torus<M: AsRef<dyn Material>> {
+    center: Point3,
+    major_radius: f32,
+    minor_radius: f32,
+    material: M,
+}
+
+impl<M: AsRef<dyn Material>> Torus<M> {
+    pub fn new(center: Point3, major_radius: f32, minor_radius: f32, material: M) -> Self {
+        Self {
+            center,
+            major_radius,
+            minor_radius,
+            material,
+        }
+    }
+}
+
+impl<M: AsRef<dyn Material> + Send + Sync> Hittable for Torus<M> {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord<'_>> {
+        stats::primitive_test();
+        let (major, minor): (f64, f64) = (self.major_radius as f64, self.minor_radius as f64);
+        let length: f64 = ray.direction().len() as f64;
+        let d: [f64; 3] = [0, 1, 2].map(|axis| ray.direction()[axis] as f64 / length);
+        let mut o: [f64; 3] = [0, 1, 2].map(|axis| (ray.origin() - self.center)[axis] as f64);
+
+        // The quartic is badly conditioned far away from the torus, so move the
+        // origin along the ray to just in front of the bounding sphere first.
+        let closest: f64 = -(o[0] * d[0] + o[1] * d[1] + o[2] * d[2]);
+        let shift: f64 = (closest - (major + minor)).max(0.0);
+        (0..3).for_each(|axis| o[axis] += shift * d[axis]);
+
+        // (|p|² + R² - r²)² = 4 R² (x² + z²) along p = o + s d, with |d| = 1.
+        let b: f64 = 2.0 * (o[0] * d[0] + o[1] * d[1] + o[2] * d[2]);
+        let k: f64 = o[0] * o[0] + o[1] * o[1] + o[2] * o[2] + major * major - minor * minor;
+        let four_major_squared: f64 = 4.0 * major * major;
+        let coefficients: [f64; 5] = [
+            1.0,
+            2.0 * b,
+            b * b + 2.0 * k - four_major_squared * (d[0] * d[0] + d[2] * d[2]),
+            2.0 * b * k - 2.0 * four_major_squared * (o[0] * d[0] + o[2] * d[2]),
+            k * k - four_major_squared * (o[0] * o[0] + o[2] * o[2]),
+        ];
+
+        let candidates = solve_quartic(coefficients).into_iter().map(|s| {
+            let t: f32 = ((shift + s) / length) as f32;
+            let p: Vec3 = ray.at(t) - self.center;
+            // The nearest point on the centre circle of the tube.
+            let radial: Vec3 = Vec3::new(p.x(), 0.0, p.z());
+            let ring: Vec3 = match radial.is_near_zero() {
+                true => Vec3::new(self.major_radius, 0.0, 0.0),
+                false => self.major_radius * radial.unit_vector(),
+            };
+            let outward_normal: Vec3 = (p - ring).unit_vector();
+            let around_tube: f32 =
+                f32::atan2(p.y(), self.major_radius - radial.len()).rem_euclid(2.0 * PI);
+
+            Some::<Candidate>((
+                t,
+                outward_normal,
+                [azimuth(p.x(), p.z()), around_tube / (2.0 * PI)],
+            ))
+        });
+
+        nearest_hit(candidates, ray, t_min, t_max, self.material.as_ref())
+    }
+
+    fn bounding_box(&self, _time_0: f32, _time_1: f32) -> Option<Aabb> {
+        let outer: f32 = self.major_radius + self.minor_radius;
+        let extent: Vec3 = Vec3::new(outer, self.minor_radius, outer);
+
+        Some(Aabb::new(self.center - extent, self.center + extent))
+    }
+}
+
+/// The real roots of `c[0] x³ + c[1] x² + c[2] x + c[3]`.
+fn solve_cubic(c: [f64; 4]) -> Vec<f64> {
+    let [a, b, d]: [f64; 3] = [c[1] / c[0], c[2] / c[0], c[3] / c[0]];
+
+    // Depressed cubic t³ + p t + q with x = t - a / 3.
+    let p: f64 = b - a * a / 3.0;
+    let q: f64 = 2.0 * a * a * a / 27.0 - a * b / 3.0 + d;
+    let offset: f64 = -a / 3.0;
+    let discriminant: f64 = q * q / 4.0 + p * p * p / 27.0;
+
+    match discriminant {
+        disc if 0.0 < disc => {
+            let root: f64 = disc.sqrt();
+            vec![(-q / 2.0 + root).cbrt() + (-q / 2.0 - root).cbrt() + offset]
+        }
+        _ if p == 0.0 => vec![offset],
+        _ => {
+            // Three real roots, trigonometrically.
+            let m: f64 = 2.0 * (-p / 3.0).sqrt();
+            let angle: f64 = (3.0 * q / (p * m)).clamp(-1.0, 1.0).acos() / 3.0;
+            (0..3)
+                .map(|k| m * (angle - 2.0 * std::f64::consts::PI * k as f64 / 3.0).cos() + offset)
+                .collect()
+        }
+    }
+}
+
+/// The real roots of `c[0] x⁴ + c[1] x³ + c[2] x² + c[3] x + c[4]` with
+/// Ferrari's method, polished with Newton's method.
+fn solve_quartic(c: [f64; 5]) -> Vec<f64> {
+    let [a, b, d, e]: [f64; 4] = [c[1] / c[0], c[2] / c[0], c[3] / c[0], c[4] / c[0]];
+
+    // Depressed quartic y⁴ + p y² + q y + r with x = y - a / 4.
+    let p: f64 = b - 3.0 * a * a / 8.0;
+    let q: f64 = d - a * b / 2.0 + a * a * a / 8.0;
+    let r: f64 = e - a * d / 4.0 + a * a * b / 16.0 - 3.0 * a * a * a * a / 256.0;
+
+    let mut roots: Vec<f64> = Vec::with_capacity(4);
+    let mut push_quadratic = |linear: f64, constant: f64| {
+        let discriminant: f64 = linear * linear / 4.0 - constant;
+        if 0.0 <= discriminant {
+            let root: f64 = discriminant.sqrt();
+            roots.extend([-linear / 2.0 - root, -linear / 2.0 + root]);
+        }
+    };
+
+    if q.abs() < 1e-12 {
+        // Biquadratic, a quadratic in y².
+        let discriminant: f64 = p * p / 4.0 - r;
+        if discriminant < 0.0 {
+            return Vec::new();
+        }
+        [
+            -p / 2.0 - discriminant.sqrt(),
+            -p / 2.0 + discriminant.sqrt(),
+        ]
+        .into_iter()
+        .filter(|z| 0.0 <= *z)
+        .for_each(|z| push_quadratic(0.0, -z));
+    } else {
+        // Split into two quadratics with a positive root m of the resolvent
+        // cubic m³ + p m² + (p²/4 - r) m - q²/8, which exists since q ≠ 0.
+        let m: f64 = solve_cubic([1.0, p, p * p / 4.0 - r, -q * q / 8.0])
+            .into_iter()
+            .fold(f64::NEG_INFINITY, f64::max);
+        if m <= 0.0 {
+            return Vec::new();
+        }
+        let s: f64 = (2.0 * m).sqrt();
+        push_quadratic(s, p / 2.0 + m - q / (2.0 * s));
+        push_quadratic(-s, p / 2.0 + m + q / (2.0 * s));
+    }
+
+    let polynomial = |x: f64| (((c[0] * x + c[1]) * x + c[2]) * x + c[3]) * x + c[4];
+    let derivative = |x: f64| ((4.0 * c[0] * x + 3.0 * c[1]) * x + 2.0 * c[2]) * x + c[3];
+    roots
+        .into_iter()
+        .map(|y| {
+            let mut x: f64 = y - a / 4.0;
+            for _ in 0..2 {
+                let slope: f64 = derivative(x);
+                if slope != 0.0 {
+                    x -= polynomial(x) / slope;
+                }
+            }
+            x
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use in_one_weekend::color::ColorRGBMapTo0_1;
+
+    use super::*;
+    use crate::{material::Lambertian, textures::SolidColor};
+
+    #[test]
+    fn quartic_solver_should_find_real_roots() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        let mut roots: Vec<f64> = solve_quartic([1.0, -10.0, 35.0, -50.0, 24.0]);
+        roots.sort_by(f64::total_cmp);
+        assert_eq!(4, roots.len());
+        roots
+            .iter()
+            .zip([1.0, 2.0, 3.0, 4.0])
+            .for_each(|(root, expected)| assert!((root - expected).abs() < 1e-9));
+
+        // (x² + 1)(x - 2)(x + 3) has two real roots.
+        let mut roots: Vec<f64> = solve_quartic([1.0, 1.0, -5.0, 1.0, -6.0]);
+        roots.sort_by(f64::total_cmp);
+        assert_eq!(2, roots.len());
+        assert!((roots[0] + 3.0).abs() < 1e-9 && (roots[1] - 2.0).abs() < 1e-9);
+
+        // x⁴ + 1 has none.
+        assert!(solve_quartic([1.0, 0.0, 0.0, 0.0, 1.0]).is_empty());
+    }
+
+    #[test]
+    fn torus_should_handle_tangent_and_grazing_rays() {
+        let material: Arc<dyn Material> = Arc::new(Lambertian::new(SolidColor::from(
+            ColorRGBMapTo0_1::new(0.5, 0.5, 0.5),
+        )));
+        let torus = Torus::new(Point3::new(0.0, 1.0, 0.0), 2.0, 0.5, material);
+        let along_x = Vec3::new(1.0, 0.0, 0.0);
+        let hit = |origin: Point3, direction: Vec3| {
+            torus.hit(&Ray::new(origin, direction, 0.0), 0.001, f32::INFINITY)
+        };
+
+        let hit_record = hit(Point3::new(-10.0, 1.0, 0.0), along_x).unwrap();
+        assert!((hit_record.t - 7.5).abs() < 1e-4);
+        assert!((hit_record.normal() - Vec3::new(-1.0, 0.0, 0.0)).len() < 1e-4);
+        assert!(hit_record.is_front_face());
+
+        // From far away the result should be just as precise.
+        let hit_record = hit(Point3::new(-1000.0, 1.0, 0.0), 0.5 * along_x).unwrap();
+        assert!((hit_record.position() - Point3::new(-2.5, 1.0, 0.0)).len() < 1e-3);
+
+        // Through the hole, and down into the tube.
+        let down = Vec3::new(0.0, -1.0, 0.0);
+        assert!(hit(Point3::new(0.0, 5.0, 0.0), down).is_none());
+        let hit_record = hit(Point3::new(2.0, 5.0, 0.0), down).unwrap();
+        assert!((hit_record.t - 3.5).abs() < 1e-4);
+        assert!((hit_record.v() - 0.25).abs() < 1e-4);
+
+        // Tangent to the top of the tube, where the ray meets a double root.
+        let hit_record = hit(Point3::new(-10.0, 1.4999, 0.0), along_x).unwrap();
+        assert!((hit_record.position().x() + 2.0).abs() < 0.05);
+        assert!(hit(Point3::new(-10.0, 1.501, 0.0), along_x).is_none());
+
+        // Grazing the outer equator.
+        let hit_record = hit(Point3::new(2.4999, 1.0, -10.0), Vec3::new(0.0, 0.0, 1.0)).unwrap();
+        assert!((hit_record.position() - Point3::new(2.5, 1.0, 0.0)).len() < 0.05);
+        assert!(hit(Point3::new(2.501, 1.0, -10.0), Vec3::new(0.0, 0.0, 1.0)).is_none());
+    }
+}
@@ -1,6 +1,15 @@
+pub mod animation;
 pub mod camera;
+pub mod distributed;
 pub mod hittable;
 pub mod material;
 pub mod noise;
+pub mod phase;
 pub mod ray;
+pub mod render;
+pub mod sampler;
+pub mod scene;
+pub mod sdf;
+pub mod stats;
 pub mod textures;
+pub mod volume;
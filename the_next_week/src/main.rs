@@ -1,41 +1,37 @@
-mod camera;
-mod hittable;
-mod material;
-mod noise;
-mod ray;
-mod textures;
-
 extern crate num_cpus;
 use humantime::format_duration;
 use in_one_weekend::{
-    color::{ColorRGB, ColorRGBMapTo0_1},
-    image::{PPMImg, PPMImgMagicNum},
-    point::Point3,
-    thread_pool::ThreadPool,
+    thread_pool::{CancellationToken, ThreadPool},
     utils,
-    vec3::Vec3,
 };
-use rand::{random, Rng};
 
-use std::{
-    sync::{Arc, Mutex},
-    time,
-};
+use std::{fs, io, thread, time};
 
-use crate::{
-    camera::Camera,
-    hittable::{
-        ConstantMedium, Cuboid, Hittable, HittableList, Instance, MovingSphere, Sphere, XZRect,
+use the_next_week::{
+    render::{
+        self, Checkpoint, Framebuffer, PixelEstimate, Progressive, SampleBudget, TileOrder,
+        TileScheduler,
     },
-    material::{Attenuation, Dielectric, DiffuseLight, Lambertian, Material, Metal},
-    noise::Perlin,
-    ray::Ray,
-    textures::{ImageTexture, NoiseTexture, SolidColor},
+    sampler::{Sampler, Sobol},
+    scene::Scene,
+    stats::{self, RenderStats},
 };
 
 const ASPECT_RATIO: f32 = 1.0;
 
-fn main() -> std::io::Result<()> {
+fn main() -> io::Result<()> {
+    // `--resume` carries on from the checkpoint of an earlier run.
+    let resume: bool = match std::env::args().nth(1).as_deref() {
+        None => false,
+        Some("--resume") => true,
+        Some(argument) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown argument {argument}, the only option is --resume"),
+            ))
+        }
+    };
+
     let num_cpus = num_cpus::get();
     println!("num_cpus::get() : {num_cpus}");
 
@@ -46,280 +42,120 @@ fn main() -> std::io::Result<()> {
     const IMAGE_HEIGHT: usize = (IMAGE_WIDTH as f32 / ASPECT_RATIO) as usize;
     const SAMPLES_PER_PIXEL: usize = 10240;
     const OUTPUT_IMAGE_PATH: &str = "./target/image.ppm";
-    let image: Arc<Mutex<_>> = Arc::new(Mutex::new(PPMImg::<IMAGE_WIDTH, IMAGE_HEIGHT>::new(
-        PPMImgMagicNum::P3,
-    )));
-
-    // World
-    let world: Arc<HittableList<Box<dyn Hittable>>> = Arc::new(self::final_scene());
-
-    // Camera
-    let look_from: Point3 = Point3::new(478.0, 278.0, -600.0);
-    let look_at: Point3 = Point3::new(278.0, 278.0, 0.0);
-    let disk_to_focus: f32 = 10.0;
-    const APERTURE: f32 = 0.1;
-
-    let camera: Arc<Camera> = Arc::new(
-        Camera::builder()
-            .look_from(look_from)
-            .look_at(look_at)
-            .up(Vec3::new(0.0, 1.0, 0.0))
-            .fov(40.0)
-            .aspect_ratio(ASPECT_RATIO)
-            .aperture(APERTURE)
-            .focus_dist(disk_to_focus)
-            .time_0(0.0)
-            .time_1(1.0)
-            .build(),
-    );
-
-    let time_render_start: time::Instant = time::Instant::now();
-
-    // Render
+    // A base pass of 256 samples everywhere, and up to SAMPLES_PER_PIXEL in the
+    // pixels still noisier than 1% after it.
+    let budget: SampleBudget = SampleBudget::adaptive(256, SAMPLES_PER_PIXEL, 0.01);
+    // Samples added to each unconverged pixel before the images are rewritten.
+    const SAMPLES_PER_PASS: usize = 64;
+    // Where to write how many samples each pixel took, white for the most.
+    const SAMPLE_COUNT_IMAGE_PATH: Option<&str> = Some("./target/sample_count.ppm");
+    // Every random number of the render derives from it, for repeatable images.
+    const SEED: u64 = 0;
+    // Where the framebuffer is saved after each pass, for `--resume`.
+    const CHECKPOINT_PATH: &str = "./target/checkpoint.bin";
+    // Each pass is split into jobs of this many by this many pixels.
+    const TILE_SIZE: usize = 32;
+    // Where the counters of the render are written, when built with
+    // `--features stats`.
+    const STATS_PATH: &str = "./target/stats.json";
+
+    // World and camera
     const MAX_DEPTH_RAY_RECURSION: u16 = 50;
-    let num_pixels_has_rendered: Arc<Mutex<usize>> = Arc::new(Mutex::new(0usize));
-
-    (0..IMAGE_HEIGHT).for_each(|row| {
-        (0..IMAGE_WIDTH).for_each(|column| {
-            let image = Arc::clone(&image);
-            let world = Arc::clone(&world);
-            let camera = Arc::clone(&camera);
-            let num_pixels_has_rendered = Arc::clone(&num_pixels_has_rendered);
-
-            thread_pool.execute(move || {
-                let pixel_color: ColorRGB = self::pixel_color::<
-                    IMAGE_HEIGHT,
-                    IMAGE_WIDTH,
-                    SAMPLES_PER_PIXEL,
-                    MAX_DEPTH_RAY_RECURSION,
-                >(row, column, world.as_ref(), &camera);
+    let scene: Scene =
+        Scene::final_scene(SEED, ASPECT_RATIO).set_max_depth(MAX_DEPTH_RAY_RECURSION);
 
-                image
-                    .lock()
-                    .unwrap()
-                    .set_pixel_color(row, column, pixel_color);
-
-                if let Ok(mut num) = num_pixels_has_rendered.lock() {
-                    *num += 1;
-                    if *num % (IMAGE_HEIGHT * IMAGE_WIDTH / 1000) == 0 {
-                        utils::log_progress(*num as f64 / (IMAGE_HEIGHT * IMAGE_WIDTH) as f64)
-                            .unwrap();
-                    }
-                };
-            })
-        });
-    });
+    let sampler: Sobol = Sobol::new().set_seed(SEED);
 
+    // Pressing Enter stops the render once the tiles in flight are done, with
+    // a checkpoint to `--resume` from.
+    let cancellation: CancellationToken = CancellationToken::new();
     {
-        let num_pixels_has_rendered = Arc::clone(&num_pixels_has_rendered);
-        thread_pool.execute(move || {
-            while let Ok(num_mutex_guard) = num_pixels_has_rendered.lock() {
-                if *num_mutex_guard == IMAGE_HEIGHT * IMAGE_WIDTH {
-                    break;
-                }
+        let cancellation = cancellation.clone();
+        thread::spawn(move || {
+            // Nothing to read, as from /dev/null, is no request to stop.
+            if let Ok(1..) = io::stdin().read_line(&mut String::new()) {
+                cancellation.cancel();
             }
-
-            eprintln!(
-                "The render took {}",
-                format_duration(time_render_start.elapsed())
-            );
         });
     }
 
-    thread_pool.execute(move || {
-        while let Ok(num_mutex_guard) = num_pixels_has_rendered.lock() {
-            if *num_mutex_guard == IMAGE_HEIGHT * IMAGE_WIDTH {
-                break;
-            }
-        }
-
-        image
-            .lock()
-            .map(|img_mutex_guard| {
-                eprintln!("Writing image to file···");
-                img_mutex_guard.write_to_file(OUTPUT_IMAGE_PATH)
-            })
-            .unwrap_or_else(|p_err| p_err.into_inner().write_to_file(OUTPUT_IMAGE_PATH))
-            .map(|_| eprintln!("Writing image to file done!"))
-            .unwrap_or_else(|err| eprintln!("Writing image to file failed! {}", err))
-    });
-
-    Ok(())
-}
+    let time_render_start: time::Instant = time::Instant::now();
 
-fn ray_color(ray: &Ray, world: &dyn Hittable, depth: u16) -> ColorRGBMapTo0_1 {
-    if depth == 0 {
-        return ColorRGBMapTo0_1::new(0.0, 0.0, 0.0);
+    // Render, rewriting the images after each pass so that the render can be
+    // stopped whenever it looks good enough.
+    let integrator = |row: usize, column: usize, sampler: &mut dyn Sampler| {
+        scene.sample(row, column, IMAGE_WIDTH, IMAGE_HEIGHT, sampler)
+    };
+    let checkpoint: Checkpoint<IMAGE_WIDTH, IMAGE_HEIGHT> =
+        Checkpoint::new(SEED, sampler, &integrator);
+    let mut progressive: Progressive<IMAGE_WIDTH, IMAGE_HEIGHT> = match resume {
+        true => {
+            eprintln!("Resuming from {CHECKPOINT_PATH}");
+            let framebuffer = checkpoint.load(CHECKPOINT_PATH)?;
+            Progressive::with_framebuffer(budget, SAMPLES_PER_PASS, framebuffer)
+        }
+        false => Progressive::new(budget, SAMPLES_PER_PASS),
     }
+    .set_scheduler(
+        TileScheduler::new(TILE_SIZE, TileOrder::Spiral).set_cancellation(cancellation.clone()),
+    );
+    let passes: usize = SAMPLES_PER_PIXEL.div_ceil(SAMPLES_PER_PASS);
+
+    let mut pass: usize = progressive
+        .framebuffer()
+        .pixels()
+        .map(PixelEstimate::count)
+        .max()
+        .unwrap_or_default()
+        .div_ceil(SAMPLES_PER_PASS);
+    while !progressive.is_done() && !cancellation.is_cancelled() {
+        progressive.pass(&thread_pool, sampler, &integrator);
+        pass += 1;
+
+        let framebuffer: &Framebuffer<IMAGE_WIDTH, IMAGE_HEIGHT> = progressive.framebuffer();
+        render::write_image(&framebuffer.to_image(scene.exposure()), OUTPUT_IMAGE_PATH)?;
+        if let Some(path) = SAMPLE_COUNT_IMAGE_PATH {
+            render::write_image(&framebuffer.to_sample_count_image(budget.max()), path)?;
+        }
+        checkpoint.save(framebuffer, CHECKPOINT_PATH)?;
 
-    let background_color = ColorRGBMapTo0_1::new(0.0, 0.0, 0.0);
-
-    world
-        .hit(ray, 0.001, f32::INFINITY)
-        .map_or(background_color, |hit_record| {
-            match (
-                hit_record.material().emitted(
-                    hit_record.u(),
-                    hit_record.v(),
-                    &hit_record.position(),
-                ),
-                hit_record.material().scatter(ray, &hit_record),
-            ) {
-                (None, None) => background_color,
-                (Some(emit_record), None) => emit_record.color() * emit_record.luminance(),
-                (None, Some(scatter_rec)) => {
-                    scatter_rec.albedo() * ray_color(scatter_rec.ray_scattered(), world, depth - 1)
-                }
-                (Some(emit_record), Some(scatter_rec)) => {
-                    emit_record.color() * emit_record.luminance()
-                        + scatter_rec.albedo()
-                            * ray_color(scatter_rec.ray_scattered(), world, depth - 1)
-                }
-            }
-        })
-}
-
-fn pixel_color<const HEIGHT: usize, const WIDTH: usize, const SAMPLES: usize, const DEPTH: u16>(
-    row: usize,
-    column: usize,
-    world: &dyn Hittable,
-    camera: &Camera,
-) -> ColorRGB {
-    let [red, green, blue] = (0..SAMPLES)
-        .fold([0.0, 0.0, 0.0], |[r, g, b], _| {
-            let u = (column as f32 + random::<f32>()) / (WIDTH - 1) as f32;
-            let v = ((HEIGHT - 1 - row) as f32 + random::<f32>()) / (HEIGHT - 1) as f32;
-            let ray: Ray = camera.get_ray(u, v);
-            let ray_color: ColorRGBMapTo0_1 = ray_color(&ray, world, DEPTH);
-
-            [r + ray_color.r(), g + ray_color.g(), b + ray_color.b()]
-        })
-        // Divide the color by the number of samples and gamma-correct for gamma=2.0.
-        .map(|v: f32| (v / SAMPLES as f32).sqrt());
-
-    ColorRGBMapTo0_1::new(red, green, blue).into()
-}
-
-fn final_scene() -> HittableList<Box<dyn Hittable>> {
-    let mut objects: HittableList<Box<dyn Hittable>> = HittableList::default();
-
-    const SIZE: usize = 20;
-    let mut boxes1: HittableList<Box<dyn Hittable>> = HittableList::with_capacity(SIZE * SIZE);
-    let ground: Arc<Lambertian<SolidColor>> = Arc::new(Lambertian::new(SolidColor::from(
-        ColorRGBMapTo0_1::new(0.48, 0.83, 0.53),
-    )));
-    let mut rng = rand::thread_rng();
-    (0..SIZE).for_each(|i| {
-        (0..SIZE).for_each(|j| {
-            let w = 100.0;
-            let [x0, y0, z0]: [f32; 3] = [-1000.0 + i as f32 * w, 0.0, -1000.0 + j as f32 * w];
-            let [x1, y1, z1]: [f32; 3] = [x0 + w, rng.gen_range(1.0..101.0), z0 + w];
-
-            boxes1.add(Box::new(Cuboid::new(
-                Point3::new(x0, y0, z0),
-                Point3::new(x1, y1, z1),
-                Arc::clone(&ground) as Arc<dyn Material>,
-            )))
-        })
-    });
-    objects.add(Box::new(boxes1));
-
-    let light: Arc<DiffuseLight<SolidColor>> = Arc::new(DiffuseLight::new(
-        SolidColor::from(ColorRGBMapTo0_1::new(1.0, 1.0, 1.0)),
-        15.0,
-    ));
-    objects.add(Box::new(XZRect::new(
-        123.0..=423.0,
-        147.0..=412.0,
-        554.0,
-        light as Arc<dyn Material>,
-    )));
-
-    let center_1: Point3 = Point3::new(400.0, 400.0, 200.0);
-    let center_2: Point3 = Point3::new(400.0, 400.0, 200.0) + Point3::new(30.0, 0.0, 0.0);
-    objects.add(Box::new(MovingSphere::new(
-        center_1,
-        center_2,
-        0.0,
-        1.0,
-        50.0,
-        Arc::new(Lambertian::new(SolidColor::from(ColorRGBMapTo0_1::new(
-            0.7, 0.3, 0.1,
-        )))) as Arc<dyn Material>,
-    )));
-
-    let dielectric: Arc<Dielectric> = Arc::new(Dielectric::new(1.5));
-    objects.add(Box::new(Sphere::new(
-        Point3::new(260.0, 150.0, 45.0),
-        50.0,
-        Arc::clone(&dielectric) as Arc<dyn Material>,
-    )));
-
-    let metal: Arc<Metal> = Arc::new(Metal::new(Attenuation::new(Vec3::new(0.8, 0.8, 0.9)), 1.0));
-    objects.add(Box::new(Sphere::new(
-        Point3::new(0.0, 150.0, 145.0),
-        50.0,
-        metal as Arc<dyn Material>,
-    )));
-
-    let boundary = Arc::new(Sphere::new(
-        Point3::new(360.0, 150.0, 145.0),
-        70.0,
-        Arc::clone(&dielectric) as Arc<dyn Material>,
-    ));
-    objects.add(Box::new(Arc::clone(&boundary)));
-    objects.add(Box::new(ConstantMedium::new(
-        boundary,
-        SolidColor::from(ColorRGBMapTo0_1::new(0.2, 0.4, 0.9)),
-        0.2,
-    )));
-    objects.add(Box::new(ConstantMedium::new(
-        Sphere::new(
-            Point3::new(0.0, 0.0, 0.0),
-            5000.0,
-            Arc::clone(&dielectric) as Arc<dyn Material>,
-        ),
-        SolidColor::from(ColorRGBMapTo0_1::new(1.0, 1.0, 1.0)),
-        0.0001,
-    )));
-
-    let emat: Arc<Lambertian<ImageTexture>> = Arc::new(Lambertian::new(
-        ImageTexture::new("the_next_week/assets/images/earthmap.jpg")
-            .expect("can not find image under the path 'the_next_week/assets/images/earthmap.jpg'"),
-    ));
-    objects.add(Box::new(Sphere::new(
-        Point3::new(400.0, 200.0, 400.0),
-        100.0,
-        emat as Arc<dyn Material>,
-    )));
+        utils::log_progress(match progressive.is_done() {
+            true => 1.0,
+            false => pass as f64 / passes as f64,
+        })?;
+    }
 
-    let pertext: NoiseTexture<Perlin> = NoiseTexture::new(Perlin::default()).set_scale(0.1);
-    objects.add(Box::new(Sphere::new(
-        Point3::new(220.0, 280.0, 300.0),
-        80.0,
-        Arc::new(Lambertian::new(pertext)) as Arc<dyn Material>,
-    )));
+    eprintln!(
+        "The render took {} up to pass {pass}",
+        format_duration(time_render_start.elapsed())
+    );
+    if cancellation.is_cancelled() {
+        eprintln!("Stopped early, run again with --resume to carry on");
+    }
 
-    const CAPACITY: usize = 1000;
-    let mut boxes2: HittableList<Box<dyn Hittable>> = HittableList::with_capacity(CAPACITY);
-    let white: Arc<Lambertian<SolidColor>> = Arc::new(Lambertian::new(SolidColor::from(
-        ColorRGBMapTo0_1::new(0.73, 0.73, 0.73),
-    )));
-    (0..CAPACITY).for_each(|_| {
-        boxes2.add(Box::new(Sphere::new(
-            Point3::random_range(0.0, 165.0),
-            10.0,
-            Arc::clone(&white) as Arc<dyn Material>,
-        )))
-    });
+    let millis =
+        |duration: time::Duration| time::Duration::from_millis(duration.as_millis() as u64);
+    thread_pool
+        .stats()
+        .iter()
+        .enumerate()
+        .for_each(|(worker, stats)| {
+            eprintln!(
+                "Worker {worker}: {} jobs ({} stolen), busy {}, idle {}",
+                stats.jobs_run,
+                stats.jobs_stolen,
+                format_duration(millis(stats.busy)),
+                format_duration(millis(stats.idle)),
+            )
+        });
 
-    objects.add(Box::new(Instance::Translate {
-        prototype: Box::new(Instance::RotateY {
-            prototype: Box::new(boxes2) as Box<dyn Hittable>,
-            radians: 15_f32.to_radians(),
-        }) as Box<dyn Hittable>,
-        displacement: Vec3::new(-100.0, 270.0, 395.0),
-    }));
+    if stats::ENABLED {
+        let render_stats: RenderStats =
+            RenderStats::collect(time_render_start.elapsed(), &thread_pool.stats());
+        eprintln!("{render_stats}");
+        fs::write(STATS_PATH, render_stats.to_json())?;
+        eprintln!("Render statistics written to {STATS_PATH}");
+    }
 
-    objects
+    Ok(())
 }
@@ -1,7 +1,6 @@
 use in_one_weekend::vec3::Vec3;
-use rand::random;
 
-use crate::{hittable::HitRecord, ray::Ray};
+use crate::{hittable::HitRecord, ray::Ray, sampler::Sampler, stats};
 
 use super::{reflect, refract, Attenuation, Emit, Scatter, ScatterRecord};
 
@@ -26,7 +25,13 @@ impl Dielectric {
 impl Emit for Dielectric {}
 
 impl Scatter for Dielectric {
-    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
+        stats::scatter("dielectric");
         let refract_ration: f32 = match hit_record.is_front_face() {
             // IOR of air is 1.0
             true => 1.0 / self.ior,
@@ -38,7 +43,7 @@ impl Scatter for Dielectric {
         let sin_theta: f32 = (1.0 - cos_theta.powi(2)).abs().sqrt();
 
         let direction: Vec3 = match 1.0 < refract_ration * sin_theta
-            || random::<f32>() < Self::reflectance(cos_theta, refract_ration)
+            || sampler.get_1d() < Self::reflectance(cos_theta, refract_ration)
         {
             true => reflect(ray_in_dir_unit, hit_record.normal()),
             false => refract(
@@ -1,6 +1,6 @@
 use in_one_weekend::point::Point3;
 
-use crate::{hittable::HitRecord, ray::Ray, textures::Texture};
+use crate::{hittable::HitRecord, ray::Ray, sampler::Sampler, textures::Texture};
 
 use super::{Emit, EmitRecord, Scatter, ScatterRecord};
 
@@ -28,7 +28,12 @@ impl<T: Texture> Emit for DiffuseLight<T> {
 }
 
 impl<T: Texture> Scatter for DiffuseLight<T> {
-    fn scatter(&self, _ray_in: &Ray, _hit_record: &HitRecord) -> Option<ScatterRecord> {
+    fn scatter(
+        &self,
+        _ray_in: &Ray,
+        _hit_record: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
         None
     }
 }
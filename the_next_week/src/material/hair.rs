@@ -0,0 +1,343 @@
+use std::f32::consts::{PI, TAU};
+
+use in_one_weekend::{color::ColorRGBMapTo0_1, vec3::Vec3};
+
+use crate::{hittable::HitRecord, ray::Ray, sampler::Sampler, stats};
+
+use super::{dielectric::Dielectric, Attenuation, Emit, Scatter, ScatterRecord};
+
+/// Number of lobes sampled separately: reflection (R), transmission through
+/// the fibre (TT), one internal reflection (TRT), and all longer paths
+/// together.
+const LOBES: usize = 4;
+
+/// Light scattered by a fibre, after the model of d'Eon et al. (2011) as
+/// extended by Chiang et al. (2016): each lobe is a longitudinal spread around
+/// the cone of mirror directions, times an azimuthal spread around the
+/// direction refraction through a cylinder leads to.
+///
+/// Meant for `Curve`, whose tangent gives the direction of the fibre and whose
+/// v gives where across it the ray hit.
+///
+/// A lobe is chosen in proportion to the light it carries and then sampled
+/// exactly, so the scattered ray is weighted by the colour of the lobe only.
+pub struct Hair {
+    sigma_a: Vec3,
+    eta: f32,
+    /// Longitudinal variance per lobe.
+    v: [f32; LOBES],
+    /// Azimuthal logistic scale.
+    s: f32,
+    /// Sine and cosine of twice, four times and eight times the scale tilt.
+    sin_2k_alpha: [f32; 3],
+    cos_2k_alpha: [f32; 3],
+}
+
+impl Hair {
+    /// `sigma_a` is the absorption inside the fibre per unit of its diameter.
+    /// `beta_m` and `beta_n`, both in `(0, 1]`, are the longitudinal and
+    /// azimuthal roughness, and `alpha` the tilt of the cuticle scales in
+    /// degrees, usually about 2.
+    pub fn new(sigma_a: Vec3, beta_m: f32, beta_n: f32, alpha: f32) -> Self {
+        debug_assert!(0.0 < beta_m && beta_m <= 1.0);
+        debug_assert!(0.0 < beta_n && beta_n <= 1.0);
+
+        let v0: f32 = (0.726 * beta_m + 0.812 * beta_m.powi(2) + 3.7 * beta_m.powi(20)).powi(2);
+        let s: f32 =
+            (PI / 8.0).sqrt() * (0.265 * beta_n + 1.194 * beta_n.powi(2) + 5.372 * beta_n.powi(22));
+
+        let mut sin_2k_alpha = [alpha.to_radians().sin(), 0.0, 0.0];
+        let mut cos_2k_alpha = [(1.0 - sin_2k_alpha[0].powi(2)).max(0.0).sqrt(), 0.0, 0.0];
+        for i in 1..3 {
+            sin_2k_alpha[i] = 2.0 * cos_2k_alpha[i - 1] * sin_2k_alpha[i - 1];
+            cos_2k_alpha[i] = cos_2k_alpha[i - 1].powi(2) - sin_2k_alpha[i - 1].powi(2);
+        }
+
+        Self {
+            sigma_a,
+            eta: 1.55,
+            v: [v0, v0 / 4.0, 4.0 * v0, 4.0 * v0],
+            s,
+            sin_2k_alpha,
+            cos_2k_alpha,
+        }
+    }
+
+    /// Natural hair from the concentrations of the dark eumelanin and the
+    /// reddish pheomelanin: about 8 for black hair, 1.3 for brown and 0.3 for
+    /// blonde, with a little pheomelanin for red tints.
+    pub fn from_melanin(eumelanin: f32, pheomelanin: f32, beta_m: f32, beta_n: f32) -> Self {
+        let sigma_a: Vec3 =
+            eumelanin * Vec3::new(0.419, 0.697, 1.37) + pheomelanin * Vec3::new(0.187, 0.4, 1.05);
+
+        Self::new(sigma_a, beta_m, beta_n, 2.0)
+    }
+
+    /// The absorption that makes hair of the given roughness about `color`
+    /// when lit from all around.
+    pub fn from_color(color: ColorRGBMapTo0_1, beta_m: f32, beta_n: f32) -> Self {
+        let denominator: f32 = 5.969 - 0.215 * beta_n + 2.532 * beta_n.powi(2)
+            - 10.73 * beta_n.powi(3)
+            + 5.574 * beta_n.powi(4)
+            + 0.245 * beta_n.powi(5);
+        let absorption = |c: f32| (c.max(1e-4).ln() / denominator).powi(2);
+
+        Self::new(
+            Vec3::new(
+                absorption(color.r()),
+                absorption(color.g()),
+                absorption(color.b()),
+            ),
+            beta_m,
+            beta_n,
+            2.0,
+        )
+    }
+
+    pub fn set_eta(mut self, eta: f32) -> Self {
+        self.eta = eta;
+        self
+    }
+
+    /// The light each lobe carries for a ray at `cos_theta_o` to the normal
+    /// plane of the fibre hitting it at offset `h` from its axis.
+    fn attenuation(&self, cos_theta_o: f32, h: f32) -> [Vec3; LOBES] {
+        let sin_theta_o: f32 = (1.0 - cos_theta_o.powi(2)).max(0.0).sqrt();
+        let sin_theta_t: f32 = sin_theta_o / self.eta;
+        let cos_theta_t: f32 = (1.0 - sin_theta_t.powi(2)).max(0.0).sqrt();
+        let cos_gamma_t: f32 = (1.0 - (h / self.modified_eta(sin_theta_o, cos_theta_o)).powi(2))
+            .max(0.0)
+            .sqrt();
+
+        // Transmittance of one pass through the fibre.
+        let length: f32 = 2.0 * cos_gamma_t / cos_theta_t;
+        let t: Vec3 = Vec3::new(
+            (-self.sigma_a.x() * length).exp(),
+            (-self.sigma_a.y() * length).exp(),
+            (-self.sigma_a.z() * length).exp(),
+        );
+
+        let cos_gamma_o: f32 = (1.0 - h * h).max(0.0).sqrt();
+        let f: f32 = Dielectric::reflectance(cos_theta_o * cos_gamma_o, self.eta);
+        let r: Vec3 = Vec3::new(f, f, f);
+        let tt: Vec3 = (1.0 - f).powi(2) * t;
+        let trt: Vec3 = f * tt * t;
+        let rest: Vec3 = Vec3::new(
+            trt.x() * f * t.x() / (1.0 - t.x() * f),
+            trt.y() * f * t.y() / (1.0 - t.y() * f),
+            trt.z() * f * t.z() / (1.0 - t.z() * f),
+        );
+
+        [r, tt, trt, rest]
+    }
+
+    /// The index of refraction seen in the normal plane of the fibre.
+    fn modified_eta(&self, sin_theta_o: f32, cos_theta_o: f32) -> f32 {
+        (self.eta * self.eta - sin_theta_o * sin_theta_o).sqrt() / cos_theta_o.max(1e-4)
+    }
+
+    /// `sin_theta_o` and `cos_theta_o` turned by the scale tilt of `lobe`.
+    fn tilt(&self, lobe: usize, sin_theta_o: f32, cos_theta_o: f32) -> (f32, f32) {
+        let (k, sign): (usize, f32) = match lobe {
+            0 => (1, -1.0),
+            1 => (0, 1.0),
+            2 => (2, 1.0),
+            _ => return (sin_theta_o, cos_theta_o),
+        };
+        let (sin, cos): (f32, f32) = (sign * self.sin_2k_alpha[k], self.cos_2k_alpha[k]);
+
+        (
+            sin_theta_o * cos + cos_theta_o * sin,
+            cos_theta_o * cos - sin_theta_o * sin,
+        )
+    }
+}
+
+fn luminance(c: Vec3) -> f32 {
+    0.2126 * c.x() + 0.7152 * c.y() + 0.0722 * c.z()
+}
+
+/// A sample of the logistic distribution with scale `s` restricted to
+/// `[-π, π]`.
+fn sample_trimmed_logistic(u: f32, s: f32) -> f32 {
+    let cdf = |x: f32| 1.0 / (1.0 + (-x / s).exp());
+    let k: f32 = cdf(PI) - cdf(-PI);
+    let x: f32 = -s * (1.0 / (u * k + cdf(-PI)) - 1.0).ln();
+
+    x.clamp(-PI, PI)
+}
+
+impl Emit for Hair {}
+
+impl Scatter for Hair {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
+        stats::scatter("hair");
+        let wo: Vec3 = -ray_in.direction().unit_vector();
+
+        // The fibre runs along x, and z faces the ray across it.
+        let x: Vec3 = match hit_record.tangent() {
+            Some(tangent) => tangent,
+            None => {
+                let normal: Vec3 = hit_record.normal();
+                let other: Vec3 = match normal.x().abs() < 0.9 {
+                    true => Vec3::new(1.0, 0.0, 0.0),
+                    false => Vec3::new(0.0, 1.0, 0.0),
+                };
+                normal.cross(other).unit_vector()
+            }
+        };
+        let across: Vec3 = wo - wo.dot(x) * x;
+        let z: Vec3 = match across.is_near_zero() {
+            true => hit_record.normal(),
+            false => across.unit_vector(),
+        };
+        let y: Vec3 = z.cross(x);
+
+        let h: f32 = (2.0 * hit_record.v() - 1.0).clamp(-1.0, 1.0);
+        let sin_theta_o: f32 = wo.dot(x).clamp(-1.0, 1.0);
+        let cos_theta_o: f32 = (1.0 - sin_theta_o.powi(2)).max(0.0).sqrt();
+        let phi_o: f32 = wo.dot(z).atan2(wo.dot(y));
+
+        // Pick a lobe in proportion to its luminance.
+        let attenuation: [Vec3; LOBES] = self.attenuation(cos_theta_o, h);
+        let total: f32 = attenuation.iter().copied().map(luminance).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut u: f32 = sampler.get_1d() * total;
+        let lobe: usize = (0..LOBES)
+            .find(|&p| {
+                let found: bool = u < luminance(attenuation[p]);
+                u -= luminance(attenuation[p]);
+                found
+            })
+            .unwrap_or(LOBES - 1);
+        let pdf: f32 = luminance(attenuation[lobe]) / total;
+
+        // The longitudinal angle.
+        let (sin_theta_op, cos_theta_op) = self.tilt(lobe, sin_theta_o, cos_theta_o);
+        let v: f32 = self.v[lobe];
+        let [u, u_phi]: [f32; 2] = sampler.get_2d();
+        let u: f32 = u.max(1e-5);
+        let cos_theta: f32 = 1.0 + v * (u + (1.0 - u) * (-2.0 / v).exp()).ln();
+        let sin_theta: f32 = (1.0 - cos_theta.powi(2)).max(0.0).sqrt();
+        let cos_phi: f32 = (TAU * u_phi).cos();
+        let sin_theta_i: f32 =
+            (-cos_theta * sin_theta_op + sin_theta * cos_phi * cos_theta_op).clamp(-1.0, 1.0);
+        let cos_theta_i: f32 = (1.0 - sin_theta_i.powi(2)).max(0.0).sqrt();
+
+        // The azimuthal angle, around where the lobe leaves the cylinder.
+        let gamma_o: f32 = h.asin();
+        let gamma_t: f32 = (h / self.modified_eta(sin_theta_o, cos_theta_o))
+            .clamp(-1.0, 1.0)
+            .asin();
+        let d_phi: f32 = match lobe < LOBES - 1 {
+            true => {
+                let p: f32 = lobe as f32;
+                2.0 * p * gamma_t - 2.0 * gamma_o
+                    + p * PI
+                    + sample_trimmed_logistic(sampler.get_1d(), self.s)
+            }
+            false => TAU * sampler.get_1d(),
+        };
+        let phi_i: f32 = phi_o + d_phi;
+
+        let direction: Vec3 =
+            sin_theta_i * x + cos_theta_i * phi_i.cos() * y + cos_theta_i * phi_i.sin() * z;
+
+        // Lobes brighter than the chance of picking them, in some channel,
+        // are weighted above one.
+        Some(ScatterRecord::new(
+            Ray::new(hit_record.position(), direction, ray_in.time()),
+            Attenuation(attenuation[lobe] / pdf),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use in_one_weekend::point::Point3;
+
+    use super::*;
+    use crate::{
+        hittable::{Curve, Hittable},
+        material::Material,
+        sampler::Independent,
+    };
+
+    fn scatter_many(hair: Hair) -> Vec<(Vec3, Vec3)> {
+        let material: Arc<dyn Material> = Arc::new(hair);
+        let curve = Curve::new(
+            [
+                Point3::new(0.0, -1.0, 0.0),
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(0.0, 1.0, 0.0),
+                Point3::new(0.0, 2.0, 0.0),
+            ],
+            [0.1, 0.1],
+            material,
+        );
+
+        (0..2000)
+            .map(|i| {
+                let x: f32 = (i as f32 / 2000.0 - 0.5) * 0.09;
+                let ray = Ray::new(Point3::new(x, 0.5, -2.0), Vec3::new(0.0, -0.3, 1.0), 0.0);
+                let hit_record = curve.hit(&ray, 0.001, f32::INFINITY).unwrap();
+                let scattered = hit_record
+                    .material()
+                    .scatter(&ray, &hit_record, &mut Independent::new())
+                    .unwrap();
+                let albedo: ColorRGBMapTo0_1 = scattered.albedo().into();
+                (
+                    scattered.ray_scattered().direction(),
+                    Vec3::new(albedo.r(), albedo.g(), albedo.b()),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hair_without_absorption_should_keep_all_light() {
+        scatter_many(Hair::new(Vec3::new(0.0, 0.0, 0.0), 0.3, 0.3, 2.0))
+            .iter()
+            .for_each(|(direction, weight)| {
+                assert!((direction.len() - 1.0).abs() < 1e-4);
+                assert!(
+                    (*weight - Vec3::new(1.0, 1.0, 1.0)).len() < 1e-4,
+                    "{weight}"
+                );
+            });
+    }
+
+    #[test]
+    fn hair_should_scatter_around_the_specular_cone() {
+        let samples = scatter_many(Hair::from_melanin(8.0, 0.0, 0.2, 0.3));
+
+        // Black hair about only reflects, on down the fibre and back towards
+        // the ray.
+        let mean: Vec3 = samples
+            .iter()
+            .fold(Vec3::new(0.0, 0.0, 0.0), |sum, (direction, _)| {
+                sum + *direction
+            })
+            / samples.len() as f32;
+        assert!(mean.y() < -0.15, "{mean}");
+        assert!(mean.z() < 0.0, "{mean}");
+        let weight: Vec3 = samples
+            .iter()
+            .fold(Vec3::new(0.0, 0.0, 0.0), |sum, (_, w)| sum + *w)
+            / samples.len() as f32;
+        assert!(weight.x() < 0.5, "{weight}");
+
+        // Blonde hair passes more light in red than in blue.
+        let blonde = Hair::from_color(ColorRGBMapTo0_1::new(0.8, 0.6, 0.3), 0.3, 0.3);
+        assert!(blonde.sigma_a.x() < blonde.sigma_a.z());
+    }
+}
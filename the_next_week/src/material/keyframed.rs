@@ -0,0 +1,152 @@
+use in_one_weekend::point::Point3;
+
+use crate::{hittable::HitRecord, ray::Ray, sampler::Sampler};
+
+use super::{Emit, EmitRecord, Material, Scatter, ScatterRecord};
+
+/// A material that changes over time, from one keyed material to the next,
+/// such as a surface turning from matte to polished.
+///
+/// Between two keys each ray scatters off one of them, picked by how near
+/// the time of the ray is to each, which blends the two on average without
+/// their parameters having to be interpolated. The light emitted is picked
+/// the same way by `emitted_sampled`; `emitted`, which has no time to go by,
+/// is that of the first key.
+pub struct Keyframed<M: AsRef<dyn Material>> {
+    keys: Vec<(f32, M)>,
+}
+
+impl<M: AsRef<dyn Material>> Keyframed<M> {
+    /// `(time, material)` keys. Panics if `keys` is empty.
+    pub fn new(mut keys: Vec<(f32, M)>) -> Self {
+        assert!(!keys.is_empty(), "a keyframed material needs keys");
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { keys }
+    }
+
+    /// The material a ray at `time` meets.
+    fn at(&self, time: f32, sampler: &mut dyn Sampler) -> &dyn Material {
+        let next: usize = self.keys.partition_point(|(key_time, _)| *key_time <= time);
+        let key: usize = match next {
+            0 => 0,
+            n if n == self.keys.len() => n - 1,
+            n => {
+                let (time_a, time_b): (f32, f32) = (self.keys[n - 1].0, self.keys[n].0);
+                match sampler.get_1d() < (time - time_a) / (time_b - time_a) {
+                    true => n,
+                    false => n - 1,
+                }
+            }
+        };
+
+        self.keys[key].1.as_ref()
+    }
+}
+
+impl<M: AsRef<dyn Material>> Emit for Keyframed<M> {
+    fn emitted(&self, u: f32, v: f32, p: &Point3) -> Option<EmitRecord> {
+        self.keys[0].1.as_ref().emitted(u, v, p)
+    }
+
+    fn emitted_sampled(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<EmitRecord> {
+        self.at(ray_in.time(), sampler)
+            .emitted_sampled(ray_in, hit_record, sampler)
+    }
+}
+
+impl<M: AsRef<dyn Material>> Scatter for Keyframed<M> {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
+        self.at(ray_in.time(), sampler)
+            .scatter(ray_in, hit_record, sampler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use in_one_weekend::{color::ColorRGBMapTo0_1, vec3::Vec3};
+
+    use super::*;
+    use crate::{
+        hittable::{Hittable, Sphere},
+        material::{Attenuation, DiffuseLight, Lambertian},
+        sampler::Independent,
+        textures::SolidColor,
+    };
+
+    #[test]
+    fn keyframed_material_should_blend_between_keys() {
+        let lambertian = |color: Vec3| -> Arc<dyn Material> {
+            Arc::new(Lambertian::new(SolidColor::from(Attenuation::new(color))))
+        };
+        let material: Arc<dyn Material> = Arc::new(Keyframed::new(vec![
+            (1.0, lambertian(Vec3::new(0.0, 0.0, 1.0))),
+            (0.0, lambertian(Vec3::new(1.0, 0.0, 0.0))),
+        ]));
+        let sphere = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, material);
+
+        let mean_albedo = |time: f32| -> f32 {
+            let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0), time);
+            let hit_record = sphere.hit(&ray, 0.001, f32::INFINITY).unwrap();
+            (0..4000)
+                .map(|_| {
+                    let scattered = hit_record
+                        .material()
+                        .scatter(&ray, &hit_record, &mut Independent::new())
+                        .unwrap();
+                    let albedo: ColorRGBMapTo0_1 = scattered.albedo().into();
+                    albedo.r()
+                })
+                .sum::<f32>()
+                / 4000.0
+        };
+
+        assert_eq!(1.0, mean_albedo(-1.0));
+        assert_eq!(0.0, mean_albedo(2.0));
+        assert!((mean_albedo(0.25) - 0.75).abs() < 0.05);
+    }
+
+    #[test]
+    fn keyframed_material_should_emit_the_key_of_the_time() {
+        let light = |color: ColorRGBMapTo0_1| -> Arc<dyn Material> {
+            Arc::new(DiffuseLight::new(SolidColor::from(color), 1.0))
+        };
+        let material: Arc<dyn Material> = Arc::new(Keyframed::new(vec![
+            (0.0, light(ColorRGBMapTo0_1::new(1.0, 0.0, 0.0))),
+            (1.0, light(ColorRGBMapTo0_1::new(0.0, 0.0, 1.0))),
+        ]));
+        let sphere = Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, material);
+        let mut sampler = Independent::new().set_seed(1);
+
+        let mut mean_red = |time: f32| -> f32 {
+            let ray = Ray::new(Point3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0), time);
+            let hit_record = sphere.hit(&ray, 0.001, f32::INFINITY).unwrap();
+            (0..4000)
+                .map(|_| {
+                    hit_record
+                        .material()
+                        .emitted_sampled(&ray, &hit_record, &mut sampler)
+                        .unwrap()
+                        .color()
+                        .r()
+                })
+                .sum::<f32>()
+                / 4000.0
+        };
+
+        assert_eq!(1.0, mean_red(-1.0));
+        assert_eq!(0.0, mean_red(2.0));
+        assert!((mean_red(0.75) - 0.25).abs() < 0.05);
+    }
+}
@@ -1,6 +1,6 @@
 use in_one_weekend::vec3::Vec3;
 
-use crate::{hittable::HitRecord, ray::Ray, textures::Texture};
+use crate::{hittable::HitRecord, ray::Ray, sampler::Sampler, stats, textures::Texture};
 
 use super::{Emit, Scatter, ScatterRecord};
 
@@ -17,8 +17,14 @@ impl<T: Texture> Lambertian<T> {
 impl<T: Texture> Emit for Lambertian<T> {}
 
 impl<T: Texture> Scatter for Lambertian<T> {
-    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
-        let mut scatter_direction: Vec3 = hit_record.normal() + Vec3::random_unit_vector();
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
+        stats::scatter("lambertian");
+        let mut scatter_direction: Vec3 = hit_record.normal() + sampler.unit_vector();
 
         // Catch degenerate scatter direction
         if scatter_direction.is_near_zero() {
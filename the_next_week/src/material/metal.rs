@@ -1,6 +1,6 @@
 use in_one_weekend::vec3::Vec3;
 
-use crate::{hittable::HitRecord, ray::Ray};
+use crate::{hittable::HitRecord, ray::Ray, sampler::Sampler, stats};
 
 use super::{reflect, Attenuation, Emit, Scatter, ScatterRecord};
 
@@ -21,7 +21,13 @@ impl Metal {
 impl Emit for Metal {}
 
 impl Scatter for Metal {
-    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
+        stats::scatter("metal");
         let reflect_direction: Vec3 =
             reflect(ray_in.direction().unit_vector(), hit_record.normal());
 
@@ -32,7 +38,7 @@ impl Scatter for Metal {
         Some(ScatterRecord::new(
             Ray::new(
                 hit_record.position(),
-                reflect_direction + self.fuzz * Vec3::random_in_unit_sphere(),
+                reflect_direction + self.fuzz * sampler.in_unit_sphere(),
                 ray_in.time(),
             ),
             self.albedo,
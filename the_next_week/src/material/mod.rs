@@ -1,12 +1,14 @@
 mod dielectric;
 mod diffuse_light;
-mod isotropic;
+mod hair;
+mod keyframed;
 mod lambertian;
 mod metal;
+mod volumetric;
 
 pub use self::{
-    dielectric::Dielectric, diffuse_light::DiffuseLight, isotropic::Isotropic,
-    lambertian::Lambertian, metal::Metal,
+    dielectric::Dielectric, diffuse_light::DiffuseLight, hair::Hair, keyframed::Keyframed,
+    lambertian::Lambertian, metal::Metal, volumetric::Volumetric,
 };
 
 use in_one_weekend::{
@@ -17,16 +19,38 @@ use in_one_weekend::{
 
 use std::ops;
 
-use crate::{hittable::HitRecord, ray::Ray};
+use crate::{hittable::HitRecord, ray::Ray, sampler::Sampler};
 
 pub trait Scatter {
-    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord>;
+    /// The ray scattered off `hit_record`, if any, with its random choices
+    /// drawn from `sampler`.
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord>;
 }
 
 pub trait Emit {
     fn emitted(&self, _u: f32, _v: f32, _p: &Point3) -> Option<EmitRecord> {
         None
     }
+
+    /// The light emitted where `ray_in` hit, with any random choice, such as
+    /// which key of a material changing over time the ray meets, drawn from
+    /// `sampler`.
+    ///
+    /// By default this is `emitted`, which suits materials that do not change
+    /// over time.
+    fn emitted_sampled(
+        &self,
+        _ray_in: &Ray,
+        hit_record: &HitRecord,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<EmitRecord> {
+        self.emitted(hit_record.u(), hit_record.v(), &hit_record.position())
+    }
 }
 
 pub trait Material: Scatter + Emit + Send + Sync {}
@@ -139,9 +163,9 @@ pub struct EmitRecord {
 }
 
 impl EmitRecord {
-    // pub fn new(color: ColorRGBMapTo0_1, luminance: f32) -> Self {
-    //     Self { color, luminance }
-    // }
+    pub fn new(color: ColorRGBMapTo0_1, luminance: f32) -> Self {
+        Self { color, luminance }
+    }
 
     #[inline]
     pub fn color(&self) -> ColorRGBMapTo0_1 {
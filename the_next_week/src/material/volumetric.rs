@@ -0,0 +1,52 @@
+use crate::{
+    hittable::HitRecord, phase::PhaseFunction, ray::Ray, sampler::Sampler, stats, textures::Texture,
+};
+
+use super::{Emit, Scatter, ScatterRecord};
+
+/// The material of a participating medium: the texture gives the single
+/// scattering albedo and the phase function picks the scattered direction.
+pub struct Volumetric<T: Texture, P: PhaseFunction> {
+    albedo: T,
+    phase_function: P,
+}
+
+impl<T: Texture, P: PhaseFunction> Volumetric<T, P> {
+    pub fn new(texture: T, phase_function: P) -> Self {
+        Self {
+            albedo: texture,
+            phase_function,
+        }
+    }
+
+    #[inline]
+    pub fn phase_function(&self) -> &P {
+        &self.phase_function
+    }
+}
+
+impl<T: Texture, P: PhaseFunction> Emit for Volumetric<T, P> {}
+
+impl<T: Texture, P: PhaseFunction> Scatter for Volumetric<T, P> {
+    fn scatter(
+        &self,
+        ray_in: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut dyn Sampler,
+    ) -> Option<ScatterRecord> {
+        stats::scatter("volumetric");
+        // The sampling density equals the phase function, so the phase function
+        // cancels out of the estimator and only the albedo remains.
+        Some(ScatterRecord {
+            ray: Ray::new(
+                hit_record.position(),
+                self.phase_function.sample(ray_in.direction(), sampler),
+                ray_in.time(),
+            ),
+            albedo: self
+                .albedo
+                .value(hit_record.u(), hit_record.v(), &hit_record.position())
+                .into(),
+        })
+    }
+}
@@ -1,5 +1,5 @@
 use in_one_weekend::{point::Point3, vec3::Vec3};
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
 pub struct Perlin {
     ranvec: Box<[Vec3]>,
@@ -11,6 +11,27 @@ pub struct Perlin {
 impl Perlin {
     const POINT_COUNT: usize = 256;
 
+    /// Noise whose gradients and permutations are drawn from `seed`, so that
+    /// the same seed gives the same noise.
+    pub fn new(seed: u64) -> Self {
+        let mut rng: StdRng = StdRng::seed_from_u64(seed);
+        let mut ranvec = Box::new([Vec3::default(); Self::POINT_COUNT]);
+        ranvec.iter_mut().for_each(|vec| {
+            *vec = Vec3::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            )
+            .unit_vector()
+        });
+        Self {
+            ranvec,
+            perm_x: Self::perlin_generate_perm(&mut rng),
+            perm_y: Self::perlin_generate_perm(&mut rng),
+            perm_z: Self::perlin_generate_perm(&mut rng),
+        }
+    }
+
     pub fn noise(&self, p: &Point3) -> f32 {
         let [u, v, w]: [f32; 3] = [p.x(), p.y(), p.z()].map(|v| v - v.floor());
         let [i, j, k]: [usize; 3] = [p.x(), p.y(), p.z()].map(|v| v.floor() as usize);
@@ -39,20 +60,20 @@ impl Perlin {
             .abs()
     }
 
-    fn perlin_generate_perm() -> Box<[usize; Self::POINT_COUNT]> {
+    fn perlin_generate_perm(rng: &mut StdRng) -> Box<[usize; Self::POINT_COUNT]> {
         let mut p = Box::new([0; Self::POINT_COUNT]);
         p.iter_mut()
             .enumerate()
             .for_each(|(index, value)| *value = index);
 
-        Self::permute(&mut p);
+        Self::permute(&mut p, rng);
 
         p
     }
 
-    fn permute(p: &mut [usize; Self::POINT_COUNT]) {
+    fn permute(p: &mut [usize; Self::POINT_COUNT], rng: &mut StdRng) {
         (1..p.len()).rev().for_each(|index| {
-            let target = rand::thread_rng().gen_range(0..index);
+            let target = rng.gen_range(0..index);
             p.swap(index, target);
         })
     }
@@ -77,15 +98,6 @@ impl Perlin {
 
 impl Default for Perlin {
     fn default() -> Self {
-        let mut ranvec = Box::new([Vec3::default(); Self::POINT_COUNT]);
-        ranvec
-            .iter_mut()
-            .for_each(|vec| *vec = Vec3::random_range(-1.0, 1.0).unit_vector());
-        Self {
-            ranvec,
-            perm_x: Self::perlin_generate_perm(),
-            perm_y: Self::perlin_generate_perm(),
-            perm_z: Self::perlin_generate_perm(),
-        }
+        Self::new(0)
     }
 }
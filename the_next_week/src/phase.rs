@@ -0,0 +1,171 @@
+use core::f32::consts::PI;
+
+use in_one_weekend::vec3::Vec3;
+
+use crate::sampler::Sampler;
+
+/// Angular distribution of light scattered inside a participating medium.
+///
+/// `cos_theta` is the cosine of the angle between the direction the light was
+/// travelling before the scattering event and the direction it travels after,
+/// so `1.0` means straight ahead.
+pub trait PhaseFunction: Send + Sync {
+    /// Density per steradian of scattering by the angle with cosine `cos_theta`.
+    fn evaluate(&self, cos_theta: f32) -> f32;
+
+    /// Sample a unit scattered direction for light travelling along `direction`,
+    /// distributed proportionally to `evaluate`.
+    fn sample(&self, direction: Vec3, sampler: &mut dyn Sampler) -> Vec3;
+}
+
+/// Scatters equally in all directions.
+#[derive(Clone, Copy, Default)]
+pub struct IsotropicPhase;
+
+impl PhaseFunction for IsotropicPhase {
+    fn evaluate(&self, _cos_theta: f32) -> f32 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn sample(&self, _direction: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        sampler.unit_vector()
+    }
+}
+
+/// Henyey–Greenstein phase function, the usual stand-in for Mie scattering.
+///
+/// The asymmetry `g` in (-1, 1) is the mean cosine of the scattering angle:
+/// positive values scatter forward (haze, clouds), negative values backward
+/// and zero is isotropic.
+#[derive(Clone, Copy)]
+pub struct HenyeyGreenstein {
+    g: f32,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(g: f32) -> Self {
+        debug_assert!((-1.0..=1.0).contains(&g));
+        Self {
+            g: g.clamp(-0.999, 0.999),
+        }
+    }
+
+    #[inline]
+    pub fn g(&self) -> f32 {
+        self.g
+    }
+}
+
+impl PhaseFunction for HenyeyGreenstein {
+    fn evaluate(&self, cos_theta: f32) -> f32 {
+        let denom: f32 = 1.0 + self.g * self.g - 2.0 * self.g * cos_theta;
+
+        (1.0 - self.g * self.g) / (4.0 * PI * denom * denom.sqrt())
+    }
+
+    fn sample(&self, direction: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        let [xi, u_phi]: [f32; 2] = sampler.get_2d();
+        let cos_theta: f32 = if self.g.abs() < 1e-3 {
+            1.0 - 2.0 * xi
+        } else {
+            let sqr_term: f32 = (1.0 - self.g * self.g) / (1.0 - self.g + 2.0 * self.g * xi);
+            (1.0 + self.g * self.g - sqr_term * sqr_term) / (2.0 * self.g)
+        };
+
+        around(direction, cos_theta.clamp(-1.0, 1.0), u_phi)
+    }
+}
+
+/// Rayleigh phase function, for scattering by particles much smaller than the
+/// wavelength such as the molecules of a clear sky.
+#[derive(Clone, Copy, Default)]
+pub struct Rayleigh;
+
+impl PhaseFunction for Rayleigh {
+    fn evaluate(&self, cos_theta: f32) -> f32 {
+        3.0 / (16.0 * PI) * (1.0 + cos_theta * cos_theta)
+    }
+
+    fn sample(&self, direction: Vec3, sampler: &mut dyn Sampler) -> Vec3 {
+        // Invert the CDF `(cos³θ + 3cosθ + 4) / 8` with Cardano's formula.
+        let [xi, u_phi]: [f32; 2] = sampler.get_2d();
+        let q: f32 = 4.0 * xi - 2.0;
+        let a: f32 = (q + (q * q + 1.0).sqrt()).cbrt();
+        let cos_theta: f32 = a - 1.0 / a;
+
+        around(direction, cos_theta.clamp(-1.0, 1.0), u_phi)
+    }
+}
+
+/// A unit vector at the angle with cosine `cos_theta` from `direction`, at the
+/// azimuth `u_phi` of the way round.
+fn around(direction: Vec3, cos_theta: f32, u_phi: f32) -> Vec3 {
+    let w: Vec3 = direction.unit_vector();
+    let a: Vec3 = match 0.9 < w.x().abs() {
+        true => Vec3::new(0.0, 1.0, 0.0),
+        false => Vec3::new(1.0, 0.0, 0.0),
+    };
+    let v: Vec3 = w.cross(a).unit_vector();
+    let u: Vec3 = w.cross(v);
+
+    let sin_theta: f32 = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi: f32 = 2.0 * PI * u_phi;
+
+    sin_theta * phi.cos() * u + sin_theta * phi.sin() * v + cos_theta * w
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::Independent;
+
+    /// Integrate `phase` over the sphere with the midpoint rule in cosθ.
+    fn integrate(phase: &dyn PhaseFunction) -> f32 {
+        const STEPS: usize = 10_000;
+        (0..STEPS)
+            .map(|i| {
+                let cos_theta: f32 = -1.0 + (i as f32 + 0.5) * 2.0 / STEPS as f32;
+                phase.evaluate(cos_theta) * 2.0 * PI * 2.0 / STEPS as f32
+            })
+            .sum()
+    }
+
+    /// Monte Carlo estimate of the mean cosine of sampled directions.
+    fn mean_cosine(phase: &dyn PhaseFunction) -> f32 {
+        const SAMPLES: usize = 100_000;
+        let direction: Vec3 = Vec3::new(0.3, -0.5, 0.8);
+        (0..SAMPLES)
+            .map(|_| {
+                phase
+                    .sample(direction, &mut Independent::new())
+                    .dot(direction.unit_vector())
+            })
+            .sum::<f32>()
+            / SAMPLES as f32
+    }
+
+    #[test]
+    fn phase_functions_should_be_normalized() {
+        for phase in [
+            &IsotropicPhase as &dyn PhaseFunction,
+            &HenyeyGreenstein::new(0.0),
+            &HenyeyGreenstein::new(0.7),
+            &HenyeyGreenstein::new(-0.4),
+            &Rayleigh,
+        ] {
+            assert!((integrate(phase) - 1.0).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn sampling_should_match_evaluation() {
+        assert!(mean_cosine(&IsotropicPhase).abs() < 0.02);
+        assert!(mean_cosine(&Rayleigh).abs() < 0.02);
+        for g in [0.8, 0.3, -0.6] {
+            assert!((mean_cosine(&HenyeyGreenstein::new(g)) - g).abs() < 0.02);
+            let sampled: Vec3 =
+                HenyeyGreenstein::new(g).sample(Vec3::new(0.0, 0.0, 2.0), &mut Independent::new());
+            assert!((sampled.len() - 1.0).abs() < 1e-4);
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use in_one_weekend::color::ColorRGBMapTo0_1;
+
+/// Brightness below which the error of a pixel counts in absolute terms
+/// rather than relative to the pixel, so that near black pixels, where noise
+/// does not show, do not take every sample there is.
+const DARK: f32 = 1.0 / 256.0;
+
+/// The running mean and variance of the samples of a pixel, by Welford's
+/// method.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PixelEstimate {
+    count: usize,
+    mean: [f32; 3],
+    squared_deviations: [f32; 3],
+}
+
+impl PixelEstimate {
+    pub fn add(&mut self, sample: ColorRGBMapTo0_1) {
+        self.count += 1;
+        let n: f32 = self.count as f32;
+        [sample.r(), sample.g(), sample.b()]
+            .into_iter()
+            .enumerate()
+            .for_each(|(channel, value)| {
+                let delta: f32 = value - self.mean[channel];
+                self.mean[channel] += delta / n;
+                self.squared_deviations[channel] += delta * (value - self.mean[channel]);
+            });
+    }
+
+    /// The number of samples taken.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn mean(&self) -> ColorRGBMapTo0_1 {
+        let [r, g, b]: [f32; 3] = self.mean;
+        ColorRGBMapTo0_1::new(r, g, b)
+    }
+
+    /// The sample variance of each channel.
+    pub fn variance(&self) -> [f32; 3] {
+        match self.count {
+            0 | 1 => [0.0; 3],
+            n => self.squared_deviations.map(|m2| m2 / (n - 1) as f32),
+        }
+    }
+
+    /// The size of `to_bytes`.
+    pub(crate) const BYTES: usize = 8 + 6 * 4;
+
+    /// The estimate in little-endian bytes, for checkpoints and the network.
+    pub(crate) fn to_bytes(self) -> [u8; Self::BYTES] {
+        let mut bytes: [u8; Self::BYTES] = [0; Self::BYTES];
+        bytes[..8].copy_from_slice(&(self.count as u64).to_le_bytes());
+        self.mean
+            .iter()
+            .chain(&self.squared_deviations)
+            .enumerate()
+            .for_each(|(i, value)| {
+                bytes[8 + 4 * i..12 + 4 * i].copy_from_slice(&value.to_le_bytes())
+            });
+
+        bytes
+    }
+
+    pub(crate) fn from_bytes(bytes: &[u8; Self::BYTES]) -> Self {
+        let value = |i: usize| f32::from_le_bytes(bytes[8 + 4 * i..12 + 4 * i].try_into().unwrap());
+        Self {
+            count: u64::from_le_bytes(bytes[..8].try_into().unwrap()) as usize,
+            mean: [value(0), value(1), value(2)],
+            squared_deviations: [value(3), value(4), value(5)],
+        }
+    }
+
+    /// The standard error of the mean relative to the mean, of the worst
+    /// channel; infinite before there are two samples to tell it from.
+    pub fn relative_error(&self) -> f32 {
+        if self.count < 2 {
+            return f32::INFINITY;
+        }
+
+        let n: f32 = self.count as f32;
+        self.variance()
+            .into_iter()
+            .zip(self.mean)
+            .map(|(variance, mean)| (variance / n).sqrt() / mean.max(DARK))
+            .fold(0.0, f32::max)
+    }
+}
+
+/// How many samples each pixel takes.
+///
+/// An adaptive budget takes a base pass of samples in every pixel, then keeps
+/// doubling the samples of the pixels whose relative error is still above a
+/// threshold, up to a maximum. Smooth and empty parts of the image stop after
+/// the base pass and the samples go where the noise is. Doubling keeps the
+/// counts at powers of two as long as the base is one, where `Sobol` samples
+/// are spread best.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SampleBudget {
+    base: usize,
+    max: usize,
+    threshold: f32,
+}
+
+impl SampleBudget {
+    /// `samples` in every pixel.
+    pub fn uniform(samples: usize) -> Self {
+        Self::adaptive(samples, samples, 0.0)
+    }
+
+    /// `base` samples in every pixel and up to `max` in those whose relative
+    /// error is above `threshold`.
+    pub fn adaptive(base: usize, max: usize, threshold: f32) -> Self {
+        assert!(0 < base && base <= max, "need 0 < base <= max samples");
+        Self {
+            base,
+            max,
+            threshold,
+        }
+    }
+
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Whether a pixel with `estimate` has taken all the samples it needs.
+    pub fn is_converged(&self, estimate: &PixelEstimate) -> bool {
+        let count: usize = estimate.count();
+        self.max <= count || (self.base <= count && estimate.relative_error() <= self.threshold)
+    }
+
+    /// Add up to `samples` more samples to `estimate` from `sample`, which
+    /// takes the sample index, unless it has converged; the base pass counts
+    /// as unconverged.
+    pub fn extend(
+        &self,
+        estimate: &mut PixelEstimate,
+        samples: usize,
+        mut sample: impl FnMut(usize) -> ColorRGBMapTo0_1,
+    ) {
+        if self.is_converged(estimate) {
+            return;
+        }
+
+        let count: usize = estimate.count();
+        (count..(count + samples).min(self.max)).for_each(|index| estimate.add(sample(index)));
+    }
+
+    /// Estimate a pixel from `sample`, which takes the sample index.
+    pub fn sample(&self, mut sample: impl FnMut(usize) -> ColorRGBMapTo0_1) -> PixelEstimate {
+        let mut estimate: PixelEstimate = PixelEstimate::default();
+        self.extend(&mut estimate, self.base, &mut sample);
+        while !self.is_converged(&estimate) {
+            let count: usize = estimate.count();
+            self.extend(&mut estimate, count, &mut sample);
+        }
+
+        estimate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adaptive_budget_should_spend_samples_on_noisy_pixels() {
+        let budget: SampleBudget = SampleBudget::adaptive(16, 1024, 0.01);
+        let gray = |_| ColorRGBMapTo0_1::new(0.5, 0.5, 0.5);
+        assert_eq!(16, budget.sample(gray).count());
+        let black = |_| ColorRGBMapTo0_1::new(0.0, 0.0, 0.0);
+        assert_eq!(16, budget.sample(black).count());
+
+        // A firefly every 64 samples.
+        let sparkle = |index: usize| match index % 64 {
+            0 => ColorRGBMapTo0_1::new(50.0, 50.0, 50.0),
+            _ => ColorRGBMapTo0_1::new(0.1, 0.1, 0.1),
+        };
+        let estimate: PixelEstimate = budget.sample(sparkle);
+        assert_eq!(1024, estimate.count());
+        assert!((estimate.mean().r() - (50.0 + 63.0 * 0.1) / 64.0).abs() < 1e-3);
+
+        let uniform: SampleBudget = SampleBudget::uniform(100);
+        assert_eq!(100, uniform.sample(sparkle).count());
+        assert_eq!(100, uniform.sample(gray).count());
+    }
+
+    #[test]
+    fn pixel_estimate_should_match_the_sample_variance() {
+        let mut estimate: PixelEstimate = PixelEstimate::default();
+        [1.0, 2.0, 4.0, 7.0]
+            .into_iter()
+            .for_each(|v| estimate.add(ColorRGBMapTo0_1::new(v, 2.0 * v, 0.0)));
+
+        assert_eq!(3.5, estimate.mean().r());
+        let [r, g, b]: [f32; 3] = estimate.variance();
+        assert!((r - 7.0).abs() < 1e-5 && (g - 28.0).abs() < 1e-4 && b == 0.0);
+        assert!((estimate.relative_error() - (7.0_f32 / 4.0).sqrt() / 3.5).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use in_one_weekend::color::ColorRGBMapTo0_1;
+
+use crate::sampler::{self, Sampler};
+
+use super::{Framebuffer, PixelEstimate};
+
+/// The first bytes of a checkpoint file, with the version of its layout.
+const MAGIC: &[u8; 8] = b"RTCKPT01";
+
+/// Pixels along each side of the grid sampled for the fingerprint.
+const PROBE_GRID: usize = 8;
+
+/// What identifies a render, to save its framebuffer to a file and pick it up
+/// again after the program was stopped.
+///
+/// The random numbers of each pixel follow from the seed and the number of
+/// samples the pixel has taken, so the seed and the framebuffer are all the
+/// state there is to save. The fingerprint hashes a few samples of the render
+/// itself, which changes with the scene, camera, image size, sampler or seed,
+/// so that a checkpoint is not resumed into a different render.
+///
+/// The file holds, little-endian: the magic bytes, the width and height, the
+/// fingerprint and the seed as `u64`, then every pixel estimate row by row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Checkpoint<const WIDTH: usize, const HEIGHT: usize> {
+    fingerprint: u64,
+    seed: u64,
+}
+
+impl<const WIDTH: usize, const HEIGHT: usize> Checkpoint<WIDTH, HEIGHT> {
+    /// The checkpoint of rendering samples of `integrator`, as `Progressive`
+    /// takes, with `sampler` seeded by `seed`.
+    pub fn new<S, I>(seed: u64, sampler: S, integrator: &I) -> Self
+    where
+        S: Sampler,
+        I: Fn(usize, usize, &mut dyn Sampler) -> ColorRGBMapTo0_1,
+    {
+        let mut sampler: S = sampler;
+        let mut fingerprint: u64 = sampler::hash(&[WIDTH as u64, HEIGHT as u64, seed]);
+        (0..PROBE_GRID * PROBE_GRID).for_each(|probe| {
+            let row: usize = (2 * (probe / PROBE_GRID) + 1) * HEIGHT / (2 * PROBE_GRID);
+            let column: usize = (2 * (probe % PROBE_GRID) + 1) * WIDTH / (2 * PROBE_GRID);
+            sampler.start_pixel_sample(column, row, 0);
+            let color: ColorRGBMapTo0_1 = integrator(row, column, &mut sampler);
+            fingerprint = sampler::hash(&[
+                fingerprint,
+                color.r().to_bits() as u64,
+                color.g().to_bits() as u64,
+                color.b().to_bits() as u64,
+            ]);
+        });
+
+        Self { fingerprint, seed }
+    }
+
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    /// Write `framebuffer` next to `path` and move it over `path` when
+    /// complete, so that a crash while saving keeps the previous checkpoint.
+    pub fn save(
+        &self,
+        framebuffer: &Framebuffer<WIDTH, HEIGHT>,
+        path: impl AsRef<Path>,
+    ) -> io::Result<()> {
+        let mut partial: OsString = path.as_ref().as_os_str().to_owned();
+        partial.push(".partial");
+        let partial: PathBuf = partial.into();
+
+        let mut file: BufWriter<File> = BufWriter::new(File::create(&partial)?);
+        file.write_all(MAGIC)?;
+        for value in [WIDTH as u64, HEIGHT as u64, self.fingerprint, self.seed] {
+            file.write_all(&value.to_le_bytes())?;
+        }
+        for estimate in framebuffer.pixels() {
+            file.write_all(&estimate.to_bytes())?;
+        }
+        file.into_inner()?.sync_all()?;
+
+        fs::rename(partial, path)
+    }
+
+    /// The framebuffer saved at `path`, if it was saved by this checkpoint.
+    pub fn load(&self, path: impl AsRef<Path>) -> io::Result<Framebuffer<WIDTH, HEIGHT>> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut file: BufReader<File> = BufReader::new(File::open(path)?);
+
+        let mut magic: [u8; 8] = [0; 8];
+        file.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a checkpoint, or of another version"));
+        }
+
+        let mut header: [u64; 4] = [0; 4];
+        for value in header.iter_mut() {
+            let mut bytes: [u8; 8] = [0; 8];
+            file.read_exact(&mut bytes)?;
+            *value = u64::from_le_bytes(bytes);
+        }
+        let [width, height, fingerprint, seed]: [u64; 4] = header;
+        if (width, height) != (WIDTH as u64, HEIGHT as u64) {
+            return Err(invalid("the checkpoint is of an image of another size"));
+        }
+        if seed != self.seed {
+            return Err(invalid("the checkpoint is of a render with another seed"));
+        }
+        if fingerprint != self.fingerprint {
+            return Err(invalid("the checkpoint is of another scene"));
+        }
+
+        let mut framebuffer: Framebuffer<WIDTH, HEIGHT> = Framebuffer::new();
+        for row in 0..HEIGHT {
+            for estimate in framebuffer.row_mut(row) {
+                let mut bytes: [u8; PixelEstimate::BYTES] = [0; PixelEstimate::BYTES];
+                file.read_exact(&mut bytes)?;
+                *estimate = PixelEstimate::from_bytes(&bytes);
+            }
+        }
+        if file.read(&mut [0])? != 0 {
+            return Err(invalid("the checkpoint has bytes past the last pixel"));
+        }
+
+        Ok(framebuffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use in_one_weekend::thread_pool::ThreadPool;
+
+    use super::*;
+    use crate::{
+        render::{Progressive, SampleBudget},
+        sampler::Sobol,
+    };
+
+    fn integrator(row: usize, column: usize, sampler: &mut dyn Sampler) -> ColorRGBMapTo0_1 {
+        let [x, y]: [f32; 2] = sampler.get_2d();
+        let value: f32 = (row + column) as f32 * x * y;
+        ColorRGBMapTo0_1::new(value, 0.5 * value, 1.0)
+    }
+
+    #[test]
+    fn resumed_render_should_match_an_uninterrupted_one() {
+        // Of this process, for test runs side by side not to share the file.
+        let path: PathBuf = std::env::temp_dir().join(format!(
+            "the_next_week_checkpoint_test_{}.bin",
+            std::process::id()
+        ));
+        let budget: SampleBudget = SampleBudget::uniform(24);
+        let sampler: Sobol = Sobol::new().set_seed(5);
+        let thread_pool: ThreadPool = ThreadPool::new(2);
+        let checkpoint: Checkpoint<6, 4> = Checkpoint::new(5, sampler, &integrator);
+
+        let mut uninterrupted: Progressive<6, 4> = Progressive::new(budget, 8);
+        (0..3).for_each(|_| uninterrupted.pass(&thread_pool, sampler, &integrator));
+
+        let mut interrupted: Progressive<6, 4> = Progressive::new(budget, 8);
+        interrupted.pass(&thread_pool, sampler, &integrator);
+        checkpoint.save(interrupted.framebuffer(), &path).unwrap();
+        drop(interrupted);
+
+        let framebuffer: Framebuffer<6, 4> = checkpoint.load(&path).unwrap();
+        let mut resumed: Progressive<6, 4> = Progressive::with_framebuffer(budget, 8, framebuffer);
+        (0..2).for_each(|_| resumed.pass(&thread_pool, sampler, &integrator));
+        assert_eq!(uninterrupted.framebuffer(), resumed.framebuffer());
+
+        // Another seed or scene is refused.
+        let reseeded: Checkpoint<6, 4> = Checkpoint::new(6, Sobol::new().set_seed(6), &integrator);
+        assert!(reseeded.load(&path).is_err());
+        let dimmer = |row: usize, column: usize, sampler: &mut dyn Sampler| {
+            integrator(row, column, sampler) * 0.5
+        };
+        let changed: Checkpoint<6, 4> = Checkpoint::new(5, sampler, &dimmer);
+        assert_ne!(checkpoint.fingerprint(), changed.fingerprint());
+        assert_eq!(
+            io::ErrorKind::InvalidData,
+            changed.load(&path).unwrap_err().kind()
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,103 @@
+use std::{
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use in_one_weekend::{
+    color::ColorRGBMapTo0_1,
+    image::{PPMImg, PPMImgMagicNum},
+};
+
+use super::PixelEstimate;
+
+/// The running estimate of every pixel of an image, in linear floating point,
+/// so that samples can keep being added to it and it can be written out at
+/// any point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Framebuffer<const WIDTH: usize, const HEIGHT: usize> {
+    pixels: Vec<PixelEstimate>,
+}
+
+impl<const WIDTH: usize, const HEIGHT: usize> Framebuffer<WIDTH, HEIGHT> {
+    pub fn new() -> Self {
+        Self {
+            pixels: vec![PixelEstimate::default(); WIDTH * HEIGHT],
+        }
+    }
+
+    pub fn pixel(&self, row: usize, column: usize) -> &PixelEstimate {
+        &self.pixels[row * WIDTH + column]
+    }
+
+    pub fn pixel_mut(&mut self, row: usize, column: usize) -> &mut PixelEstimate {
+        &mut self.pixels[row * WIDTH + column]
+    }
+
+    pub fn row(&self, row: usize) -> &[PixelEstimate] {
+        &self.pixels[row * WIDTH..(row + 1) * WIDTH]
+    }
+
+    pub fn row_mut(&mut self, row: usize) -> &mut [PixelEstimate] {
+        &mut self.pixels[row * WIDTH..(row + 1) * WIDTH]
+    }
+
+    pub fn pixels(&self) -> impl Iterator<Item = &PixelEstimate> {
+        self.pixels.iter()
+    }
+
+    /// The image so far, scaled by `exposure` and gamma-corrected for
+    /// gamma=2.0.
+    pub fn to_image(&self, exposure: f32) -> PPMImg<WIDTH, HEIGHT> {
+        self.map_to_image(|estimate| {
+            let mean: ColorRGBMapTo0_1 = estimate.mean() * exposure;
+            ColorRGBMapTo0_1::new(mean.r().sqrt(), mean.g().sqrt(), mean.b().sqrt())
+        })
+    }
+
+    /// How many samples each pixel has taken, from black for none to white
+    /// for `max`.
+    pub fn to_sample_count_image(&self, max: usize) -> PPMImg<WIDTH, HEIGHT> {
+        self.map_to_image(|estimate| {
+            let share: f32 = estimate.count() as f32 / max as f32;
+            ColorRGBMapTo0_1::new(share, share, share)
+        })
+    }
+
+    fn map_to_image(
+        &self,
+        color: impl Fn(&PixelEstimate) -> ColorRGBMapTo0_1,
+    ) -> PPMImg<WIDTH, HEIGHT> {
+        let mut image = PPMImg::<WIDTH, HEIGHT>::new(PPMImgMagicNum::P3);
+        (0..HEIGHT).for_each(|row| {
+            self.row(row)
+                .iter()
+                .enumerate()
+                .for_each(|(column, estimate)| {
+                    image.set_pixel_color(row, column, color(estimate).into())
+                })
+        });
+
+        image
+    }
+}
+
+impl<const WIDTH: usize, const HEIGHT: usize> Default for Framebuffer<WIDTH, HEIGHT> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Write `image` next to `path` and move it over `path` when complete, so that
+/// `path` always holds a whole image even if the program stops while writing.
+pub fn write_image<const WIDTH: usize, const HEIGHT: usize>(
+    image: &PPMImg<WIDTH, HEIGHT>,
+    path: impl AsRef<Path>,
+) -> io::Result<()> {
+    let mut partial: OsString = path.as_ref().as_os_str().to_owned();
+    partial.push(".partial");
+    let partial: PathBuf = partial.into();
+
+    image.write_to_file(&partial)?;
+    fs::rename(partial, path)
+}
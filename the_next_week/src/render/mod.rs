@@ -0,0 +1,13 @@
+mod adaptive;
+mod checkpoint;
+mod framebuffer;
+mod progressive;
+mod tile;
+
+pub use self::{
+    adaptive::{PixelEstimate, SampleBudget},
+    checkpoint::Checkpoint,
+    framebuffer::{write_image, Framebuffer},
+    progressive::Progressive,
+    tile::{Tile, TileOrder, TileScheduler},
+};
@@ -0,0 +1,174 @@
+use in_one_weekend::{
+    color::ColorRGBMapTo0_1,
+    thread_pool::{CancellationToken, JoinHandle, ThreadPool},
+};
+
+use crate::sampler::Sampler;
+
+use super::{Framebuffer, PixelEstimate, SampleBudget, Tile, TileScheduler};
+
+/// Renders an image in passes over the whole frame, each adding a few more
+/// samples to every pixel that has not converged yet, so that there is a
+/// complete, if noisy, image after every pass.
+///
+/// Each pixel takes its samples in order of index whichever pass they fall
+/// in, so a pixel comes out the same for the same number of samples however
+/// they are split into passes.
+pub struct Progressive<const WIDTH: usize, const HEIGHT: usize> {
+    framebuffer: Framebuffer<WIDTH, HEIGHT>,
+    budget: SampleBudget,
+    samples_per_pass: usize,
+    scheduler: TileScheduler,
+}
+
+impl<const WIDTH: usize, const HEIGHT: usize> Progressive<WIDTH, HEIGHT> {
+    pub fn new(budget: SampleBudget, samples_per_pass: usize) -> Self {
+        Self::with_framebuffer(budget, samples_per_pass, Framebuffer::new())
+    }
+
+    /// Carry on adding samples to `framebuffer`, as loaded from a `Checkpoint`.
+    pub fn with_framebuffer(
+        budget: SampleBudget,
+        samples_per_pass: usize,
+        framebuffer: Framebuffer<WIDTH, HEIGHT>,
+    ) -> Self {
+        assert!(0 < samples_per_pass, "a pass needs samples");
+        Self {
+            framebuffer,
+            budget,
+            samples_per_pass,
+            scheduler: TileScheduler::default(),
+        }
+    }
+
+    /// How the frame is split into jobs, default tiles of 16 by 16 pixels
+    /// spiralling out from the middle.
+    pub fn set_scheduler(mut self, scheduler: TileScheduler) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+
+    pub fn framebuffer(&self) -> &Framebuffer<WIDTH, HEIGHT> {
+        &self.framebuffer
+    }
+
+    /// Whether every pixel has taken all the samples it needs.
+    pub fn is_done(&self) -> bool {
+        self.framebuffer
+            .pixels()
+            .all(|estimate| self.budget.is_converged(estimate))
+    }
+
+    /// Render one pass on `thread_pool`, a job per tile, and wait for it.
+    ///
+    /// `integrator` gives one sample of the pixel at a row and column, from a
+    /// copy of `sampler` already started on it. The tiles skipped after the
+    /// cancellation of the scheduler keep the samples they had, so that the
+    /// framebuffer can still be checkpointed and resumed.
+    pub fn pass<S, I>(&mut self, thread_pool: &ThreadPool, sampler: S, integrator: &I)
+    where
+        S: Sampler + Clone + Send,
+        I: Fn(usize, usize, &mut dyn Sampler) -> ColorRGBMapTo0_1 + Sync,
+    {
+        let (budget, samples): (SampleBudget, usize) = (self.budget, self.samples_per_pass);
+        let framebuffer: &mut Framebuffer<WIDTH, HEIGHT> = &mut self.framebuffer;
+        let cancellation: &CancellationToken = self.scheduler.cancellation();
+
+        thread_pool.scope(|scope| {
+            let tiles: Vec<(Tile, JoinHandle<Vec<PixelEstimate>>)> = self
+                .scheduler
+                .tiles::<WIDTH, HEIGHT>()
+                .into_iter()
+                .map(|tile| {
+                    let mut estimates: Vec<PixelEstimate> = tile
+                        .pixels()
+                        .map(|(row, column)| *framebuffer.pixel(row, column))
+                        .collect();
+                    let mut sampler: S = sampler.clone();
+
+                    let estimates = scope.spawn(move || {
+                        if cancellation.is_cancelled() {
+                            return estimates;
+                        }
+
+                        tile.pixels().zip(estimates.iter_mut()).for_each(
+                            |((row, column), estimate)| {
+                                budget.extend(estimate, samples, |index| {
+                                    sampler.start_pixel_sample(column, row, index);
+                                    integrator(row, column, &mut sampler)
+                                })
+                            },
+                        );
+
+                        estimates
+                    });
+                    (tile, estimates)
+                })
+                .collect();
+
+            for (tile, estimates) in tiles {
+                tile.pixels()
+                    .zip(estimates.join().unwrap_or_else(|panic| panic!("{panic}")))
+                    .for_each(|((row, column), estimate)| {
+                        *framebuffer.pixel_mut(row, column) = estimate
+                    });
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{render::TileOrder, sampler::Sobol};
+
+    fn integrator(row: usize, column: usize, sampler: &mut dyn Sampler) -> ColorRGBMapTo0_1 {
+        let [x, y]: [f32; 2] = sampler.get_2d();
+        let value: f32 = match column {
+            0 => 0.5,
+            _ => (column * row) as f32 * x * y,
+        };
+        ColorRGBMapTo0_1::new(value, value, value)
+    }
+
+    fn render(budget: SampleBudget, samples_per_pass: usize) -> (Framebuffer<5, 3>, usize) {
+        let thread_pool: ThreadPool = ThreadPool::new(2);
+        let mut progressive: Progressive<5, 3> = Progressive::new(budget, samples_per_pass)
+            .set_scheduler(TileScheduler::new(2, TileOrder::Hilbert));
+        let mut passes: usize = 0;
+        while !progressive.is_done() {
+            progressive.pass(&thread_pool, Sobol::new().set_seed(3), &integrator);
+            passes += 1;
+        }
+
+        (progressive.framebuffer, passes)
+    }
+
+    #[test]
+    fn passes_should_add_up_to_a_single_render() {
+        let budget: SampleBudget = SampleBudget::uniform(20);
+        let (framebuffer, passes) = render(budget, 6);
+        assert_eq!(4, passes);
+
+        (0..3).for_each(|row| {
+            (0..5).for_each(|column| {
+                let mut sampler: Sobol = Sobol::new().set_seed(3);
+                let single: PixelEstimate = budget.sample(|index| {
+                    sampler.start_pixel_sample(column, row, index);
+                    integrator(row, column, &mut sampler)
+                });
+                assert_eq!(&single, framebuffer.pixel(row, column));
+            })
+        });
+    }
+
+    #[test]
+    fn adaptive_passes_should_stop_at_converged_pixels() {
+        let budget: SampleBudget = SampleBudget::adaptive(8, 40, 0.05);
+        let (framebuffer, _) = render(budget, 6);
+
+        assert!(framebuffer.pixels().all(|pixel| budget.is_converged(pixel)));
+        assert_eq!(12, framebuffer.pixel(2, 0).count());
+        assert_eq!(40, framebuffer.pixel(2, 4).count());
+    }
+}
@@ -0,0 +1,317 @@
+use std::{
+    ops::Range,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use in_one_weekend::{
+    color::ColorRGB,
+    image::{PPMImg, PPMImgMagicNum},
+    thread_pool::{CancellationToken, JoinHandle, ThreadPool},
+    utils,
+};
+
+use crate::sampler::Sampler;
+
+/// The order tiles are handed out to the thread pool in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TileOrder {
+    /// Row by row from the top left, like the image is written.
+    Scanline,
+    /// Outwards from the centre, where the subject of a shot usually is, so
+    /// that it shows up first.
+    Spiral,
+    /// Along a Hilbert curve, so that consecutive tiles are neighbours and
+    /// share what they bring into the caches.
+    Hilbert,
+}
+
+/// A rectangle of the image, rendered as one job.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tile {
+    pub row: usize,
+    pub column: usize,
+    pub height: usize,
+    pub width: usize,
+}
+
+impl Tile {
+    pub fn rows(&self) -> Range<usize> {
+        self.row..self.row + self.height
+    }
+
+    pub fn columns(&self) -> Range<usize> {
+        self.column..self.column + self.width
+    }
+
+    pub fn len(&self) -> usize {
+        self.height * self.width
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The row and column of every pixel of the tile, row by row.
+    pub fn pixels(&self) -> impl Iterator<Item = (usize, usize)> {
+        let columns: Range<usize> = self.columns();
+        self.rows()
+            .flat_map(move |row| columns.clone().map(move |column| (row, column)))
+    }
+}
+
+/// Splits an image into square tiles and renders them as jobs on a thread
+/// pool, a job per tile.
+///
+/// A job renders its tile into a buffer of its own and returns it to be
+/// copied into the image, so the workers share nothing but an atomic count of
+/// the pixels done.
+#[derive(Clone, Debug)]
+pub struct TileScheduler {
+    size: usize,
+    order: TileOrder,
+    cancellation: CancellationToken,
+}
+
+impl TileScheduler {
+    pub fn new(size: usize, order: TileOrder) -> Self {
+        assert!(0 < size, "a tile needs pixels");
+        Self {
+            size,
+            order,
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    /// Once `cancellation` is cancelled, the tiles that have not started yet
+    /// are skipped, and the ones being rendered are finished.
+    pub fn set_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    /// The tiles of a `WIDTH` by `HEIGHT` image in the order of the scheduler.
+    /// The tiles on the right and bottom edges are cut to fit.
+    pub fn tiles<const WIDTH: usize, const HEIGHT: usize>(&self) -> Vec<Tile> {
+        let columns: usize = WIDTH.div_ceil(self.size);
+        let rows: usize = HEIGHT.div_ceil(self.size);
+        let tile = |(row, column): (usize, usize)| {
+            let (row, column): (usize, usize) = (row * self.size, column * self.size);
+            Tile {
+                row,
+                column,
+                height: self.size.min(HEIGHT - row),
+                width: self.size.min(WIDTH - column),
+            }
+        };
+
+        match self.order {
+            TileOrder::Scanline => (0..rows)
+                .flat_map(|row| (0..columns).map(move |column| (row, column)))
+                .map(tile)
+                .collect(),
+            TileOrder::Spiral => spiral(rows, columns).map(tile).collect(),
+            TileOrder::Hilbert => hilbert(rows, columns).map(tile).collect(),
+        }
+    }
+
+    /// Render a `WIDTH` by `HEIGHT` image on `thread_pool` and wait for it,
+    /// logging the progress as tiles complete.
+    ///
+    /// `pixel_color` gives the colour of the pixel at a row and column, with a
+    /// copy of `sampler` for the tile. The tiles skipped after a cancellation
+    /// are left black, and a panic in a job is raised again here.
+    pub fn render<const WIDTH: usize, const HEIGHT: usize, S, F>(
+        &self,
+        thread_pool: &ThreadPool,
+        sampler: S,
+        pixel_color: &F,
+    ) -> PPMImg<WIDTH, HEIGHT>
+    where
+        S: Sampler + Clone + Send,
+        F: Fn(usize, usize, &mut dyn Sampler) -> ColorRGB + Sync,
+    {
+        const LOG_STEPS: usize = 1000;
+        let num_pixels: usize = WIDTH * HEIGHT;
+        let num_pixels_has_rendered: AtomicUsize = AtomicUsize::new(0);
+
+        thread_pool.scope(|scope| {
+            let tiles: Vec<(Tile, JoinHandle<Option<Vec<ColorRGB>>>)> = self
+                .tiles::<WIDTH, HEIGHT>()
+                .into_iter()
+                .map(|tile| {
+                    let mut sampler: S = sampler.clone();
+                    let num_pixels_has_rendered: &AtomicUsize = &num_pixels_has_rendered;
+                    let cancellation: &CancellationToken = &self.cancellation;
+
+                    let colors = scope.spawn(move || {
+                        if cancellation.is_cancelled() {
+                            return None;
+                        }
+
+                        let colors: Vec<ColorRGB> = tile
+                            .pixels()
+                            .map(|(row, column)| pixel_color(row, column, &mut sampler))
+                            .collect();
+
+                        let before: usize =
+                            num_pixels_has_rendered.fetch_add(tile.len(), Ordering::Relaxed);
+                        let after: usize = before + tile.len();
+                        if before * LOG_STEPS / num_pixels != after * LOG_STEPS / num_pixels {
+                            utils::log_progress(after as f64 / num_pixels as f64).unwrap();
+                        }
+
+                        Some(colors)
+                    });
+                    (tile, colors)
+                })
+                .collect();
+
+            let mut image = PPMImg::<WIDTH, HEIGHT>::new(PPMImgMagicNum::P3);
+            for (tile, colors) in tiles {
+                let colors: Option<Vec<ColorRGB>> =
+                    colors.join().unwrap_or_else(|panic| panic!("{panic}"));
+                tile.pixels()
+                    .zip(colors.into_iter().flatten())
+                    .for_each(|((row, column), color)| image.set_pixel_color(row, column, color));
+            }
+
+            image
+        })
+    }
+}
+
+impl Default for TileScheduler {
+    fn default() -> Self {
+        Self::new(16, TileOrder::Spiral)
+    }
+}
+
+/// The cells of a `rows` by `columns` grid, spiralling out from the middle.
+fn spiral(rows: usize, columns: usize) -> impl Iterator<Item = (usize, usize)> {
+    // Right, down, left, up, with legs of 1, 1, 2, 2, 3, 3, … steps.
+    const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+    let legs = (1..).flat_map(|length: usize| [length, length]);
+    let steps = DIRECTIONS
+        .into_iter()
+        .cycle()
+        .zip(legs)
+        .flat_map(|(direction, length)| std::iter::repeat_n(direction, length));
+
+    let start: (isize, isize) = ((rows as isize - 1) / 2, (columns as isize - 1) / 2);
+    std::iter::once((0, 0))
+        .chain(steps)
+        .scan(start, |(row, column), (d_row, d_column)| {
+            (*row, *column) = (*row + d_row, *column + d_column);
+            Some((*row, *column))
+        })
+        .filter(move |&(row, column)| {
+            (0..rows as isize).contains(&row) && (0..columns as isize).contains(&column)
+        })
+        .map(|(row, column)| (row as usize, column as usize))
+        .take(rows * columns)
+}
+
+/// The cells of a `rows` by `columns` grid along the Hilbert curve over the
+/// smallest power of two square that holds it, skipping the cells outside.
+fn hilbert(rows: usize, columns: usize) -> impl Iterator<Item = (usize, usize)> {
+    let side: usize = rows.max(columns).next_power_of_two();
+    (0..side * side)
+        .map(move |index| {
+            // Undo the curve one level at a time, from the finest quadrants up.
+            let (mut row, mut column, mut rest): (usize, usize, usize) = (0, 0, index);
+            let mut scale: usize = 1;
+            while scale < side {
+                let right: usize = 1 & (rest / 2);
+                let down: usize = 1 & (rest ^ right);
+                if down == 0 {
+                    if right == 1 {
+                        row = scale - 1 - row;
+                        column = scale - 1 - column;
+                    }
+                    (row, column) = (column, row);
+                }
+                column += scale * right;
+                row += scale * down;
+                rest /= 4;
+                scale *= 2;
+            }
+            (row, column)
+        })
+        .filter(move |&(row, column)| row < rows && column < columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::Independent;
+
+    const ORDERS: [TileOrder; 3] = [TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert];
+
+    #[test]
+    fn tiles_should_cover_every_pixel_once() {
+        for order in ORDERS {
+            let scheduler: TileScheduler = TileScheduler::new(4, order);
+            let mut covered: Vec<usize> = vec![0; 13 * 10];
+            scheduler
+                .tiles::<13, 10>()
+                .iter()
+                .flat_map(Tile::pixels)
+                .for_each(|(row, column)| covered[row * 13 + column] += 1);
+            assert!(covered.iter().all(|&count| count == 1), "{order:?}");
+        }
+    }
+
+    #[test]
+    fn orders_should_start_and_step_where_expected() {
+        let spiral: Vec<Tile> = TileScheduler::new(10, TileOrder::Spiral).tiles::<50, 30>();
+        assert_eq!((10, 20), (spiral[0].row, spiral[0].column));
+
+        // Each tile along the Hilbert curve is next to the one before.
+        let hilbert: Vec<Tile> = TileScheduler::new(1, TileOrder::Hilbert).tiles::<8, 8>();
+        assert_eq!(64, hilbert.len());
+        hilbert.windows(2).for_each(|pair| {
+            let distance: usize =
+                pair[0].row.abs_diff(pair[1].row) + pair[0].column.abs_diff(pair[1].column);
+            assert_eq!(1, distance);
+        });
+    }
+
+    #[test]
+    fn render_should_color_every_pixel_once() {
+        let thread_pool: ThreadPool = ThreadPool::new(3);
+        let calls: Vec<AtomicUsize> = (0..7 * 5).map(|_| AtomicUsize::new(0)).collect();
+        let pixel_color = |row: usize, column: usize, _: &mut dyn Sampler| {
+            calls[row * 7 + column].fetch_add(1, Ordering::Relaxed);
+            ColorRGB::new(row as u8, column as u8, 0)
+        };
+
+        for order in ORDERS {
+            let _: PPMImg<7, 5> =
+                TileScheduler::new(3, order).render(&thread_pool, Independent::new(), &pixel_color);
+        }
+        assert!(calls
+            .iter()
+            .all(|count| count.load(Ordering::Relaxed) == ORDERS.len()));
+    }
+
+    #[test]
+    fn render_should_skip_tiles_once_cancelled() {
+        let thread_pool: ThreadPool = ThreadPool::new(2);
+        let cancellation: CancellationToken = CancellationToken::new();
+        let calls: AtomicUsize = AtomicUsize::new(0);
+        let pixel_color = |_: usize, _: usize, _: &mut dyn Sampler| {
+            calls.fetch_add(1, Ordering::Relaxed);
+            ColorRGB::new(255, 255, 255)
+        };
+
+        cancellation.cancel();
+        let _: PPMImg<6, 4> = TileScheduler::new(2, TileOrder::Scanline)
+            .set_cancellation(cancellation)
+            .render(&thread_pool, Independent::new(), &pixel_color);
+        assert_eq!(0, calls.load(Ordering::Relaxed));
+    }
+}
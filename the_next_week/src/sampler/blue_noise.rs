@@ -0,0 +1,222 @@
+use std::sync::OnceLock;
+
+use rand::{rngs::StdRng, seq::index, SeedableRng};
+
+use super::{hash, Sampler, Sobol, ONE_MINUS_EPSILON};
+
+/// Width and height of the tiled blue noise mask.
+const MASK_SIZE: usize = 64;
+
+/// Standard deviation, in pixels, of the filter measuring how clustered the
+/// points of the mask are.
+const MASK_SIGMA: f32 = 1.5;
+
+/// The same scrambled Sobol samples in every pixel, each shifted by a blue
+/// noise mask, after Heitz and Belcour's "Distributing Monte Carlo Errors as
+/// a Blue Noise in Screen Space".
+///
+/// Neighbouring pixels get shifts far apart, so their errors differ in a way
+/// the eye averages out: at low sample counts the noise looks finer than that
+/// of samplers scrambling each pixel on its own, for the same error per pixel.
+#[derive(Clone, Copy, Debug)]
+pub struct BlueNoise {
+    sobol: Sobol,
+    column: usize,
+    row: usize,
+}
+
+impl BlueNoise {
+    pub fn new() -> Self {
+        Self {
+            sobol: Sobol::new(),
+            column: 0,
+            row: 0,
+        }
+    }
+
+    /// Draw the offset of the mask for each draw from `seed`.
+    pub fn set_seed(self, seed: u64) -> Self {
+        Self {
+            sobol: self.sobol.set_seed(seed),
+            ..self
+        }
+    }
+
+    /// The shift of the pixel for a draw, from the mask moved by a different
+    /// offset for each draw.
+    fn shift(&self, draw: usize, dimension: usize) -> f32 {
+        let offset: u64 = hash(&[self.sobol.seed(), draw as u64, dimension as u64]);
+        let x: usize = (self.column + offset as usize) % MASK_SIZE;
+        let y: usize = (self.row + (offset >> 32) as usize) % MASK_SIZE;
+
+        blue_noise_mask()[y * MASK_SIZE + x]
+    }
+}
+
+impl Default for BlueNoise {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sampler for BlueNoise {
+    fn start_pixel_sample(&mut self, column: usize, row: usize, index: usize) {
+        (self.column, self.row) = (column, row);
+        self.sobol.start(self.sobol.seed(), index);
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        let ([bits], draw) = self.sobol.next_bits::<1>();
+        rotate(bits, self.shift(draw, 0))
+    }
+
+    fn get_2d(&mut self) -> [f32; 2] {
+        let ([x, y], draw) = self.sobol.next_bits::<2>();
+        [
+            rotate(x, self.shift(draw, 0)),
+            rotate(y, self.shift(draw, 1)),
+        ]
+    }
+}
+
+/// `bits` as a number in `[0, 1)`, moved round by `shift`.
+fn rotate(bits: u32, shift: f32) -> f32 {
+    let sample: f32 = super::to_unit(bits) + shift;
+    match sample < 1.0 {
+        true => sample,
+        false => (sample - 1.0).min(ONE_MINUS_EPSILON),
+    }
+}
+
+/// A `MASK_SIZE` square of the numbers `(i + 0.5) / MASK_SIZE²`, each once,
+/// placed so that any threshold leaves points evenly apart, without clumps
+/// or gaps.
+fn blue_noise_mask() -> &'static [f32] {
+    static MASK: OnceLock<Vec<f32>> = OnceLock::new();
+    MASK.get_or_init(|| {
+        let ranks: Vec<usize> = void_and_cluster();
+        let cells: f32 = (MASK_SIZE * MASK_SIZE) as f32;
+        ranks
+            .iter()
+            .map(|&rank| (rank as f32 + 0.5) / cells)
+            .collect()
+    })
+}
+
+/// The rank of each cell of the mask by Ulichney's void-and-cluster method.
+fn void_and_cluster() -> Vec<usize> {
+    const CELLS: usize = MASK_SIZE * MASK_SIZE;
+
+    // How much a point weighs on each cell, by offset, wrapping around.
+    let filter: Vec<f32> = (0..CELLS)
+        .map(|i| {
+            let wrapped = |d: usize| d.min(MASK_SIZE - d) as f32;
+            let (dx, dy): (f32, f32) = (wrapped(i % MASK_SIZE), wrapped(i / MASK_SIZE));
+            (-(dx * dx + dy * dy) / (2.0 * MASK_SIGMA * MASK_SIGMA)).exp()
+        })
+        .collect();
+
+    struct Pattern {
+        points: Vec<bool>,
+        energy: Vec<f32>,
+    }
+
+    impl Pattern {
+        fn set(&mut self, filter: &[f32], cell: usize, point: bool) {
+            self.points[cell] = point;
+            let sign: f32 = if point { 1.0 } else { -1.0 };
+            let (px, py): (usize, usize) = (cell % MASK_SIZE, cell / MASK_SIZE);
+            for y in 0..MASK_SIZE {
+                let fy: usize = (y + MASK_SIZE - py) % MASK_SIZE;
+                for x in 0..MASK_SIZE {
+                    let fx: usize = (x + MASK_SIZE - px) % MASK_SIZE;
+                    self.energy[y * MASK_SIZE + x] += sign * filter[fy * MASK_SIZE + fx];
+                }
+            }
+        }
+
+        /// The point in the tightest cluster.
+        fn tightest_cluster(&self) -> usize {
+            (0..CELLS)
+                .filter(|&cell| self.points[cell])
+                .max_by(|&a, &b| self.energy[a].total_cmp(&self.energy[b]))
+                .expect("a point")
+        }
+
+        /// The empty cell in the largest void.
+        fn largest_void(&self) -> usize {
+            (0..CELLS)
+                .filter(|&cell| !self.points[cell])
+                .min_by(|&a, &b| self.energy[a].total_cmp(&self.energy[b]))
+                .expect("an empty cell")
+        }
+    }
+
+    // Start from a tenth of the cells at random and move points from the
+    // tightest cluster to the largest void until that changes nothing.
+    let mut prototype = Pattern {
+        points: vec![false; CELLS],
+        energy: vec![0.0; CELLS],
+    };
+    let initial: usize = CELLS / 10;
+    index::sample(&mut StdRng::seed_from_u64(0), CELLS, initial)
+        .into_iter()
+        .for_each(|cell| prototype.set(&filter, cell, true));
+    loop {
+        let cluster: usize = prototype.tightest_cluster();
+        prototype.set(&filter, cluster, false);
+        let void: usize = prototype.largest_void();
+        prototype.set(&filter, void, true);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut ranks: Vec<usize> = vec![0; CELLS];
+
+    // The points of the prototype rank below it, the most clustered last.
+    let mut pattern = Pattern {
+        points: prototype.points.clone(),
+        energy: prototype.energy.clone(),
+    };
+    for rank in (0..initial).rev() {
+        let cluster: usize = pattern.tightest_cluster();
+        pattern.set(&filter, cluster, false);
+        ranks[cluster] = rank;
+    }
+
+    // The empty cells rank above it, filling the largest void first.
+    for rank in initial..CELLS {
+        let void: usize = prototype.largest_void();
+        prototype.set(&filter, void, true);
+        ranks[void] = rank;
+    }
+
+    ranks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blue_noise_mask_should_hold_each_rank_apart_from_its_neighbours() {
+        let mask: &[f32] = blue_noise_mask();
+        let mut ranks: Vec<usize> = mask
+            .iter()
+            .map(|value| (value * mask.len() as f32) as usize)
+            .collect();
+        ranks.sort_unstable();
+        assert_eq!((0..mask.len()).collect::<Vec<usize>>(), ranks);
+
+        // White noise neighbours differ by a third on average.
+        let difference: f32 = (0..mask.len())
+            .map(|i| {
+                let right: usize = i / MASK_SIZE * MASK_SIZE + (i + 1) % MASK_SIZE;
+                (mask[i] - mask[right]).abs()
+            })
+            .sum::<f32>()
+            / mask.len() as f32;
+        assert!(0.4 < difference, "{difference}");
+    }
+}
@@ -0,0 +1,100 @@
+use rand::random;
+
+use super::{hash, to_unit, Sampler, ONE_MINUS_EPSILON};
+
+/// The bases of the dimensions of a `Halton` sequence.
+const PRIMES: [u64; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+/// The Halton sequence, the radical inverse of the sample index in a prime
+/// base for each dimension, shifted by a random amount per pixel and
+/// dimension so that pixels do not repeat each other's errors.
+///
+/// Dimensions past the last prime are hashed to independent random numbers.
+#[derive(Clone, Copy, Debug)]
+pub struct Halton {
+    seed: u64,
+    pixel: u64,
+    index: u64,
+    dimension: usize,
+}
+
+impl Halton {
+    pub fn new() -> Self {
+        Self {
+            seed: random(),
+            pixel: 0,
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// Draw the shift of every pixel and dimension from `seed`.
+    pub fn set_seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+}
+
+impl Default for Halton {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sampler for Halton {
+    fn start_pixel_sample(&mut self, column: usize, row: usize, index: usize) {
+        self.pixel = hash(&[self.seed, column as u64, row as u64]);
+        self.index = index as u64;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        let dimension: usize = self.dimension;
+        self.dimension += 1;
+
+        let shift: f32 = to_unit(hash(&[self.pixel, dimension as u64]) as u32);
+        match PRIMES.get(dimension) {
+            Some(&base) => {
+                let sample: f32 = radical_inverse(base, self.index) + shift;
+                match sample < 1.0 {
+                    true => sample,
+                    false => (sample - 1.0).min(ONE_MINUS_EPSILON),
+                }
+            }
+            None => to_unit(hash(&[self.pixel, dimension as u64, self.index]) as u32),
+        }
+    }
+
+    fn get_2d(&mut self) -> [f32; 2] {
+        [self.get_1d(), self.get_1d()]
+    }
+}
+
+/// The digits of `index` in `base` mirrored about the radix point.
+fn radical_inverse(base: u64, mut index: u64) -> f32 {
+    let inverse_base: f64 = 1.0 / base as f64;
+    let (mut reversed, mut inverse_base_n): (u64, f64) = (0, 1.0);
+    while index != 0 {
+        let next: u64 = index / base;
+        reversed = reversed * base + (index - next * base);
+        inverse_base_n *= inverse_base;
+        index = next;
+    }
+
+    ((reversed as f64 * inverse_base_n) as f32).min(ONE_MINUS_EPSILON)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn radical_inverse_should_mirror_the_digits() {
+        assert_eq!(0.0, radical_inverse(2, 0));
+        assert_eq!(0.5, radical_inverse(2, 1));
+        assert_eq!(0.75, radical_inverse(2, 3));
+        assert!((radical_inverse(3, 5) - 7.0 / 9.0).abs() < 1e-7);
+    }
+}
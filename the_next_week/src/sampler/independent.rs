@@ -0,0 +1,58 @@
+use rand::random;
+
+use super::{hash, to_unit, Sampler};
+
+/// Independent uniform random numbers, hashed from the seed, pixel, sample
+/// index and dimension.
+#[derive(Clone, Copy, Debug)]
+pub struct Independent {
+    seed: u64,
+    sample: u64,
+    dimension: u64,
+}
+
+impl Independent {
+    pub fn new() -> Self {
+        Self::seeded(random())
+    }
+
+    /// Hash every number from `seed`, without drawing one from the thread RNG
+    /// as `new` does.
+    pub const fn seeded(seed: u64) -> Self {
+        Self {
+            seed,
+            sample: seed,
+            dimension: 0,
+        }
+    }
+
+    /// Hash every number from `seed`.
+    pub fn set_seed(self, seed: u64) -> Self {
+        Self::seeded(seed)
+    }
+}
+
+impl Default for Independent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sampler for Independent {
+    fn start_pixel_sample(&mut self, column: usize, row: usize, index: usize) {
+        self.sample = hash(&[self.seed, column as u64, row as u64, index as u64]);
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        let bits: u64 = hash(&[self.sample, self.dimension]);
+        self.dimension += 1;
+        to_unit(bits as u32)
+    }
+
+    fn get_2d(&mut self) -> [f32; 2] {
+        let bits: u64 = hash(&[self.sample, self.dimension]);
+        self.dimension += 1;
+        [to_unit(bits as u32), to_unit((bits >> 32) as u32)]
+    }
+}
@@ -0,0 +1,197 @@
+mod blue_noise;
+mod halton;
+mod independent;
+mod sobol;
+mod stratified;
+
+use std::f32::consts::{FRAC_PI_4, TAU};
+
+use in_one_weekend::vec3::Vec3;
+
+pub use self::{
+    blue_noise::BlueNoise, halton::Halton, independent::Independent, sobol::Sobol,
+    stratified::Stratified,
+};
+
+/// The largest `f32` below 1, to keep samples in `[0, 1)`.
+const ONE_MINUS_EPSILON: f32 = 1.0 - f32::EPSILON / 2.0;
+
+/// Where the random numbers of a render come from.
+///
+/// Each sample of a pixel draws its numbers one dimension after another: the
+/// position in the pixel first, then the lens and shutter time of the camera,
+/// then whatever each bounce needs. Samplers other than `Independent` spread
+/// the samples of a pixel evenly over each dimension, or pair of dimensions,
+/// which converges faster than independent random numbers do.
+///
+/// The numbers of a sample depend only on the seed of the sampler, random
+/// unless set, and on the pixel and sample index, so a render with a set seed
+/// repeats exactly however its pixels are shared out between threads.
+///
+/// A sampler is used by one thread at a time; clone one for each job.
+pub trait Sampler {
+    /// Start sample `index` of the pixel at `column` and `row`, from its first
+    /// dimension.
+    fn start_pixel_sample(&mut self, column: usize, row: usize, index: usize);
+
+    /// The next dimension, in `[0, 1)`.
+    fn get_1d(&mut self) -> f32;
+
+    /// The next two dimensions, spread evenly together.
+    fn get_2d(&mut self) -> [f32; 2];
+
+    /// A point in the unit disk in the xy plane, by the concentric mapping,
+    /// which keeps evenly spread samples evenly spread.
+    fn in_unit_disk(&mut self) -> Vec3 {
+        let [a, b]: [f32; 2] = self.get_2d().map(|u| 2.0 * u - 1.0);
+        if a == 0.0 && b == 0.0 {
+            return Vec3::new(0.0, 0.0, 0.0);
+        }
+
+        let (r, theta): (f32, f32) = match b.abs() < a.abs() {
+            true => (a, FRAC_PI_4 * (b / a)),
+            false => (b, 2.0 * FRAC_PI_4 - FRAC_PI_4 * (a / b)),
+        };
+        Vec3::new(r * theta.cos(), r * theta.sin(), 0.0)
+    }
+
+    /// A point on the unit sphere.
+    fn unit_vector(&mut self) -> Vec3 {
+        let [a, b]: [f32; 2] = self.get_2d();
+        let z: f32 = 1.0 - 2.0 * a;
+        let r: f32 = (1.0 - z * z).max(0.0).sqrt();
+        let (sin, cos): (f32, f32) = (TAU * b).sin_cos();
+
+        Vec3::new(r * cos, r * sin, z)
+    }
+
+    /// A point in the unit ball.
+    fn in_unit_sphere(&mut self) -> Vec3 {
+        self.unit_vector() * self.get_1d().cbrt()
+    }
+}
+
+/// Mixes the bits of `value` so that nearby inputs give unrelated outputs.
+fn mix_bits(mut value: u64) -> u64 {
+    value ^= value >> 31;
+    value = value.wrapping_mul(0x7fb5_d329_728e_a185);
+    value ^= value >> 27;
+    value = value.wrapping_mul(0x81da_def4_bc2d_d44d);
+    value ^= value >> 33;
+    value
+}
+
+/// A hash of `values`, for seeding the scrambles of each pixel and dimension,
+/// among other things.
+pub(crate) fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(0x9e37_79b9_7f4a_7c15, |hash, &value| {
+        mix_bits(hash ^ mix_bits(value.wrapping_add(0x9e37_79b9_7f4a_7c15)))
+    })
+}
+
+/// The high bits of `bits` as a number in `[0, 1)`.
+fn to_unit(bits: u32) -> f32 {
+    (bits >> 8) as f32 / (1 << 24) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The mean squared error of estimating the integral of a smooth function
+    /// over the unit square with 64 samples, over many pixels.
+    fn mean_squared_error(sampler: &mut dyn Sampler) -> f32 {
+        const PIXELS: usize = 64;
+        const SAMPLES: usize = 64;
+        let f = |[x, y]: [f32; 2]| (x * 3.0).sin() * y * y;
+        let expected: f32 = (1.0 - 3.0_f32.cos()) / 3.0 / 3.0;
+
+        (0..PIXELS)
+            .map(|pixel| {
+                let estimate: f32 = (0..SAMPLES)
+                    .map(|index| {
+                        sampler.start_pixel_sample(pixel, 0, index);
+                        f(sampler.get_2d())
+                    })
+                    .sum::<f32>()
+                    / SAMPLES as f32;
+                (estimate - expected).powi(2)
+            })
+            .sum::<f32>()
+            / PIXELS as f32
+    }
+
+    #[test]
+    fn low_discrepancy_samplers_should_beat_independent_samples() {
+        let independent: f32 = mean_squared_error(&mut Independent::new());
+        for (name, sampler) in [
+            ("stratified", &mut Stratified::new(8, 8) as &mut dyn Sampler),
+            ("halton", &mut Halton::new()),
+            ("sobol", &mut Sobol::new()),
+            ("blue noise", &mut BlueNoise::new()),
+        ] {
+            let error: f32 = mean_squared_error(sampler);
+            assert!(
+                error * 4.0 < independent,
+                "{name}: {error} vs {independent}"
+            );
+        }
+    }
+
+    #[test]
+    fn seeded_samplers_should_repeat_in_any_pixel_order() {
+        // Whichever thread renders a pixel, and whatever it rendered before,
+        // the pixel gets the same numbers.
+        let draws = |sampler: &mut dyn Sampler, pixels: &mut dyn Iterator<Item = usize>| {
+            let mut draws: Vec<(usize, [f32; 3])> = pixels
+                .flat_map(|pixel| (0..4).map(move |index| (pixel, index)))
+                .map(|(pixel, index)| {
+                    sampler.start_pixel_sample(pixel % 4, pixel / 4, index);
+                    let [a, b]: [f32; 2] = sampler.get_2d();
+                    (pixel, [a, b, sampler.get_1d()])
+                })
+                .collect();
+            draws.sort_by_key(|(pixel, _)| *pixel);
+            draws
+        };
+
+        let makes: [fn() -> Box<dyn Sampler>; 5] = [
+            || Box::new(Independent::new().set_seed(7)),
+            || Box::new(Stratified::new(2, 2).set_seed(7)),
+            || Box::new(Halton::new().set_seed(7)),
+            || Box::new(Sobol::new().set_seed(7)),
+            || Box::new(BlueNoise::new().set_seed(7)),
+        ];
+        for make in makes {
+            let forwards = draws(make().as_mut(), &mut (0..16));
+            let backwards = draws(make().as_mut(), &mut (0..16).rev());
+            assert_eq!(forwards, backwards);
+        }
+        assert_ne!(
+            draws(&mut Sobol::new().set_seed(7), &mut (0..16)),
+            draws(&mut Sobol::new().set_seed(8), &mut (0..16))
+        );
+    }
+
+    #[test]
+    fn samples_should_stay_in_the_unit_interval() {
+        for sampler in [
+            &mut Independent::new() as &mut dyn Sampler,
+            &mut Stratified::new(4, 4),
+            &mut Halton::new(),
+            &mut Sobol::new(),
+            &mut BlueNoise::new(),
+        ] {
+            (0..64).for_each(|index| {
+                sampler.start_pixel_sample(3, 5, index);
+                (0..40).for_each(|_| {
+                    assert!((0.0..1.0).contains(&sampler.get_1d()));
+                    let [a, b]: [f32; 2] = sampler.get_2d();
+                    assert!((0.0..1.0).contains(&a) && (0.0..1.0).contains(&b));
+                });
+                assert!(sampler.in_unit_disk().len() <= 1.0);
+                assert!((sampler.unit_vector().len() - 1.0).abs() < 1e-5);
+            });
+        }
+    }
+}
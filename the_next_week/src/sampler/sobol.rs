@@ -0,0 +1,156 @@
+use rand::random;
+
+use super::{hash, to_unit, Sampler};
+
+/// Generator matrices of the first two Sobol dimensions, as the column for
+/// each bit of the index: the van der Corput sequence and the one with the
+/// primitive polynomial `x + 1`.
+const DIRECTIONS: [[u32; 32]; 2] = [directions(false), directions(true)];
+
+const fn directions(pascal: bool) -> [u32; 32] {
+    let mut v: [u32; 32] = [0; 32];
+    let mut i: usize = 0;
+    while i < 32 {
+        v[i] = match (pascal, i) {
+            (true, 1..) => v[i - 1] ^ (v[i - 1] >> 1),
+            _ => 1 << (31 - i),
+        };
+        i += 1;
+    }
+    v
+}
+
+/// The first two dimensions of the Sobol sequence with Owen scrambling, after
+/// Burley's "Practical Hash-based Owen Scrambling".
+///
+/// Every draw, of one dimension or two, uses the same Sobol dimensions with a
+/// scramble and sample order of its own, so that any number of them can be
+/// drawn and each keeps the stratification of Sobol points: the first `2^m`
+/// samples of `get_2d` fall one in each cell of any grid of `2^m` cells. It
+/// converges best at powers of two samples per pixel.
+#[derive(Clone, Copy, Debug)]
+pub struct Sobol {
+    seed: u64,
+    pixel: u64,
+    index: u32,
+    dimension: usize,
+}
+
+impl Sobol {
+    pub fn new() -> Self {
+        Self {
+            seed: random(),
+            pixel: 0,
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// Draw the scramble of every pixel and draw from `seed`.
+    pub fn set_seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+
+    /// Start sample `index` of the sequence scrambled by `pixel`.
+    pub(super) fn start(&mut self, pixel: u64, index: usize) {
+        self.pixel = pixel;
+        self.index = index as u32;
+        self.dimension = 0;
+    }
+
+    pub(super) fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The bits of the next draw of `N` dimensions, and its number.
+    pub(super) fn next_bits<const N: usize>(&mut self) -> ([u32; N], usize) {
+        let draw: usize = self.dimension;
+        self.dimension += 1;
+
+        let seed: u64 = hash(&[self.pixel, draw as u64]);
+        let index: u32 = nested_uniform_scramble(self.index, seed as u32);
+        let bits: [u32; N] = std::array::from_fn(|dimension| {
+            let scramble: u64 = hash(&[seed, dimension as u64]);
+            nested_uniform_scramble(sobol(index, dimension), scramble as u32)
+        });
+
+        (bits, draw)
+    }
+}
+
+impl Default for Sobol {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sampler for Sobol {
+    fn start_pixel_sample(&mut self, column: usize, row: usize, index: usize) {
+        self.start(hash(&[self.seed, column as u64, row as u64]), index);
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        let ([bits], _) = self.next_bits::<1>();
+        to_unit(bits)
+    }
+
+    fn get_2d(&mut self) -> [f32; 2] {
+        self.next_bits::<2>().0.map(to_unit)
+    }
+}
+
+/// Component `dimension` of Sobol point `index`.
+fn sobol(mut index: u32, dimension: usize) -> u32 {
+    let mut bits: u32 = 0;
+    let mut bit: usize = 0;
+    while index != 0 {
+        if index & 1 == 1 {
+            bits ^= DIRECTIONS[dimension][bit];
+        }
+        index >>= 1;
+        bit += 1;
+    }
+    bits
+}
+
+/// A hash which only lets each bit of `x` depend on the bits below it.
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50_b47c);
+    x ^= x.wrapping_mul(0xb82f_1e52);
+    x ^= x.wrapping_mul(0xc7af_e638);
+    x ^= x.wrapping_mul(0x8d22_f6e6);
+    x
+}
+
+/// Owen scrambling: flips each bit of `x` depending only on the bits above it.
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sobol_pairs_should_be_stratified() {
+        let mut sampler = Sobol::new();
+        // Each draw of two dimensions, over every grid of 16 cells.
+        for draw in 0..3 {
+            for (columns, rows) in [(1, 16), (2, 8), (4, 4), (8, 2), (16, 1)] {
+                let mut cells: Vec<usize> = (0..16)
+                    .map(|index| {
+                        sampler.start_pixel_sample(1, 2, index);
+                        (0..draw).for_each(|_| {
+                            sampler.get_1d();
+                        });
+                        let [x, y]: [f32; 2] = sampler.get_2d();
+                        (y * rows as f32) as usize * columns + (x * columns as f32) as usize
+                    })
+                    .collect();
+                cells.sort_unstable();
+                assert_eq!((0..16).collect::<Vec<usize>>(), cells);
+            }
+        }
+    }
+}
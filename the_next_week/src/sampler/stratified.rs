@@ -0,0 +1,148 @@
+use rand::random;
+
+use super::{hash, to_unit, Sampler, ONE_MINUS_EPSILON};
+
+/// Jittered samples, one in each cell of a grid over every dimension, with the
+/// cells visited in a different order for each dimension and pixel so that the
+/// dimensions do not line up.
+///
+/// It takes `x_strata * y_strata` samples per pixel to cover the grid; more
+/// start over with new jitter.
+#[derive(Clone, Copy, Debug)]
+pub struct Stratified {
+    x_strata: usize,
+    y_strata: usize,
+    seed: u64,
+    pixel: u64,
+    index: usize,
+    dimension: u64,
+}
+
+impl Stratified {
+    pub fn new(x_strata: usize, y_strata: usize) -> Self {
+        assert!(0 < x_strata && 0 < y_strata);
+        Self {
+            x_strata,
+            y_strata,
+            seed: random(),
+            pixel: 0,
+            index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// Permute and jitter the cells of every pixel from `seed`.
+    pub fn set_seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+
+    /// The cell of the current sample in the next dimension.
+    fn stratum(&mut self) -> usize {
+        let strata: usize = self.x_strata * self.y_strata;
+        let permutation: u64 = hash(&[self.pixel, self.dimension]);
+        self.dimension += 1;
+
+        permutation_element(
+            (self.index % strata) as u32,
+            strata as u32,
+            permutation as u32,
+        ) as usize
+    }
+
+    /// Where in its cell the current sample falls along `axis` of the last
+    /// dimension.
+    fn jitter(&self, axis: u64) -> f32 {
+        to_unit(hash(&[self.pixel, self.index as u64, self.dimension, axis]) as u32)
+    }
+}
+
+impl Sampler for Stratified {
+    fn start_pixel_sample(&mut self, column: usize, row: usize, index: usize) {
+        self.pixel = hash(&[self.seed, column as u64, row as u64]);
+        self.index = index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f32 {
+        let strata: usize = self.x_strata * self.y_strata;
+        let stratum: usize = self.stratum();
+
+        ((stratum as f32 + self.jitter(0)) / strata as f32).min(ONE_MINUS_EPSILON)
+    }
+
+    fn get_2d(&mut self) -> [f32; 2] {
+        let stratum: usize = self.stratum();
+        let (x, y): (usize, usize) = (stratum % self.x_strata, stratum / self.x_strata);
+
+        [
+            ((x as f32 + self.jitter(0)) / self.x_strata as f32).min(ONE_MINUS_EPSILON),
+            ((y as f32 + self.jitter(1)) / self.y_strata as f32).min(ONE_MINUS_EPSILON),
+        ]
+    }
+}
+
+/// Element `index` of a random permutation of `0..length` picked by `seed`,
+/// without building the permutation (Kensler, "Correlated Multi-Jittered
+/// Sampling").
+fn permutation_element(mut index: u32, length: u32, seed: u32) -> u32 {
+    let mut mask: u32 = length.wrapping_sub(1);
+    mask |= mask >> 1;
+    mask |= mask >> 2;
+    mask |= mask >> 4;
+    mask |= mask >> 8;
+    mask |= mask >> 16;
+
+    // Cycle walk a bijection of `0..=mask` until it lands inside `0..length`.
+    loop {
+        index ^= seed;
+        index = index.wrapping_mul(0xe170_893d);
+        index ^= seed >> 16;
+        index ^= (index & mask) >> 4;
+        index ^= seed >> 8;
+        index = index.wrapping_mul(0x0929_eb3f);
+        index ^= seed >> 23;
+        index ^= (index & mask) >> 1;
+        index = index.wrapping_mul(1 | seed >> 27);
+        index = index.wrapping_mul(0x6935_fa69);
+        index ^= (index & mask) >> 11;
+        index = index.wrapping_mul(0x74dc_b303);
+        index ^= (index & mask) >> 2;
+        index = index.wrapping_mul(0x9e50_1cc3);
+        index ^= (index & mask) >> 2;
+        index = index.wrapping_mul(0xc860_a3df);
+        index &= mask;
+        index ^= index >> 5;
+        if index < length {
+            break;
+        }
+    }
+
+    (index.wrapping_add(seed)) % length
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stratified_samples_should_fill_every_stratum() {
+        let mut sampler = Stratified::new(4, 3);
+        let mut cells: Vec<[usize; 3]> = (0..12)
+            .map(|index| {
+                sampler.start_pixel_sample(7, 2, index);
+                let u: f32 = sampler.get_1d();
+                let [x, y]: [f32; 2] = sampler.get_2d();
+                [(u * 12.0) as usize, (x * 4.0) as usize, (y * 3.0) as usize]
+            })
+            .collect();
+
+        let mut strata: Vec<usize> = cells.iter().map(|cell| cell[0]).collect();
+        strata.sort_unstable();
+        assert_eq!((0..12).collect::<Vec<usize>>(), strata);
+        cells.sort_unstable_by_key(|cell| (cell[2], cell[1]));
+        cells
+            .iter()
+            .enumerate()
+            .for_each(|(i, cell)| assert_eq!([i % 4, i / 4], [cell[1], cell[2]]));
+    }
+}
@@ -0,0 +1,627 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+    sync::Arc,
+};
+
+use in_one_weekend::{color::ColorRGBMapTo0_1, point::Point3, vec3::Vec3};
+
+use crate::{
+    camera::{Camera, CameraBuilder, Projection},
+    hittable::{
+        ConstantMedium, Cuboid, Hittable, HittableList, Instance, MovingSphere, Sphere, XYRect,
+        XZRect, YZRect,
+    },
+    material::{Attenuation, Dielectric, DiffuseLight, Lambertian, Material, Metal},
+    noise::Perlin,
+    ray::Ray,
+    sampler::Sampler,
+    stats,
+    textures::{CheckerTexture, ImageTexture, NoiseTexture, SolidColor},
+};
+
+/// What a ray that hits nothing sees.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Background {
+    Color(ColorRGBMapTo0_1),
+    /// White at the horizon to light blue straight up.
+    Sky,
+}
+
+impl Background {
+    pub fn color(&self, ray: &Ray) -> ColorRGBMapTo0_1 {
+        match self {
+            Background::Color(color) => *color,
+            Background::Sky => {
+                let unit_direction: Vec3 = ray.direction().unit_vector();
+                let t: f32 = 0.5 * (unit_direction.y() + 1.0);
+                (1.0 - t) * ColorRGBMapTo0_1::new(1.0, 1.0, 1.0)
+                    + t * ColorRGBMapTo0_1::new(0.5, 0.7, 1.0)
+            }
+        }
+    }
+}
+
+/// A world, the camera looking at it and the background, with a path tracer to
+/// render them.
+pub struct Scene {
+    world: HittableList<Box<dyn Hittable>>,
+    camera: Box<dyn Projection>,
+    background: Background,
+    max_depth: u16,
+}
+
+impl Scene {
+    /// The names `builtin` knows.
+    pub const BUILTIN: [&'static str; 3] = ["checkered_spheres", "cornell_box", "final_scene"];
+
+    pub fn new(
+        world: HittableList<Box<dyn Hittable>>,
+        camera: impl Projection + 'static,
+        background: Background,
+    ) -> Self {
+        Self {
+            world,
+            camera: Box::new(camera),
+            background,
+            max_depth: 50,
+        }
+    }
+
+    /// The most bounces a path takes, default 50.
+    pub fn set_max_depth(mut self, depth: u16) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Factor the light reaching the image is scaled by, as the camera has it.
+    pub fn exposure(&self) -> f32 {
+        self.camera.exposure()
+    }
+
+    /// One of the scenes of `BUILTIN`, seen with `aspect_ratio`.
+    pub fn builtin(name: &str, aspect_ratio: f32) -> Option<Self> {
+        match name {
+            "checkered_spheres" => Some(self::checkered_spheres(aspect_ratio)),
+            "cornell_box" => Some(self::cornell_box(aspect_ratio)),
+            "final_scene" => Some(Self::final_scene(0, aspect_ratio)),
+            _ => None,
+        }
+    }
+
+    /// The last scene of the book, with the heights of the ground boxes, the
+    /// noise texture and the places of the little spheres drawn from `seed`.
+    /// `builtin` has it with seed 0.
+    pub fn final_scene(seed: u64, aspect_ratio: f32) -> Self {
+        self::final_scene(seed, aspect_ratio)
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P, aspect_ratio: f32) -> io::Result<Self> {
+        Self::read(BufReader::new(File::open(path)?), aspect_ratio)
+    }
+
+    /// A scene read from a text file with a camera and spheres:
+    ///
+    /// ```text
+    /// # look_from look_at fov [aperture focus_dist]
+    /// camera 13 2 3  0 0 0  20  0.1 10
+    /// # r g b, or sky
+    /// background sky
+    /// # center radius material
+    /// sphere 0 -1000 0  1000  lambertian 0.5 0.5 0.5
+    /// sphere 0 1 0  1  dielectric 1.5
+    /// sphere 4 1 0  1  metal 0.7 0.6 0.5  0.0
+    /// sphere 0 4 0  0.5  light 1 1 1  4
+    /// ```
+    ///
+    /// A metal takes its albedo and fuzz, and a light its colour and
+    /// luminance. The background is black if not given. Blank lines and
+    /// everything after a `#` are ignored.
+    pub fn read<R: BufRead>(reader: R, aspect_ratio: f32) -> io::Result<Self> {
+        let mut world: HittableList<Box<dyn Hittable>> = HittableList::default();
+        let mut camera: Option<Camera> = None;
+        let mut background: Background = Background::Color(ColorRGBMapTo0_1::new(0.0, 0.0, 0.0));
+
+        for (number, line) in reader.lines().enumerate() {
+            let line: String = line?;
+            let data: &str = line.split('#').next().unwrap_or_default();
+            let mut words = data.split_whitespace();
+            let Some(keyword) = words.next() else {
+                continue;
+            };
+            let error = |message: &str| invalid_data(&format!("line {}: {message}", number + 1));
+
+            let rest: Vec<&str> = words.collect();
+            match keyword {
+                "camera" => {
+                    let values: Vec<f32> = numbers(&rest).map_err(|err| error(&err))?;
+                    let builder: CameraBuilder = match values.len() {
+                        7 | 9 => Camera::builder()
+                            .look_from(Point3::new(values[0], values[1], values[2]))
+                            .look_at(Point3::new(values[3], values[4], values[5]))
+                            .up(Vec3::new(0.0, 1.0, 0.0))
+                            .fov(values[6])
+                            .aspect_ratio(aspect_ratio)
+                            .time_0(0.0)
+                            .time_1(1.0),
+                        _ => return Err(error("expected look_from, look_at and fov")),
+                    };
+                    camera = Some(match values.len() {
+                        9 => builder.aperture(values[7]).focus_dist(values[8]).build(),
+                        _ => builder.focus_dist(1.0).build(),
+                    });
+                }
+                "background" => {
+                    background = match rest.as_slice() {
+                        ["sky"] => Background::Sky,
+                        _ => Background::Color(color(&rest).map_err(|err| error(&err))?),
+                    }
+                }
+                "sphere" => {
+                    let values: Vec<f32> = rest
+                        .get(..4)
+                        .ok_or_else(|| error("expected a center and a radius"))
+                        .and_then(|values| numbers(values).map_err(|err| error(&err)))?;
+                    let material: Arc<dyn Material> =
+                        self::material(&rest[4..]).map_err(|err| error(&err))?;
+                    world.add(Box::new(Sphere::new(
+                        Point3::new(values[0], values[1], values[2]),
+                        values[3],
+                        material,
+                    )));
+                }
+                _ => return Err(error(&format!("unknown keyword {keyword}"))),
+            }
+        }
+
+        let camera: Camera = camera.ok_or_else(|| invalid_data("no camera"))?;
+        Ok(Self::new(world, camera, background))
+    }
+
+    /// One sample of the pixel at `row` and `column` of a `width` by `height`
+    /// image.
+    pub fn sample(
+        &self,
+        row: usize,
+        column: usize,
+        width: usize,
+        height: usize,
+        sampler: &mut dyn Sampler,
+    ) -> ColorRGBMapTo0_1 {
+        let [du, dv]: [f32; 2] = sampler.get_2d();
+        let u = (column as f32 + du) / (width - 1) as f32;
+        let v = ((height - 1 - row) as f32 + dv) / (height - 1) as f32;
+        self.camera
+            .get_ray(u, v, sampler)
+            .map_or(ColorRGBMapTo0_1::new(0.0, 0.0, 0.0), |ray| {
+                stats::camera_ray();
+                self.ray_color(&ray, self.max_depth, sampler)
+            })
+    }
+
+    fn ray_color(&self, ray: &Ray, depth: u16, sampler: &mut dyn Sampler) -> ColorRGBMapTo0_1 {
+        if depth == 0 {
+            return ColorRGBMapTo0_1::new(0.0, 0.0, 0.0);
+        }
+        stats::ray();
+
+        let Some(hit_record) = self.world.hit_sampled(ray, 0.001, f32::INFINITY, sampler) else {
+            return self.background.color(ray);
+        };
+
+        let material: &dyn Material = hit_record.material();
+        let emitted: ColorRGBMapTo0_1 = material
+            .emitted_sampled(ray, &hit_record, sampler)
+            .map_or(ColorRGBMapTo0_1::new(0.0, 0.0, 0.0), |emit_record| {
+                emit_record.color() * emit_record.luminance()
+            });
+        let scattered: ColorRGBMapTo0_1 = material.scatter(ray, &hit_record, sampler).map_or(
+            ColorRGBMapTo0_1::new(0.0, 0.0, 0.0),
+            |scatter_rec| {
+                scatter_rec.albedo()
+                    * self.ray_color(scatter_rec.ray_scattered(), depth - 1, sampler)
+            },
+        );
+
+        (emitted + scattered) * hit_record.weight()
+    }
+}
+
+/// Where a scene comes from, to be sent to the machines rendering it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SceneSource {
+    /// One of `Scene::BUILTIN`.
+    Builtin(String),
+    /// The contents of a scene file, as `Scene::read` takes.
+    Text(String),
+}
+
+impl SceneSource {
+    /// The scene file at `path`, read now so that it does not need to be
+    /// where the scene is loaded.
+    pub fn file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        std::fs::read_to_string(path).map(Self::Text)
+    }
+
+    pub fn load(&self, aspect_ratio: f32) -> io::Result<Scene> {
+        match self {
+            SceneSource::Builtin(name) => Scene::builtin(name, aspect_ratio).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("no built-in scene {name}, there are {:?}", Scene::BUILTIN),
+                )
+            }),
+            SceneSource::Text(text) => Scene::read(text.as_bytes(), aspect_ratio),
+        }
+    }
+}
+
+fn numbers(words: &[&str]) -> Result<Vec<f32>, String> {
+    words
+        .iter()
+        .map(|word| word.parse::<f32>())
+        .collect::<Result<_, _>>()
+        .map_err(|err| err.to_string())
+}
+
+fn color(words: &[&str]) -> Result<ColorRGBMapTo0_1, String> {
+    match numbers(words)?.as_slice() {
+        &[r, g, b] => Ok(ColorRGBMapTo0_1::new(r, g, b)),
+        _ => Err("expected a color as r g b".to_string()),
+    }
+}
+
+fn material(words: &[&str]) -> Result<Arc<dyn Material>, String> {
+    let (kind, values) = words
+        .split_first()
+        .ok_or_else(|| "expected a material".to_string())?;
+    let values: Vec<f32> = numbers(values)?;
+    match (*kind, values.as_slice()) {
+        ("lambertian", &[r, g, b]) => Ok(Arc::new(Lambertian::new(SolidColor::from(
+            ColorRGBMapTo0_1::new(r, g, b),
+        )))),
+        ("metal", &[r, g, b, fuzz]) => Ok(Arc::new(Metal::new(
+            Attenuation::new(Vec3::new(r, g, b)),
+            fuzz,
+        ))),
+        ("dielectric", &[ior]) => Ok(Arc::new(Dielectric::new(ior))),
+        ("light", &[r, g, b, luminance]) => Ok(Arc::new(DiffuseLight::new(
+            SolidColor::from(ColorRGBMapTo0_1::new(r, g, b)),
+            luminance,
+        ))),
+        _ => Err(format!("can not make a {kind} of {values:?}")),
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn checkered_spheres(aspect_ratio: f32) -> Scene {
+    let mut world: HittableList<Box<dyn Hittable>> = HittableList::default();
+    let checker: CheckerTexture<SolidColor, SolidColor> = CheckerTexture::new(
+        ColorRGBMapTo0_1::new(0.2, 0.3, 0.1).into(),
+        ColorRGBMapTo0_1::new(0.9, 0.9, 0.9).into(),
+    );
+    let material: Arc<dyn Material> = Arc::new(Lambertian::new(checker));
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, -10.0, 0.0),
+        10.0,
+        Arc::clone(&material),
+    )));
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, 10.0, 0.0),
+        10.0,
+        material,
+    )));
+
+    let camera: Camera = Camera::builder()
+        .look_from(Point3::new(13.0, 2.0, 3.0))
+        .look_at(Point3::new(0.0, 0.0, 0.0))
+        .up(Vec3::new(0.0, 1.0, 0.0))
+        .fov(20.0)
+        .aspect_ratio(aspect_ratio)
+        .aperture(0.1)
+        .focus_dist(10.0)
+        .time_0(0.0)
+        .time_1(1.0)
+        .build();
+
+    Scene::new(world, camera, Background::Sky)
+}
+
+fn cornell_box(aspect_ratio: f32) -> Scene {
+    let mut world: HittableList<Box<dyn Hittable>> = HittableList::default();
+
+    let lambertian = |r: f32, g: f32, b: f32| -> Arc<dyn Material> {
+        Arc::new(Lambertian::new(SolidColor::from(ColorRGBMapTo0_1::new(
+            r, g, b,
+        ))))
+    };
+    let red: Arc<dyn Material> = lambertian(0.65, 0.05, 0.05);
+    let white: Arc<dyn Material> = lambertian(0.73, 0.73, 0.73);
+    let green: Arc<dyn Material> = lambertian(0.12, 0.45, 0.15);
+    let light: Arc<dyn Material> = Arc::new(DiffuseLight::new(
+        SolidColor::from(ColorRGBMapTo0_1::new(1.0, 1.0, 1.0)),
+        15.0,
+    ));
+
+    world.add(Box::new(YZRect::new(
+        0.0..=555.0,
+        0.0..=555.0,
+        555.0,
+        green,
+    )));
+    world.add(Box::new(YZRect::new(0.0..=555.0, 0.0..=555.0, 0.0, red)));
+    world.add(Box::new(XZRect::new(
+        213.0..=343.0,
+        227.0..=332.0,
+        554.0,
+        light,
+    )));
+    world.add(Box::new(XZRect::new(
+        0.0..=555.0,
+        0.0..=555.0,
+        0.0,
+        Arc::clone(&white),
+    )));
+    world.add(Box::new(XZRect::new(
+        0.0..=555.0,
+        0.0..=555.0,
+        555.0,
+        Arc::clone(&white),
+    )));
+    world.add(Box::new(XYRect::new(
+        0.0..=555.0,
+        0.0..=555.0,
+        555.0,
+        Arc::clone(&white),
+    )));
+
+    [
+        (
+            Point3::new(165.0, 330.0, 165.0),
+            15.0,
+            Vec3::new(265.0, 0.0, 295.0),
+        ),
+        (
+            Point3::new(165.0, 165.0, 165.0),
+            -18.0,
+            Vec3::new(130.0, 0.0, 65.0),
+        ),
+    ]
+    .into_iter()
+    .for_each(|(size, degrees, displacement): (Point3, f32, Vec3)| {
+        let cuboid: Cuboid = Cuboid::new(Point3::new(0.0, 0.0, 0.0), size, Arc::clone(&white));
+        world.add(Box::new(Instance::Translate {
+            prototype: Box::new(Instance::RotateY {
+                prototype: Box::new(cuboid) as Box<dyn Hittable>,
+                radians: degrees.to_radians(),
+            }) as Box<dyn Hittable>,
+            displacement,
+        }));
+    });
+
+    let camera: Camera = Camera::builder()
+        .look_from(Point3::new(278.0, 278.0, -800.0))
+        .look_at(Point3::new(278.0, 278.0, 0.0))
+        .up(Vec3::new(0.0, 1.0, 0.0))
+        .fov(40.0)
+        .aspect_ratio(aspect_ratio)
+        .aperture(0.0)
+        .focus_dist(10.0)
+        .time_0(0.0)
+        .time_1(1.0)
+        .build();
+
+    Scene::new(
+        world,
+        camera,
+        Background::Color(ColorRGBMapTo0_1::new(0.0, 0.0, 0.0)),
+    )
+}
+
+fn final_scene(seed: u64, aspect_ratio: f32) -> Scene {
+    let mut objects: HittableList<Box<dyn Hittable>> = HittableList::default();
+
+    const SIZE: usize = 20;
+    let mut boxes1: HittableList<Box<dyn Hittable>> = HittableList::with_capacity(SIZE * SIZE);
+    let ground: Arc<Lambertian<SolidColor>> = Arc::new(Lambertian::new(SolidColor::from(
+        ColorRGBMapTo0_1::new(0.48, 0.83, 0.53),
+    )));
+    let mut rng: StdRng = StdRng::seed_from_u64(seed);
+    (0..SIZE).for_each(|i| {
+        (0..SIZE).for_each(|j| {
+            let w = 100.0;
+            let [x0, y0, z0]: [f32; 3] = [-1000.0 + i as f32 * w, 0.0, -1000.0 + j as f32 * w];
+            let [x1, y1, z1]: [f32; 3] = [x0 + w, rng.gen_range(1.0..101.0), z0 + w];
+
+            boxes1.add(Box::new(Cuboid::new(
+                Point3::new(x0, y0, z0),
+                Point3::new(x1, y1, z1),
+                Arc::clone(&ground) as Arc<dyn Material>,
+            )))
+        })
+    });
+    objects.add(Box::new(boxes1));
+
+    let light: Arc<DiffuseLight<SolidColor>> = Arc::new(DiffuseLight::new(
+        SolidColor::from(ColorRGBMapTo0_1::new(1.0, 1.0, 1.0)),
+        15.0,
+    ));
+    objects.add(Box::new(XZRect::new(
+        123.0..=423.0,
+        147.0..=412.0,
+        554.0,
+        light as Arc<dyn Material>,
+    )));
+
+    let center_1: Point3 = Point3::new(400.0, 400.0, 200.0);
+    let center_2: Point3 = Point3::new(400.0, 400.0, 200.0) + Point3::new(30.0, 0.0, 0.0);
+    objects.add(Box::new(MovingSphere::new(
+        center_1,
+        center_2,
+        0.0,
+        1.0,
+        50.0,
+        Arc::new(Lambertian::new(SolidColor::from(ColorRGBMapTo0_1::new(
+            0.7, 0.3, 0.1,
+        )))) as Arc<dyn Material>,
+    )));
+
+    let dielectric: Arc<Dielectric> = Arc::new(Dielectric::new(1.5));
+    objects.add(Box::new(Sphere::new(
+        Point3::new(260.0, 150.0, 45.0),
+        50.0,
+        Arc::clone(&dielectric) as Arc<dyn Material>,
+    )));
+
+    let metal: Arc<Metal> = Arc::new(Metal::new(Attenuation::new(Vec3::new(0.8, 0.8, 0.9)), 1.0));
+    objects.add(Box::new(Sphere::new(
+        Point3::new(0.0, 150.0, 145.0),
+        50.0,
+        metal as Arc<dyn Material>,
+    )));
+
+    let boundary = Arc::new(Sphere::new(
+        Point3::new(360.0, 150.0, 145.0),
+        70.0,
+        Arc::clone(&dielectric) as Arc<dyn Material>,
+    ));
+    objects.add(Box::new(Arc::clone(&boundary)));
+    objects.add(Box::new(ConstantMedium::new(
+        boundary,
+        SolidColor::from(ColorRGBMapTo0_1::new(0.2, 0.4, 0.9)),
+        0.2,
+    )));
+    objects.add(Box::new(ConstantMedium::new(
+        Sphere::new(
+            Point3::new(0.0, 0.0, 0.0),
+            5000.0,
+            Arc::clone(&dielectric) as Arc<dyn Material>,
+        ),
+        SolidColor::from(ColorRGBMapTo0_1::new(1.0, 1.0, 1.0)),
+        0.0001,
+    )));
+
+    const EARTH_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/images/earthmap.jpg");
+    let emat: Arc<Lambertian<ImageTexture>> = Arc::new(Lambertian::new(
+        ImageTexture::new(EARTH_PATH)
+            .unwrap_or_else(|err| panic!("can not read the image {EARTH_PATH}: {err}")),
+    ));
+    objects.add(Box::new(Sphere::new(
+        Point3::new(400.0, 200.0, 400.0),
+        100.0,
+        emat as Arc<dyn Material>,
+    )));
+
+    let pertext: NoiseTexture<Perlin> = NoiseTexture::new(Perlin::new(seed)).set_scale(0.1);
+    objects.add(Box::new(Sphere::new(
+        Point3::new(220.0, 280.0, 300.0),
+        80.0,
+        Arc::new(Lambertian::new(pertext)) as Arc<dyn Material>,
+    )));
+
+    const CAPACITY: usize = 1000;
+    let mut boxes2: HittableList<Box<dyn Hittable>> = HittableList::with_capacity(CAPACITY);
+    let white: Arc<Lambertian<SolidColor>> = Arc::new(Lambertian::new(SolidColor::from(
+        ColorRGBMapTo0_1::new(0.73, 0.73, 0.73),
+    )));
+    (0..CAPACITY).for_each(|_| {
+        boxes2.add(Box::new(Sphere::new(
+            Point3::new(
+                rng.gen_range(0.0..165.0),
+                rng.gen_range(0.0..165.0),
+                rng.gen_range(0.0..165.0),
+            ),
+            10.0,
+            Arc::clone(&white) as Arc<dyn Material>,
+        )))
+    });
+
+    objects.add(Box::new(Instance::Translate {
+        prototype: Box::new(Instance::RotateY {
+            prototype: Box::new(boxes2) as Box<dyn Hittable>,
+            radians: 15_f32.to_radians(),
+        }) as Box<dyn Hittable>,
+        displacement: Vec3::new(-100.0, 270.0, 395.0),
+    }));
+
+    let camera: Camera = Camera::builder()
+        .look_from(Point3::new(478.0, 278.0, -600.0))
+        .look_at(Point3::new(278.0, 278.0, 0.0))
+        .up(Vec3::new(0.0, 1.0, 0.0))
+        .fov(40.0)
+        .aspect_ratio(aspect_ratio)
+        .aperture(0.1)
+        .focus_dist(10.0)
+        .time_0(0.0)
+        .time_1(1.0)
+        .build();
+
+    Scene::new(
+        objects,
+        camera,
+        Background::Color(ColorRGBMapTo0_1::new(0.0, 0.0, 0.0)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::Independent;
+
+    const SCENE: &str = "
+        # A lit sphere on a ground.
+        camera 0 1 -5  0 1 0  40
+        background 0.1 0.1 0.1
+        sphere 0 -100 0  100  lambertian 0.5 0.5 0.5
+        sphere 0 1 0  1  light 1 0.5 0.25  2   # glows
+    ";
+
+    #[test]
+    fn read_should_build_the_scene_of_the_file() {
+        let scene: Scene = Scene::read(SCENE.as_bytes(), 1.0).unwrap();
+        assert_eq!(
+            Background::Color(ColorRGBMapTo0_1::new(0.1, 0.1, 0.1)),
+            scene.background
+        );
+
+        // The middle of the image looks straight at the light, wherever in the
+        // pixel the sample falls.
+        let color: ColorRGBMapTo0_1 = scene.sample(4, 4, 9, 9, &mut Independent::new().set_seed(1));
+        assert_eq!((2.0, 1.0, 0.5), (color.r(), color.g(), color.b()));
+    }
+
+    #[test]
+    fn read_should_report_the_line_of_an_error() {
+        let error: io::Error = Scene::read(
+            "camera 0 0 0  0 0 1  40\nsphere 0 0 0 1 chrome\n".as_bytes(),
+            1.0,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        assert!(error.to_string().starts_with("line 2:"), "{error}");
+
+        assert!(Scene::read("sphere 0 0 0 1 dielectric 1.5".as_bytes(), 1.0).is_err());
+    }
+
+    #[test]
+    fn builtin_scenes_should_load_by_name() {
+        Scene::BUILTIN
+            .iter()
+            .for_each(|name| assert!(SceneSource::Builtin(name.to_string()).load(1.5).is_ok()));
+        assert_eq!(
+            io::ErrorKind::NotFound,
+            SceneSource::Builtin("teapot".to_string())
+                .load(1.5)
+                .err()
+                .unwrap()
+                .kind()
+        );
+    }
+}
@@ -0,0 +1,298 @@
+use in_one_weekend::{point::Point3, vec3::Vec3};
+
+/// Signed distance to a surface: negative inside, positive outside.
+///
+/// Sphere tracing relies on the distance never being overestimated. Fields
+/// that only bound it loosely, such as `Twist`, need smaller steps, see
+/// `DistanceField::set_step_scale`.
+pub trait Sdf: Send + Sync {
+    fn distance(&self, p: Point3) -> f32;
+}
+
+impl<F: Fn(Point3) -> f32 + Send + Sync> Sdf for F {
+    fn distance(&self, p: Point3) -> f32 {
+        self(p)
+    }
+}
+
+impl Sdf for Box<dyn Sdf> {
+    fn distance(&self, p: Point3) -> f32 {
+        self.as_ref().distance(p)
+    }
+}
+
+/// A sphere around the origin.
+pub struct Sphere {
+    radius: f32,
+}
+
+impl Sphere {
+    pub fn new(radius: f32) -> Self {
+        Self { radius }
+    }
+}
+
+impl Sdf for Sphere {
+    fn distance(&self, p: Point3) -> f32 {
+        p.len() - self.radius
+    }
+}
+
+/// A box around the origin whose edges are rounded off by `radius`, which is
+/// included in `half_extents`.
+pub struct RoundBox {
+    half_extents: Vec3,
+    radius: f32,
+}
+
+impl RoundBox {
+    pub fn new(half_extents: Vec3, radius: f32) -> Self {
+        debug_assert!((0..3).all(|axis| radius <= half_extents[axis]));
+        Self {
+            half_extents,
+            radius,
+        }
+    }
+}
+
+impl Sdf for RoundBox {
+    fn distance(&self, p: Point3) -> f32 {
+        let inner: Vec3 = self.half_extents - self.radius;
+        let q: Vec3 = Vec3::new(
+            p.x().abs() - inner.x(),
+            p.y().abs() - inner.y(),
+            p.z().abs() - inner.z(),
+        );
+        let outside: Vec3 = Vec3::new(q.x().max(0.0), q.y().max(0.0), q.z().max(0.0));
+
+        outside.len() + q.x().max(q.y()).max(q.z()).min(0.0) - self.radius
+    }
+}
+
+/// A torus around the Y axis through the origin.
+pub struct Torus {
+    major_radius: f32,
+    minor_radius: f32,
+}
+
+impl Torus {
+    pub fn new(major_radius: f32, minor_radius: f32) -> Self {
+        Self {
+            major_radius,
+            minor_radius,
+        }
+    }
+}
+
+impl Sdf for Torus {
+    fn distance(&self, p: Point3) -> f32 {
+        let radial: f32 = (p.x() * p.x() + p.z() * p.z()).sqrt() - self.major_radius;
+        (radial * radial + p.y() * p.y()).sqrt() - self.minor_radius
+    }
+}
+
+/// The Mandelbulb fractal around the origin, within a radius of about 1.2
+/// for the classic power of 8.
+pub struct Mandelbulb {
+    power: f32,
+    iterations: u32,
+}
+
+impl Mandelbulb {
+    pub fn new(power: f32, iterations: u32) -> Self {
+        Self { power, iterations }
+    }
+}
+
+impl Default for Mandelbulb {
+    fn default() -> Self {
+        Self::new(8.0, 12)
+    }
+}
+
+impl Sdf for Mandelbulb {
+    /// The distance estimate `0.5 ln(r) r / dr` of the escaping orbit.
+    fn distance(&self, p: Point3) -> f32 {
+        const BAILOUT: f32 = 2.0;
+
+        let mut z: Vec3 = p;
+        let mut dr: f32 = 1.0;
+        let mut r: f32 = z.len();
+        for _ in 0..self.iterations {
+            if BAILOUT < r {
+                break;
+            }
+
+            // Raise to the power in spherical coordinates and add `p` back.
+            dr = r.powf(self.power - 1.0) * self.power * dr + 1.0;
+            z = match r {
+                0.0 => p,
+                r => {
+                    let theta: f32 = (z.z() / r).acos() * self.power;
+                    let phi: f32 = z.y().atan2(z.x()) * self.power;
+                    r.powf(self.power)
+                        * Vec3::new(
+                            theta.sin() * phi.cos(),
+                            theta.sin() * phi.sin(),
+                            theta.cos(),
+                        )
+                        + p
+                }
+            };
+            r = z.len();
+        }
+
+        match r {
+            0.0 => 0.0,
+            r => 0.5 * r.ln() * r / dr,
+        }
+    }
+}
+
+/// `sdf` moved by `offset`.
+pub struct Translate<S: Sdf> {
+    sdf: S,
+    offset: Vec3,
+}
+
+impl<S: Sdf> Translate<S> {
+    pub fn new(sdf: S, offset: Vec3) -> Self {
+        Self { sdf, offset }
+    }
+}
+
+impl<S: Sdf> Sdf for Translate<S> {
+    fn distance(&self, p: Point3) -> f32 {
+        self.sdf.distance(p - self.offset)
+    }
+}
+
+/// The union of two fields, blended over a distance of about `k` where they
+/// meet.
+pub struct SmoothUnion<A: Sdf, B: Sdf> {
+    a: A,
+    b: B,
+    k: f32,
+}
+
+impl<A: Sdf, B: Sdf> SmoothUnion<A, B> {
+    pub fn new(a: A, b: B, k: f32) -> Self {
+        debug_assert!(0.0 < k);
+        Self { a, b, k }
+    }
+}
+
+impl<A: Sdf, B: Sdf> Sdf for SmoothUnion<A, B> {
+    fn distance(&self, p: Point3) -> f32 {
+        smooth_min(self.a.distance(p), self.b.distance(p), self.k)
+    }
+}
+
+/// Polynomial smooth minimum, equal to `a.min(b)` when they are more than
+/// `k` apart.
+pub fn smooth_min(a: f32, b: f32, k: f32) -> f32 {
+    let h: f32 = (0.5 + 0.5 * (b - a) / k).clamp(0.0, 1.0);
+    b + h * (a - b) - k * h * (1.0 - h)
+}
+
+/// `sdf` repeated forever on a grid with the given cell size per axis, where a
+/// zero size leaves that axis alone. `sdf` should fit in the cell around the
+/// origin.
+pub struct Repeat<S: Sdf> {
+    sdf: S,
+    period: Vec3,
+}
+
+impl<S: Sdf> Repeat<S> {
+    pub fn new(sdf: S, period: Vec3) -> Self {
+        Self { sdf, period }
+    }
+}
+
+impl<S: Sdf> Sdf for Repeat<S> {
+    fn distance(&self, p: Point3) -> f32 {
+        let wrap = |x: f32, period: f32| match period {
+            0.0 => x,
+            period => x - period * (x / period).round(),
+        };
+
+        self.sdf.distance(Point3::new(
+            wrap(p.x(), self.period.x()),
+            wrap(p.y(), self.period.y()),
+            wrap(p.z(), self.period.z()),
+        ))
+    }
+}
+
+/// `sdf` twisted around the Y axis by `rate` radians per unit of height.
+///
+/// Twisting stretches distances, so trace it with a step scale of about
+/// `1 / sqrt(1 + (rate * r)²)`, where `r` is the largest distance of the shape
+/// from the axis.
+pub struct Twist<S: Sdf> {
+    sdf: S,
+    rate: f32,
+}
+
+impl<S: Sdf> Twist<S> {
+    pub fn new(sdf: S, rate: f32) -> Self {
+        Self { sdf, rate }
+    }
+}
+
+impl<S: Sdf> Sdf for Twist<S> {
+    fn distance(&self, p: Point3) -> f32 {
+        let (sin, cos): (f32, f32) = (-self.rate * p.y()).sin_cos();
+
+        self.sdf.distance(Point3::new(
+            cos * p.x() - sin * p.z(),
+            p.y(),
+            sin * p.x() + cos * p.z(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_1_SQRT_2, PI};
+
+    use super::*;
+
+    fn round_box_at(p: Point3) -> f32 {
+        RoundBox::new(Vec3::new(1.0, 2.0, 0.2), 0.1).distance(p)
+    }
+
+    #[test]
+    fn combinators_should_transform_distances() {
+        let round_box = RoundBox::new(Vec3::new(1.0, 2.0, 1.0), 0.25);
+        assert!((round_box.distance(Point3::new(3.0, 0.0, 0.0)) - 2.0).abs() < 1e-6);
+        assert!((round_box.distance(Point3::new(0.0, 0.0, 0.0)) + 1.0).abs() < 1e-6);
+        let corner: f32 = round_box.distance(Point3::new(1.0, 2.0, 1.0));
+        assert!((corner - (3_f32.sqrt() * 0.25 - 0.25)).abs() < 1e-6);
+
+        // Far from the seam a smooth union is a plain union, and near it the
+        // shapes bulge out.
+        let union = SmoothUnion::new(
+            Translate::new(Sphere::new(1.0), Vec3::new(-1.0, 0.0, 0.0)),
+            Translate::new(Sphere::new(1.0), Vec3::new(1.0, 0.0, 0.0)),
+            0.5,
+        );
+        assert!((union.distance(Point3::new(-3.0, 0.0, 0.0)) - 1.0).abs() < 1e-6);
+        assert!(union.distance(Point3::new(0.0, 0.1, 0.0)) < 0.0);
+
+        let repeated = Repeat::new(Sphere::new(0.5), Vec3::new(2.0, 0.0, 2.0));
+        assert!((repeated.distance(Point3::new(4.0, 0.0, -6.5)) - 0.0).abs() < 1e-6);
+        assert!((repeated.distance(Point3::new(4.0, 3.0, -6.0)) - 2.5).abs() < 1e-6);
+
+        let twisted = Twist::new(RoundBox::new(Vec3::new(1.0, 2.0, 0.2), 0.1), PI / 4.0);
+        let p: Point3 = Point3::new(0.5, 0.0, 0.3);
+        assert_eq!(round_box_at(p), twisted.distance(p));
+        // One unit up the box has turned by 45 degrees.
+        let turned: Point3 = Point3::new(FRAC_1_SQRT_2, 1.0, FRAC_1_SQRT_2);
+        assert!((twisted.distance(turned) - round_box_at(Point3::new(1.0, 1.0, 0.0))).abs() < 1e-5);
+
+        let mandelbulb = Mandelbulb::default();
+        assert!(mandelbulb.distance(Point3::new(0.0, 0.0, 0.0)) <= 0.0);
+        assert!(mandelbulb.distance(Point3::new(3.0, 0.0, 0.0)) > 1.5);
+    }
+}
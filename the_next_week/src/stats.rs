@@ -0,0 +1,479 @@
+use std::{collections::BTreeMap, fmt, time::Duration};
+
+use in_one_weekend::thread_pool::WorkerStats;
+
+#[cfg(feature = "stats")]
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+#[cfg(feature = "stats")]
+use in_one_weekend::thread_pool::ThreadPool;
+
+/// Whether the counters are compiled in, with the `stats` feature. Without
+/// it counting does nothing, so that it costs nothing, and
+/// `RenderStats::collect` finds no threads.
+pub const ENABLED: bool = cfg!(feature = "stats");
+
+/// A ray from the camera, starting a path.
+#[inline]
+pub fn camera_ray() {
+    #[cfg(feature = "stats")]
+    count(|counters| &counters.camera_rays);
+}
+
+/// A ray of a path traced into the world, the camera ray included.
+#[inline]
+pub fn ray() {
+    #[cfg(feature = "stats")]
+    count(|counters| &counters.rays);
+}
+
+/// A ray cast towards a light, to see whether and where it reaches it.
+#[inline]
+pub fn shadow_ray() {
+    #[cfg(feature = "stats")]
+    count(|counters| &counters.shadow_rays);
+}
+
+/// A ray tested against a primitive, such as a sphere or a rectangle.
+#[inline]
+pub fn primitive_test() {
+    #[cfg(feature = "stats")]
+    count(|counters| &counters.primitive_tests);
+}
+
+/// A node of a BVH a ray was tested against.
+#[inline]
+pub fn bvh_node_visit() {
+    #[cfg(feature = "stats")]
+    count(|counters| &counters.bvh_node_visits);
+}
+
+/// A ray that hit a `material`, asked to scatter off it whether or not it
+/// does.
+#[inline]
+pub fn scatter(material: &'static str) {
+    #[cfg(feature = "stats")]
+    COUNTERS.with(|counters| {
+        *counters
+            .scatters
+            .lock()
+            .unwrap()
+            .entry(material)
+            .or_default() += 1
+    });
+    #[cfg(not(feature = "stats"))]
+    let _ = material;
+}
+
+/// The counters of a thread, kept apart so that counting does not contend
+/// with other threads.
+#[cfg(feature = "stats")]
+#[derive(Default)]
+struct Counters {
+    camera_rays: AtomicU64,
+    rays: AtomicU64,
+    shadow_rays: AtomicU64,
+    primitive_tests: AtomicU64,
+    bvh_node_visits: AtomicU64,
+    scatters: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+/// The counters of every thread that has counted anything, in the order they
+/// first did, and kept after the threads exit.
+#[cfg(feature = "stats")]
+static THREADS: Mutex<Vec<Registered>> = Mutex::new(Vec::new());
+
+#[cfg(feature = "stats")]
+struct Registered {
+    name: String,
+    worker: Option<usize>,
+    counters: Arc<Counters>,
+}
+
+#[cfg(feature = "stats")]
+thread_local! {
+    static COUNTERS: Arc<Counters> = {
+        let current: thread::Thread = thread::current();
+        let name: String = current
+            .name()
+            .map_or_else(|| format!("{:?}", current.id()), str::to_string);
+        let counters: Arc<Counters> = Arc::default();
+        THREADS.lock().unwrap().push(Registered {
+            name,
+            worker: ThreadPool::current_worker(),
+            counters: Arc::clone(&counters),
+        });
+        counters
+    };
+}
+
+#[cfg(feature = "stats")]
+#[inline]
+fn count(counter: fn(&Counters) -> &AtomicU64) {
+    COUNTERS.with(|counters| counter(counters).fetch_add(1, Ordering::Relaxed));
+}
+
+/// What a thread has counted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ThreadStats {
+    pub name: String,
+    /// The index of the thread among the workers of the thread pool, if it is
+    /// one of them.
+    pub worker: Option<usize>,
+    /// The time the thread spent running jobs, for the workers of the pool.
+    pub busy: Option<Duration>,
+    pub camera_rays: u64,
+    pub rays: u64,
+    pub shadow_rays: u64,
+    pub primitive_tests: u64,
+    pub bvh_node_visits: u64,
+    /// Scatters by the name of the material.
+    pub scatters: BTreeMap<&'static str, u64>,
+}
+
+impl ThreadStats {
+    /// Rays per path, each path starting with a camera ray.
+    pub fn average_path_length(&self) -> f64 {
+        match self.camera_rays {
+            0 => 0.0,
+            paths => self.rays as f64 / paths as f64,
+        }
+    }
+
+    /// Rays of paths and shadow rays traced per second the thread was busy,
+    /// if that is known.
+    pub fn rays_per_second(&self) -> Option<f64> {
+        self.busy
+            .filter(|busy| !busy.is_zero())
+            .map(|busy| (self.rays + self.shadow_rays) as f64 / busy.as_secs_f64())
+    }
+
+    fn add(&mut self, other: &ThreadStats) {
+        self.camera_rays += other.camera_rays;
+        self.rays += other.rays;
+        self.shadow_rays += other.shadow_rays;
+        self.primitive_tests += other.primitive_tests;
+        self.bvh_node_visits += other.bvh_node_visits;
+        other
+            .scatters
+            .iter()
+            .for_each(|(material, count)| *self.scatters.entry(material).or_default() += count);
+    }
+}
+
+/// The counters of every thread over a render that took `elapsed`, written
+/// out as text with `Display` or as JSON with `to_json`.
+///
+/// Shadow rays are those cast towards an area light by `AreaSample::pdf_value`,
+/// so they stay at zero in renders that do not sample the lights.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    pub elapsed: Duration,
+    pub threads: Vec<ThreadStats>,
+}
+
+impl RenderStats {
+    /// What every thread has counted since the program started, with the
+    /// busy time of the workers of the thread pool from its `workers` stats.
+    pub fn collect(elapsed: Duration, workers: &[WorkerStats]) -> Self {
+        #[cfg(feature = "stats")]
+        let threads: Vec<ThreadStats> = THREADS
+            .lock()
+            .unwrap()
+            .iter()
+            .map(
+                |Registered {
+                     name,
+                     worker,
+                     counters,
+                 }| ThreadStats {
+                    name: name.clone(),
+                    worker: *worker,
+                    busy: worker
+                        .and_then(|worker| workers.get(worker))
+                        .map(|stats| stats.busy),
+                    camera_rays: counters.camera_rays.load(Ordering::Relaxed),
+                    rays: counters.rays.load(Ordering::Relaxed),
+                    shadow_rays: counters.shadow_rays.load(Ordering::Relaxed),
+                    primitive_tests: counters.primitive_tests.load(Ordering::Relaxed),
+                    bvh_node_visits: counters.bvh_node_visits.load(Ordering::Relaxed),
+                    scatters: counters.scatters.lock().unwrap().clone(),
+                },
+            )
+            .collect();
+        #[cfg(not(feature = "stats"))]
+        let threads: Vec<ThreadStats> = {
+            let _ = workers;
+            Vec::new()
+        };
+
+        Self { elapsed, threads }
+    }
+
+    /// The counters of all the threads added up.
+    pub fn total(&self) -> ThreadStats {
+        self.threads.iter().fold(
+            ThreadStats {
+                name: "total".to_string(),
+                ..ThreadStats::default()
+            },
+            |mut total, thread| {
+                total.add(thread);
+                total
+            },
+        )
+    }
+
+    /// The rays a worker of the pool traces per second it is busy, over all
+    /// of them, if any counted.
+    pub fn rays_per_second_per_thread(&self) -> Option<f64> {
+        let (rays, busy): (u64, Duration) = self
+            .threads
+            .iter()
+            .filter_map(|thread| {
+                thread
+                    .busy
+                    .map(|busy| (thread.rays + thread.shadow_rays, busy))
+            })
+            .fold((0, Duration::ZERO), |(rays, busy), thread| {
+                (rays + thread.0, busy + thread.1)
+            });
+        (!busy.is_zero()).then(|| rays as f64 / busy.as_secs_f64())
+    }
+
+    pub fn to_json(&self) -> String {
+        let total: ThreadStats = self.total();
+        let scatters: Vec<String> = total
+            .scatters
+            .iter()
+            .map(|(material, count)| format!("{}: {count}", json_string(material)))
+            .collect();
+        let threads: Vec<String> = self
+            .threads
+            .iter()
+            .map(|thread| {
+                format!(
+                    "{{\"name\": {}, \"rays\": {}, \"shadow_rays\": {}, \"busy_seconds\": {}, \"rays_per_second\": {}}}",
+                    json_string(&thread.name),
+                    thread.rays,
+                    thread.shadow_rays,
+                    json_number(thread.busy.map(|busy| busy.as_secs_f64()), 3),
+                    json_number(thread.rays_per_second(), 1)
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\n  \"elapsed_seconds\": {:.3},\n  \"camera_rays\": {},\n  \"rays\": {},\n  \
+             \"shadow_rays\": {},\n  \"primitive_tests\": {},\n  \"bvh_node_visits\": {},\n  \
+             \"average_path_length\": {:.3},\n  \"rays_per_second_per_thread\": {},\n  \
+             \"scatters\": {{{}}},\n  \"threads\": [\n    {}\n  ]\n}}\n",
+            self.elapsed.as_secs_f64(),
+            total.camera_rays,
+            total.rays,
+            total.shadow_rays,
+            total.primitive_tests,
+            total.bvh_node_visits,
+            total.average_path_length(),
+            json_number(self.rays_per_second_per_thread(), 1),
+            scatters.join(", "),
+            threads.join(",\n    ")
+        )
+    }
+}
+
+impl fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rate = |rate: Option<f64>| rate.map_or("-".to_string(), |rate| format!("{rate:.0}"));
+
+        let total: ThreadStats = self.total();
+        writeln!(f, "Camera rays         {:>16}", total.camera_rays)?;
+        writeln!(f, "Rays                {:>16}", total.rays)?;
+        writeln!(f, "Shadow rays         {:>16}", total.shadow_rays)?;
+        writeln!(f, "Primitive tests     {:>16}", total.primitive_tests)?;
+        writeln!(f, "BVH node visits     {:>16}", total.bvh_node_visits)?;
+        writeln!(
+            f,
+            "Average path length {:>16.3}",
+            total.average_path_length()
+        )?;
+        writeln!(f, "Scatters")?;
+        for (material, count) in &total.scatters {
+            writeln!(f, "  {material:<17} {count:>16}")?;
+        }
+        writeln!(f, "Rays per second busy")?;
+        for thread in &self.threads {
+            writeln!(
+                f,
+                "  {:<17} {:>16}",
+                thread.name,
+                rate(thread.rays_per_second())
+            )?;
+        }
+        write!(
+            f,
+            "  {:<17} {:>16}",
+            "per thread",
+            rate(self.rays_per_second_per_thread())
+        )
+    }
+}
+
+/// `value` with `decimals`, or `null` if there is none.
+fn json_number(value: Option<f64>, decimals: usize) -> String {
+    value.map_or("null".to_string(), |value| format!("{value:.decimals$}"))
+}
+
+fn json_string(value: &str) -> String {
+    let mut json: String = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats() -> RenderStats {
+        RenderStats {
+            elapsed: Duration::from_secs(4),
+            threads: vec![
+                ThreadStats {
+                    name: "main".to_string(),
+                    camera_rays: 2,
+                    rays: 6,
+                    scatters: BTreeMap::from([("lambertian", 4)]),
+                    ..ThreadStats::default()
+                },
+                ThreadStats {
+                    name: "\"worker\"".to_string(),
+                    worker: Some(0),
+                    busy: Some(Duration::from_secs(2)),
+                    camera_rays: 2,
+                    rays: 4,
+                    shadow_rays: 2,
+                    primitive_tests: 30,
+                    bvh_node_visits: 12,
+                    scatters: BTreeMap::from([("lambertian", 1), ("metal", 1)]),
+                },
+                ThreadStats {
+                    name: "idle".to_string(),
+                    worker: Some(1),
+                    busy: Some(Duration::ZERO),
+                    ..ThreadStats::default()
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn total_should_add_up_the_threads() {
+        let total: ThreadStats = stats().total();
+        assert_eq!(
+            (4, 10, 2, 30, 12),
+            (
+                total.camera_rays,
+                total.rays,
+                total.shadow_rays,
+                total.primitive_tests,
+                total.bvh_node_visits
+            )
+        );
+        assert_eq!(
+            BTreeMap::from([("lambertian", 5), ("metal", 1)]),
+            total.scatters
+        );
+        assert_eq!(2.5, total.average_path_length());
+    }
+
+    #[test]
+    fn rays_per_second_should_go_by_the_busy_time_of_the_workers() {
+        let stats: RenderStats = stats();
+        assert_eq!(
+            vec![None, Some(3.0), None],
+            stats
+                .threads
+                .iter()
+                .map(ThreadStats::rays_per_second)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some(3.0), stats.rays_per_second_per_thread());
+        assert_eq!(None, RenderStats::default().rays_per_second_per_thread());
+    }
+
+    #[test]
+    fn to_json_should_report_the_totals_and_each_thread() {
+        let json: String = stats().to_json();
+        assert!(json.contains("\"rays\": 10,"), "{json}");
+        assert!(json.contains("\"shadow_rays\": 2,"), "{json}");
+        assert!(json.contains("\"average_path_length\": 2.500,"), "{json}");
+        assert!(
+            json.contains("\"scatters\": {\"lambertian\": 5, \"metal\": 1},"),
+            "{json}"
+        );
+        assert!(
+            json.contains(
+                "{\"name\": \"\\\"worker\\\"\", \"rays\": 4, \"shadow_rays\": 2, \"busy_seconds\": 2.000, \"rays_per_second\": 3.0}"
+            ),
+            "{json}"
+        );
+        assert!(
+            json.contains(
+                "{\"name\": \"main\", \"rays\": 6, \"shadow_rays\": 0, \"busy_seconds\": null, \"rays_per_second\": null}"
+            ),
+            "{json}"
+        );
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn collect_should_find_the_counts_of_each_thread() {
+        thread::Builder::new()
+            .name("stats_test".to_string())
+            .spawn(|| {
+                camera_ray();
+                (0..3).for_each(|_| ray());
+                shadow_ray();
+                scatter("metal");
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+
+        let thread_pool: ThreadPool = ThreadPool::new(1);
+        thread_pool.spawn(ray).join().unwrap();
+
+        let stats: RenderStats = RenderStats::collect(Duration::from_secs(1), &thread_pool.stats());
+        let thread: &ThreadStats = stats
+            .threads
+            .iter()
+            .find(|thread| thread.name == "stats_test")
+            .unwrap();
+        assert_eq!(
+            (1, 3, 1, None),
+            (
+                thread.camera_rays,
+                thread.rays,
+                thread.shadow_rays,
+                thread.busy
+            )
+        );
+        assert_eq!(BTreeMap::from([("metal", 1)]), thread.scatters);
+        assert!(stats.threads.iter().any(|thread| thread.worker == Some(0)));
+    }
+}
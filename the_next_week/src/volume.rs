@@ -0,0 +1,540 @@
+//! Voxel density grids for heterogeneous participating media.
+//!
+//! Grids are read from a small little-endian binary layout:
+//!
+//! | field      | type        | notes                                   |
+//! |------------|-------------|-----------------------------------------|
+//! | magic      | `[u8; 4]`   | `b"RTVG"`                               |
+//! | version    | `u32`       | `1`                                     |
+//! | kind       | `u32`       | `0` dense, `1` sparse                   |
+//! | resolution | `[u32; 3]`  | number of voxels along x, y and z       |
+//!
+//! A dense grid follows with `x * y * z` `f32` densities, x varying fastest,
+//! then y, then z.
+//!
+//! A sparse grid is split, like the leaf nodes of OpenVDB, into blocks of
+//! `8 x 8 x 8` voxels and only stores the blocks that are not entirely
+//! background. It follows with the background density as `f32`, the number of
+//! blocks as `u32`, and for every block its coordinates (voxel index / 8) as
+//! `[u32; 3]` followed by its 512 `f32` densities, x varying fastest.
+//!
+//! Files of grids with more than 2²⁸ voxels are refused as corrupt, and such
+//! grids are not written.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use in_one_weekend::{point::Point3, vec3::Vec3};
+
+use crate::hittable::Aabb;
+
+const MAGIC: &[u8; 4] = b"RTVG";
+const VERSION: u32 = 1;
+const KIND_DENSE: u32 = 0;
+const KIND_SPARSE: u32 = 1;
+
+/// The most voxels a grid read from a file may have, 1 GiB of dense `f32`s,
+/// so that a corrupt header is an error rather than an allocation that
+/// cannot be made.
+const MAX_VOXELS: usize = 1 << 28;
+
+/// Edge length in voxels of the blocks of a sparse grid and of the cells of a
+/// majorant grid.
+pub const BLOCK_SIZE: usize = 8;
+const BLOCK_VOXELS: usize = BLOCK_SIZE * BLOCK_SIZE * BLOCK_SIZE;
+
+enum Storage {
+    Dense(Box<[f32]>),
+    Sparse {
+        blocks: HashMap<[usize; 3], Box<[f32; BLOCK_VOXELS]>>,
+        background: f32,
+    },
+}
+
+/// A grid of densities sampled at voxel centres and placed in the world by its
+/// bounding box.
+///
+/// The box is axis-aligned. To rotate, shear or otherwise transform a grid,
+/// wrap the `GridMedium` made of it in an `Instance::Transform`, which
+/// tracks rays through the grid in its own space.
+pub struct VoxelGrid {
+    resolution: [usize; 3],
+    storage: Storage,
+    bounds: Aabb,
+}
+
+impl VoxelGrid {
+    /// A dense grid of `resolution` voxels filled with `density`, spanning the
+    /// unit cube until placed with `set_bounds`.
+    pub fn dense(resolution: [usize; 3], density: f32) -> Self {
+        assert!(resolution.iter().all(|&n| n > 0));
+
+        Self {
+            resolution,
+            storage: Storage::Dense(vec![density; resolution.iter().product()].into()),
+            bounds: Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0)),
+        }
+    }
+
+    /// A sparse grid of `resolution` voxels filled with `background`, spanning
+    /// the unit cube until placed with `set_bounds`.
+    pub fn sparse(resolution: [usize; 3], background: f32) -> Self {
+        assert!(resolution.iter().all(|&n| n > 0));
+
+        Self {
+            resolution,
+            storage: Storage::Sparse {
+                blocks: HashMap::new(),
+                background,
+            },
+            bounds: Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0)),
+        }
+    }
+
+    /// A dense grid whose voxel `[i, j, k]` holds `f(i, j, k)`.
+    pub fn from_fn<F: Fn(usize, usize, usize) -> f32>(resolution: [usize; 3], f: F) -> Self {
+        let mut grid = Self::dense(resolution, 0.0);
+        (0..resolution[2]).for_each(|k| {
+            (0..resolution[1])
+                .for_each(|j| (0..resolution[0]).for_each(|i| grid.set(i, j, k, f(i, j, k))))
+        });
+
+        grid
+    }
+
+    /// Place the grid in the world so that it fills `bounds`, or the space of an
+    /// `Instance::Transform` wrapping its medium.
+    pub fn set_bounds(mut self, bounds: Aabb) -> Self {
+        self.bounds = bounds;
+
+        self
+    }
+
+    #[inline]
+    pub fn resolution(&self) -> [usize; 3] {
+        self.resolution
+    }
+
+    #[inline]
+    pub fn bounds(&self) -> Aabb {
+        self.bounds
+    }
+
+    pub fn is_sparse(&self) -> bool {
+        matches!(self.storage, Storage::Sparse { .. })
+    }
+
+    /// Extent of one voxel in world units along each axis.
+    pub fn voxel_size(&self) -> Vec3 {
+        let extent: Vec3 = self.bounds.max() - self.bounds.min();
+        Vec3::new(
+            extent.x() / self.resolution[0] as f32,
+            extent.y() / self.resolution[1] as f32,
+            extent.z() / self.resolution[2] as f32,
+        )
+    }
+
+    pub fn voxel(&self, i: usize, j: usize, k: usize) -> f32 {
+        debug_assert!(i < self.resolution[0] && j < self.resolution[1] && k < self.resolution[2]);
+
+        match &self.storage {
+            Storage::Dense(data) => data[self.dense_index(i, j, k)],
+            Storage::Sparse { blocks, background } => blocks
+                .get(&[i / BLOCK_SIZE, j / BLOCK_SIZE, k / BLOCK_SIZE])
+                .map_or(*background, |block| block[Self::block_index(i, j, k)]),
+        }
+    }
+
+    pub fn set(&mut self, i: usize, j: usize, k: usize, density: f32) {
+        assert!(i < self.resolution[0] && j < self.resolution[1] && k < self.resolution[2]);
+
+        let index: usize = self.dense_index(i, j, k);
+        match &mut self.storage {
+            Storage::Dense(data) => data[index] = density,
+            Storage::Sparse { blocks, background } => {
+                let key: [usize; 3] = [i / BLOCK_SIZE, j / BLOCK_SIZE, k / BLOCK_SIZE];
+                match blocks.get_mut(&key) {
+                    Some(block) => block[Self::block_index(i, j, k)] = density,
+                    None if density != *background => {
+                        let mut block = Box::new([*background; BLOCK_VOXELS]);
+                        block[Self::block_index(i, j, k)] = density;
+                        blocks.insert(key, block);
+                    }
+                    None => {}
+                }
+            }
+        }
+    }
+
+    /// Trilinearly interpolated density at a world position, zero outside the
+    /// grid's bounds.
+    pub fn density(&self, p: &Point3) -> f32 {
+        let [x, y, z]: [f32; 3] = self.to_voxel_space(p);
+        if !(0.0..=self.resolution[0] as f32).contains(&x)
+            || !(0.0..=self.resolution[1] as f32).contains(&y)
+            || !(0.0..=self.resolution[2] as f32).contains(&z)
+        {
+            return 0.0;
+        }
+
+        // Densities live at voxel centres.
+        let [x, y, z]: [f32; 3] = [x - 0.5, y - 0.5, z - 0.5];
+        let [i, j, k]: [f32; 3] = [x.floor(), y.floor(), z.floor()];
+        let [u, v, w]: [f32; 3] = [x - i, y - j, z - k];
+        let clamp =
+            |index: f32, axis: usize| (index.max(0.0) as usize).min(self.resolution[axis] - 1);
+
+        (0..2)
+            .flat_map(|di| (0..2).flat_map(move |dj| (0..2).map(move |dk| (di, dj, dk))))
+            .map(|(di, dj, dk)| {
+                let weight: f32 = (if di == 0 { 1.0 - u } else { u })
+                    * (if dj == 0 { 1.0 - v } else { v })
+                    * (if dk == 0 { 1.0 - w } else { w });
+                weight
+                    * self.voxel(
+                        clamp(i + di as f32, 0),
+                        clamp(j + dj as f32, 1),
+                        clamp(k + dk as f32, 2),
+                    )
+            })
+            .sum()
+    }
+
+    /// Continuous voxel coordinates of a world position: voxel `[i, j, k]`
+    /// covers `[i, i + 1) x [j, j + 1) x [k, k + 1)`.
+    pub fn to_voxel_space(&self, p: &Point3) -> [f32; 3] {
+        let voxel_size: Vec3 = self.voxel_size();
+        let local: Vec3 = *p - self.bounds.min();
+
+        [
+            local.x() / voxel_size.x(),
+            local.y() / voxel_size.y(),
+            local.z() / voxel_size.z(),
+        ]
+    }
+
+    /// Read a grid in the layout documented at the top of this module. The grid
+    /// spans the unit cube until placed with `set_bounds`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a voxel grid file"));
+        }
+        if read_u32(&mut reader)? != VERSION {
+            return Err(invalid_data("unsupported voxel grid version"));
+        }
+        let kind: u32 = read_u32(&mut reader)?;
+        let resolution: [usize; 3] = [
+            read_u32(&mut reader)? as usize,
+            read_u32(&mut reader)? as usize,
+            read_u32(&mut reader)? as usize,
+        ];
+        if resolution.contains(&0) {
+            return Err(invalid_data("voxel grid resolution must not be zero"));
+        }
+        check_voxel_count(resolution).map_err(|message| invalid_data(&message))?;
+
+        match kind {
+            KIND_DENSE => {
+                let mut grid = Self::dense(resolution, 0.0);
+                if let Storage::Dense(data) = &mut grid.storage {
+                    data.iter_mut()
+                        .try_for_each(|density| read_f32(&mut reader).map(|v| *density = v))?;
+                }
+                Ok(grid)
+            }
+            KIND_SPARSE => {
+                let background: f32 = read_f32(&mut reader)?;
+                let block_count: u32 = read_u32(&mut reader)?;
+                let mut grid = Self::sparse(resolution, background);
+                let blocks_per_axis: [usize; 3] = resolution.map(|n| n.div_ceil(BLOCK_SIZE));
+                if let Storage::Sparse { blocks, .. } = &mut grid.storage {
+                    (0..block_count).try_for_each(|_| {
+                        let coords: [usize; 3] = [
+                            read_u32(&mut reader)? as usize,
+                            read_u32(&mut reader)? as usize,
+                            read_u32(&mut reader)? as usize,
+                        ];
+                        if (0..3).any(|axis| coords[axis] >= blocks_per_axis[axis]) {
+                            return Err(invalid_data("voxel grid block out of range"));
+                        }
+                        let mut block = Box::new([0.0; BLOCK_VOXELS]);
+                        block
+                            .iter_mut()
+                            .try_for_each(|density| read_f32(&mut reader).map(|v| *density = v))?;
+                        blocks.insert(coords, block);
+                        Ok(())
+                    })?;
+                }
+                Ok(grid)
+            }
+            _ => Err(invalid_data("unknown voxel grid kind")),
+        }
+    }
+
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+
+        writer.flush()
+    }
+
+    /// Write the grid in the layout documented at the top of this module.
+    /// Grids over the voxels `read` accepts are refused with
+    /// `ErrorKind::InvalidInput` before anything is written.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        check_voxel_count(self.resolution)
+            .map_err(|message| io::Error::new(io::ErrorKind::InvalidInput, message))?;
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        match &self.storage {
+            Storage::Dense(_) => writer.write_all(&KIND_DENSE.to_le_bytes())?,
+            Storage::Sparse { .. } => writer.write_all(&KIND_SPARSE.to_le_bytes())?,
+        }
+        self.resolution
+            .iter()
+            .try_for_each(|&n| write_u32(writer, n))?;
+
+        match &self.storage {
+            Storage::Dense(data) => data
+                .iter()
+                .try_for_each(|density| writer.write_all(&density.to_le_bytes())),
+            Storage::Sparse { blocks, background } => {
+                writer.write_all(&background.to_le_bytes())?;
+                write_u32(writer, blocks.len())?;
+                blocks.iter().try_for_each(|(coords, block)| {
+                    coords.iter().try_for_each(|&c| write_u32(writer, c))?;
+                    block
+                        .iter()
+                        .try_for_each(|density| writer.write_all(&density.to_le_bytes()))
+                })
+            }
+        }
+    }
+
+    /// Largest density of every block of `BLOCK_SIZE` voxels per axis.
+    fn block_maxima(&self) -> ([usize; 3], Box<[f32]>) {
+        let blocks_per_axis: [usize; 3] = self.resolution.map(|n| n.div_ceil(BLOCK_SIZE));
+        let index =
+            |[bi, bj, bk]: [usize; 3]| (bk * blocks_per_axis[1] + bj) * blocks_per_axis[0] + bi;
+        let mut maxima: Box<[f32]> =
+            vec![f32::NEG_INFINITY; blocks_per_axis.iter().product()].into();
+
+        match &self.storage {
+            Storage::Dense(data) => {
+                (0..self.resolution[2]).for_each(|k| {
+                    (0..self.resolution[1]).for_each(|j| {
+                        (0..self.resolution[0]).for_each(|i| {
+                            let block = &mut maxima[index([i, j, k].map(|v| v / BLOCK_SIZE))];
+                            *block = block.max(data[self.dense_index(i, j, k)]);
+                        })
+                    })
+                });
+            }
+            Storage::Sparse { blocks, background } => {
+                maxima.iter_mut().for_each(|max| *max = *background);
+                blocks.iter().for_each(|(coords, block)| {
+                    maxima[index(*coords)] =
+                        block.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+                });
+            }
+        }
+
+        (blocks_per_axis, maxima)
+    }
+
+    #[inline]
+    fn dense_index(&self, i: usize, j: usize, k: usize) -> usize {
+        (k * self.resolution[1] + j) * self.resolution[0] + i
+    }
+
+    #[inline]
+    fn block_index(i: usize, j: usize, k: usize) -> usize {
+        ((k % BLOCK_SIZE) * BLOCK_SIZE + j % BLOCK_SIZE) * BLOCK_SIZE + i % BLOCK_SIZE
+    }
+}
+
+/// A coarse grid of upper bounds of a `VoxelGrid`'s interpolated density, one
+/// per block of `BLOCK_SIZE` voxels per axis, used to take long free-flight
+/// steps through thin regions of a medium.
+pub struct MajorantGrid {
+    resolution: [usize; 3],
+    majorants: Box<[f32]>,
+}
+
+impl MajorantGrid {
+    pub fn new(grid: &VoxelGrid) -> Self {
+        let (resolution, maxima) = grid.block_maxima();
+        let index = |[i, j, k]: [usize; 3]| (k * resolution[1] + j) * resolution[0] + i;
+
+        // Trilinear interpolation near a block's faces reads voxels of the
+        // neighbouring blocks, so every cell bounds its 3x3x3 neighbourhood.
+        let mut majorants: Box<[f32]> = vec![0.0; maxima.len()].into();
+        (0..resolution[2]).for_each(|k| {
+            (0..resolution[1]).for_each(|j| {
+                (0..resolution[0]).for_each(|i| {
+                    let neighbours = |c: usize, axis: usize| {
+                        c.saturating_sub(1)..=(c + 1).min(resolution[axis] - 1)
+                    };
+                    majorants[index([i, j, k])] = neighbours(k, 2)
+                        .flat_map(|nk| {
+                            neighbours(j, 1).flat_map(move |nj| {
+                                neighbours(i, 0).map(move |ni| index([ni, nj, nk]))
+                            })
+                        })
+                        .map(|n| maxima[n])
+                        .fold(0.0, f32::max);
+                })
+            })
+        });
+
+        Self {
+            resolution,
+            majorants,
+        }
+    }
+
+    #[inline]
+    pub fn resolution(&self) -> [usize; 3] {
+        self.resolution
+    }
+
+    /// Upper bound of the density inside the cell `[i, j, k]`, which covers the
+    /// voxels `[i, j, k] * BLOCK_SIZE` up to, excluding, `[i + 1, j + 1, k + 1] * BLOCK_SIZE`.
+    #[inline]
+    pub fn majorant(&self, i: usize, j: usize, k: usize) -> f32 {
+        self.majorants[(k * self.resolution[1] + j) * self.resolution[0] + i]
+    }
+}
+
+/// An error message if a grid of `resolution` has more voxels than
+/// `MAX_VOXELS`, which also keeps every count of the file within a `u32`.
+fn check_voxel_count(resolution: [usize; 3]) -> Result<(), String> {
+    let voxels: Option<usize> = resolution
+        .iter()
+        .try_fold(1_usize, |voxels, &n| voxels.checked_mul(n));
+    match voxels.is_none_or(|voxels| voxels > MAX_VOXELS) {
+        true => Err(format!(
+            "voxel grid resolution {resolution:?} is over {MAX_VOXELS} voxels"
+        )),
+        false => Ok(()),
+    }
+}
+
+fn write_u32<W: Write>(writer: &mut W, value: usize) -> io::Result<()> {
+    let value: u32 = u32::try_from(value).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{value} does not fit a voxel grid file"),
+        )
+    })?;
+
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_f32<R: Read>(reader: &mut R) -> io::Result<f32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(f32::from_le_bytes(bytes))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_should_round_trip_through_both_layouts() {
+        let dense = VoxelGrid::from_fn([5, 9, 3], |i, j, k| (i + 10 * j + 100 * k) as f32);
+        let mut sparse = VoxelGrid::sparse([20, 9, 17], 0.5);
+        sparse.set(19, 8, 16, 3.0);
+        sparse.set(2, 1, 0, 1.0);
+
+        for grid in [dense, sparse] {
+            let mut bytes: Vec<u8> = Vec::new();
+            grid.write(&mut bytes).unwrap();
+            let read: VoxelGrid = VoxelGrid::read(bytes.as_slice()).unwrap();
+
+            assert_eq!(grid.resolution(), read.resolution());
+            assert_eq!(grid.is_sparse(), read.is_sparse());
+            let [nx, ny, nz] = grid.resolution();
+            (0..nz).for_each(|k| {
+                (0..ny).for_each(|j| {
+                    (0..nx).for_each(|i| assert_eq!(grid.voxel(i, j, k), read.voxel(i, j, k)))
+                })
+            });
+        }
+
+        assert!(VoxelGrid::read(&b"RTVX"[..]).is_err());
+    }
+
+    #[test]
+    fn read_should_reject_a_huge_resolution_before_allocating() {
+        for resolution in [[u32::MAX; 3], [1 << 10, 1 << 10, 1 << 9]] {
+            let mut bytes: Vec<u8> = MAGIC.to_vec();
+            [VERSION, KIND_DENSE]
+                .into_iter()
+                .chain(resolution)
+                .for_each(|value| bytes.extend(value.to_le_bytes()));
+
+            let error: io::Error = VoxelGrid::read(bytes.as_slice()).err().unwrap();
+            assert_eq!(io::ErrorKind::InvalidData, error.kind(), "{error}");
+        }
+    }
+
+    #[test]
+    fn write_should_refuse_a_grid_read_would_reject() {
+        // Sparse, so that the grid itself takes no memory.
+        let grid = VoxelGrid::sparse([1 << 10, 1 << 10, 1 << 9], 0.0);
+
+        let mut bytes: Vec<u8> = Vec::new();
+        let error: io::Error = grid.write(&mut bytes).err().unwrap();
+        assert_eq!(io::ErrorKind::InvalidInput, error.kind(), "{error}");
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn density_should_interpolate_and_be_bounded_by_majorants() {
+        let grid = VoxelGrid::from_fn([20, 4, 4], |i, _, _| i as f32).set_bounds(Aabb::new(
+            Point3::new(-10.0, 0.0, 0.0),
+            Point3::new(10.0, 4.0, 4.0),
+        ));
+
+        // Voxel centres sit at x = -9.5, -8.5, ..., so x = -4 is halfway
+        // between the voxels 5 and 6.
+        assert!((grid.density(&Point3::new(-4.0, 2.0, 2.0)) - 5.5).abs() < 1e-5);
+        assert_eq!(0.0, grid.density(&Point3::new(-11.0, 2.0, 2.0)));
+
+        let majorants = MajorantGrid::new(&grid);
+        assert_eq!([3, 1, 1], majorants.resolution());
+        assert_eq!(15.0, majorants.majorant(0, 0, 0));
+        assert_eq!(19.0, majorants.majorant(2, 0, 0));
+        (0..2000).for_each(|n| {
+            let p = Point3::new(-10.0 + n as f32 * 0.01, 1.0, 3.0);
+            let [x, _, _] = grid.to_voxel_space(&p);
+            let cell: usize = (x as usize / BLOCK_SIZE).min(2);
+            assert!(grid.density(&p) <= majorants.majorant(cell, 0, 0) + 1e-4);
+        });
+    }
+}
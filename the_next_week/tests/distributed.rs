@@ -0,0 +1,149 @@
+//! Renders across worker processes of the `distributed` example on localhost.
+
+use std::{
+    io::{self, Read, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use the_next_week::{
+    distributed::{Coordinator, RenderJob},
+    render::{Framebuffer, PixelEstimate, SampleBudget, TileOrder, TileScheduler},
+    sampler::{Sampler, Sobol},
+    scene::{Scene, SceneSource},
+};
+
+const SCENE: &str = "
+    camera 0 1 -5  0 1 0  40
+    background sky
+    sphere 0 -100 0  100  lambertian 0.5 0.5 0.5
+    sphere 0 1 0  1  metal 0.7 0.6 0.5  0.2
+    sphere 1.5 0.5 -1  0.5  dielectric 1.5
+";
+
+const WIDTH: usize = 16;
+const HEIGHT: usize = 12;
+
+/// The bytes of the `Hello` a worker starts with: its tag, the magic bytes
+/// and the number of threads.
+const HELLO_LEN: usize = 1 + 8 + 8;
+
+/// The `distributed` example, built for the profile of this test.
+fn distributed_example() -> PathBuf {
+    let status = Command::new(env!("CARGO"))
+        .args(["build", "--quiet", "--example", "distributed"])
+        .args((!cfg!(debug_assertions)).then_some("--release"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // The test runs from `target/<profile>/deps`.
+    let mut path: PathBuf = std::env::current_exe().unwrap();
+    path.pop();
+    path.pop();
+    path.push("examples");
+    path.push(format!("distributed{}", std::env::consts::EXE_SUFFIX));
+    path
+}
+
+fn spawn_worker(example: &PathBuf, address: SocketAddr) -> Child {
+    Command::new(example)
+        .args(["worker", &address.to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap()
+}
+
+/// Stand between a worker and `coordinator`, passing on the `Hello` of the
+/// worker and everything the coordinator sends, but none of the tiles the
+/// worker sends back, so that it can not finish the render. Sends on `assigned`
+/// once the coordinator has answered.
+fn swallow_results(
+    listener: TcpListener,
+    coordinator: SocketAddr,
+    assigned: Sender<()>,
+) -> io::Result<()> {
+    let (mut worker, _) = listener.accept()?;
+    let mut upstream: TcpStream = TcpStream::connect(coordinator)?;
+
+    let mut hello: [u8; HELLO_LEN] = [0; HELLO_LEN];
+    worker.read_exact(&mut hello)?;
+    upstream.write_all(&hello)?;
+
+    let mut downstream: (TcpStream, TcpStream) = (upstream.try_clone()?, worker.try_clone()?);
+    thread::spawn(move || {
+        let mut buffer: [u8; 4096] = [0; 4096];
+        if let Ok(len @ 1..) = downstream.0.read(&mut buffer) {
+            let _ = downstream.1.write_all(&buffer[..len]);
+            let _ = assigned.send(());
+            let _ = io::copy(&mut downstream.0, &mut downstream.1);
+        }
+    });
+
+    // Until the worker is gone, and then hang up on the coordinator.
+    let _ = io::copy(&mut worker, &mut io::sink());
+    upstream.shutdown(Shutdown::Both)
+}
+
+fn assert_matches_local_render(job: &RenderJob, framebuffer: &Framebuffer<WIDTH, HEIGHT>) {
+    let scene: Scene = job.scene.load(WIDTH as f32 / HEIGHT as f32).unwrap();
+    let mut sampler: Sobol = Sobol::new().set_seed(job.seed);
+    for row in 0..HEIGHT {
+        for column in 0..WIDTH {
+            let expected: PixelEstimate =
+                SampleBudget::uniform(job.samples_per_pixel).sample(|index| {
+                    sampler.start_pixel_sample(column, row, index);
+                    scene.sample(row, column, WIDTH, HEIGHT, &mut sampler)
+                });
+            assert_eq!(&expected, framebuffer.pixel(row, column), "{row} {column}");
+        }
+    }
+}
+
+#[test]
+fn render_should_match_a_local_render_when_a_worker_process_is_killed() {
+    let example: PathBuf = distributed_example();
+    let job: RenderJob = RenderJob {
+        scene: SceneSource::Text(SCENE.to_string()),
+        samples_per_pixel: 16,
+        seed: 3,
+    };
+
+    let coordinator: Coordinator = Coordinator::bind("127.0.0.1:0").unwrap();
+    let address: SocketAddr = coordinator.local_addr().unwrap();
+    let render = {
+        let job: RenderJob = job.clone();
+        thread::spawn(move || {
+            coordinator.render::<WIDTH, HEIGHT>(&job, &TileScheduler::new(2, TileOrder::Hilbert))
+        })
+    };
+
+    // A worker that is killed while it holds tiles it has not sent back.
+    let proxy: TcpListener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let proxy_address: SocketAddr = proxy.local_addr().unwrap();
+    let (assigned, is_assigned): (Sender<()>, Receiver<()>) = mpsc::channel();
+    let proxy = thread::spawn(move || swallow_results(proxy, address, assigned));
+    let mut victim: Child = spawn_worker(&example, proxy_address);
+    is_assigned.recv().unwrap();
+    assert!(victim.try_wait().unwrap().is_none());
+    victim.kill().unwrap();
+    victim.wait().unwrap();
+    proxy.join().unwrap().unwrap();
+
+    let mut workers: Vec<Child> = (0..2).map(|_| spawn_worker(&example, address)).collect();
+    let framebuffer: Framebuffer<WIDTH, HEIGHT> = render.join().unwrap().unwrap();
+    // A worker may only connect once every tile is done and be turned away.
+    let finished: usize = workers
+        .iter_mut()
+        .map(|worker| worker.wait().unwrap())
+        .filter(|status| status.success())
+        .count();
+    assert!(finished >= 1);
+
+    assert_matches_local_render(&job, &framebuffer);
+}